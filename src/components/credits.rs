@@ -0,0 +1,145 @@
+use egui::Ui;
+
+use crate::util::capabilities::Capability;
+
+/// A bundled library, what it's used for and the license it's distributed under.
+struct Credit {
+    name: &'static str,
+    purpose: &'static str,
+    license: &'static str,
+    url: &'static str,
+}
+
+/// Libraries doing the heavy lifting, native codecs first.
+const CREDITS: &[Credit] = &[
+    Credit {
+        name: "libwebp",
+        purpose: "WebP encoding",
+        license: "BSD-3-Clause",
+        url: "https://chromium.googlesource.com/webm/libwebp",
+    },
+    Credit {
+        name: "rav1e",
+        purpose: "AVIF encoding",
+        license: "BSD-2-Clause",
+        url: "https://github.com/xiph/rav1e",
+    },
+    Credit {
+        name: "libimagequant",
+        purpose: "Palette quantization",
+        license: "GPL-3.0-or-later",
+        url: "https://github.com/ImageOptim/libimagequant",
+    },
+    Credit {
+        name: "SQLite",
+        purpose: "Conversion catalog",
+        license: "Public domain",
+        url: "https://sqlite.org",
+    },
+    #[cfg(feature = "heif")]
+    Credit {
+        name: "libheif",
+        purpose: "HEIC and HEIF decoding",
+        license: "LGPL-3.0",
+        url: "https://github.com/strukturag/libheif",
+    },
+    #[cfg(feature = "pdf")]
+    Credit {
+        name: "PDFium",
+        purpose: "PDF rendering",
+        license: "BSD-3-Clause",
+        url: "https://pdfium.googlesource.com/pdfium",
+    },
+    #[cfg(feature = "jxl-output")]
+    Credit {
+        name: "libjxl",
+        purpose: "JPEG XL encoding",
+        license: "BSD-3-Clause",
+        url: "https://github.com/libjxl/libjxl",
+    },
+    #[cfg(feature = "raw")]
+    Credit {
+        name: "imagepipe",
+        purpose: "Camera RAW decoding",
+        license: "LGPL-3.0",
+        url: "https://github.com/pedrocr/imagepipe",
+    },
+    Credit {
+        name: "image",
+        purpose: "Decoding and encoding of most formats",
+        license: "MIT or Apache-2.0",
+        url: "https://github.com/image-rs/image",
+    },
+    Credit {
+        name: "jxl-oxide",
+        purpose: "JPEG XL decoding",
+        license: "MIT or Apache-2.0",
+        url: "https://github.com/tirr-c/jxl-oxide",
+    },
+    Credit {
+        name: "resvg",
+        purpose: "SVG rendering",
+        license: "MIT or Apache-2.0",
+        url: "https://github.com/linebender/resvg",
+    },
+    Credit {
+        name: "egui",
+        purpose: "User interface",
+        license: "MIT or Apache-2.0",
+        url: "https://github.com/emilk/egui",
+    },
+];
+
+/// Optional features this build was compiled with.
+fn features() -> Vec<&'static str> {
+    Capability::ALL
+        .into_iter()
+        .filter(|capability| capability.is_compiled())
+        .map(Capability::feature)
+        .collect()
+}
+
+/// Version and build details, for bug reports.
+pub fn build_info(ui: &mut Ui) {
+    let features = features();
+
+    egui::Grid::new("build_info").num_columns(2).show(ui, |ui| {
+        ui.label("Version");
+        ui.label(env!("CARGO_PKG_VERSION"));
+        ui.end_row();
+
+        ui.label("Commit");
+        ui.label(env!("BUILD_COMMIT"));
+        ui.end_row();
+
+        ui.label("Build");
+        ui.label(format!(
+            "{} ({})",
+            env!("BUILD_TARGET"),
+            env!("BUILD_PROFILE")
+        ));
+        ui.end_row();
+
+        ui.label("Features");
+        ui.label(match features.is_empty() {
+            true => "None".to_string(),
+            false => features.join(", "),
+        });
+        ui.end_row();
+    });
+}
+
+/// Third-party libraries bundled in this build and their licenses.
+pub fn credits(ui: &mut Ui) {
+    egui::Grid::new("credits")
+        .num_columns(3)
+        .striped(true)
+        .show(ui, |ui| {
+            for credit in CREDITS {
+                ui.hyperlink_to(credit.name, credit.url);
+                ui.label(credit.purpose);
+                ui.label(credit.license);
+                ui.end_row();
+            }
+        });
+}
@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use egui::Ui;
+
+use crate::util::pattern::matches;
+
+pub fn file_list<'a>(
+    ui: &mut Ui,
+    id: &str,
+    files: impl ExactSizeIterator<Item = &'a PathBuf>,
+    filter: &str,
+) {
+    let total = files.len();
+    let shown: Vec<&PathBuf> = files
+        .filter(|path| {
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            matches(filter, &file_name)
+        })
+        .collect();
+
+    if shown.len() < total {
+        ui.label(format!("Showing {} of {}", shown.len(), total));
+    }
+
+    egui::ScrollArea::vertical()
+        .id_salt(id)
+        .max_height(120.0)
+        .auto_shrink([false, true])
+        .show_rows(
+            ui,
+            ui.text_style_height(&egui::TextStyle::Body),
+            shown.len(),
+            |ui, range| {
+                for path in &shown[range] {
+                    ui.label(path.display().to_string());
+                }
+            },
+        );
+}
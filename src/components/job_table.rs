@@ -0,0 +1,89 @@
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use egui::Ui;
+use egui_extras::{Column, TableBuilder};
+
+use crate::{
+    structs::update::{JobId, Stage},
+    util::{humanize, pattern::matches},
+};
+
+/// A file of the current run and how far along it is.
+pub struct JobRow {
+    pub path: PathBuf,
+    pub stage: Stage,
+    pub started: Option<Instant>,
+    pub elapsed: Option<Duration>,
+    // Size change, skip reason or error once finished
+    pub result: Option<String>,
+}
+
+impl JobRow {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            stage: Stage::Queued,
+            started: None,
+            elapsed: None,
+            result: None,
+        }
+    }
+}
+
+/// Every job of the run with its stage, time taken so far and result.
+pub fn job_table(ui: &mut Ui, jobs: &BTreeMap<JobId, JobRow>, filter: &str) {
+    let rows: Vec<&JobRow> = jobs
+        .values()
+        .filter(|row| {
+            let file_name = row.path.file_name().unwrap_or_default().to_string_lossy();
+            matches(filter, &file_name)
+        })
+        .collect();
+
+    let row_height = ui.text_style_height(&egui::TextStyle::Body);
+
+    TableBuilder::new(ui)
+        .id_salt("jobs")
+        .striped(true)
+        .max_scroll_height(200.0)
+        .column(Column::initial(240.0).clip(true).resizable(true))
+        .column(Column::exact(70.0))
+        .column(Column::exact(60.0))
+        .column(Column::remainder().clip(true))
+        .header(row_height, |mut header| {
+            for title in ["File", "Stage", "Time", "Result"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(row_height, rows.len(), |mut table_row| {
+                let row = rows[table_row.index()];
+
+                let elapsed = row
+                    .elapsed
+                    .or_else(|| row.started.map(|started| started.elapsed()));
+
+                table_row.col(|ui| {
+                    let file_name = row.path.file_name().unwrap_or_default().to_string_lossy();
+                    ui.label(file_name)
+                        .on_hover_text(row.path.display().to_string());
+                });
+                table_row.col(|ui| {
+                    ui.label(row.stage.to_string());
+                });
+                table_row.col(|ui| {
+                    ui.label(elapsed.map(humanize::duration).unwrap_or_default());
+                });
+                table_row.col(|ui| {
+                    let result = row.result.as_deref().unwrap_or_default();
+                    ui.label(result).on_hover_text(result);
+                });
+            });
+        });
+}
@@ -1 +1,6 @@
-pub mod resize;
\ No newline at end of file
+pub mod credits;
+pub mod file_list;
+pub mod job_table;
+pub mod qr;
+pub mod resize;
+pub mod triage;
@@ -0,0 +1,37 @@
+use egui::{Color32, Rect, Sense, Ui, Vec2};
+use qrcode::{Color, QrCode};
+
+// Light modules around the code scanners need to find it
+const QUIET_ZONE: usize = 2;
+
+/// `text` as a scannable QR code `size` points wide.
+pub fn qr_code(ui: &mut Ui, text: &str, size: f32) {
+    let Ok(code) = QrCode::new(text.as_bytes()) else {
+        ui.label("Address too long for a QR code");
+        return;
+    };
+
+    let (response, painter) = ui.allocate_painter(Vec2::splat(size), Sense::hover());
+    let rect = response.rect;
+    painter.rect_filled(rect, 0.0, Color32::WHITE);
+
+    let width = code.width();
+    let module = size / (width + QUIET_ZONE * 2) as f32;
+
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color != Color::Dark {
+            continue;
+        }
+
+        let (x, y) = (
+            (i % width + QUIET_ZONE) as f32,
+            (i / width + QUIET_ZONE) as f32,
+        );
+        let min = rect.min + Vec2::new(x * module, y * module);
+        painter.rect_filled(
+            Rect::from_min_size(min, Vec2::splat(module)),
+            0.0,
+            Color32::BLACK,
+        );
+    }
+}
@@ -1,6 +1,14 @@
 use egui::Ui;
 
-use crate::structs::settings::{ResizeOptions, Settings};
+use crate::structs::settings::{ResampleFilter, ResizeOptions, Settings};
+
+const FILTERS: [ResampleFilter; 5] = [
+    ResampleFilter::Nearest,
+    ResampleFilter::Triangle,
+    ResampleFilter::CatmullRom,
+    ResampleFilter::Gaussian,
+    ResampleFilter::Lanczos3,
+];
 
 pub fn resize_input(ui: &mut Ui, settings: &mut Settings) {
     egui::ComboBox::from_label("Resize options")
@@ -9,6 +17,8 @@ pub fn resize_input(ui: &mut Ui, settings: &mut Settings) {
             ResizeOptions::Largest(_) => "Largest",
             ResizeOptions::Exact(_, _) => "Exact",
             ResizeOptions::Smallest(_) => "Smallest",
+            #[cfg(feature = "seam-carving")]
+            ResizeOptions::SeamCarve(_, _) => "Content-aware",
         })
         .show_ui(ui, |ui| {
             ui.selectable_value(&mut settings.resize_options, ResizeOptions::None, "None");
@@ -27,6 +37,15 @@ pub fn resize_input(ui: &mut Ui, settings: &mut Settings) {
                 ResizeOptions::Smallest(0),
                 "Smallest",
             );
+            #[cfg(feature = "seam-carving")]
+            ui.selectable_value(
+                &mut settings.resize_options,
+                ResizeOptions::SeamCarve(0, 0),
+                "Content-aware",
+            )
+            .on_hover_text(
+                "Experimental, changes the aspect ratio by removing low detail seams. Slow",
+            );
         });
 
     match settings.resize_options {
@@ -66,5 +85,36 @@ pub fn resize_input(ui: &mut Ui, settings: &mut Settings) {
             );
             settings.resize_options = ResizeOptions::Smallest(size);
         }
+        #[cfg(feature = "seam-carving")]
+        ResizeOptions::SeamCarve(mut width, mut height) => {
+            ui.label("Retarget to exact size");
+            ui.horizontal(|ui| {
+                ui.label("Width: ");
+                ui.add(egui::DragValue::new(&mut width).range(1..=8192));
+                ui.label("Height: ");
+                ui.add(egui::DragValue::new(&mut height).range(1..=8192));
+            });
+            settings.resize_options = ResizeOptions::SeamCarve(width, height);
+        }
+    }
+
+    if settings.resize_options != ResizeOptions::None {
+        ui.checkbox(&mut settings.linear_resize, "Resize in linear light")
+            .on_hover_text("Slower, but keeps fine detail from darkening when downscaling");
+
+        egui::ComboBox::from_label("Upscaling filter")
+            .selected_text(settings.upscale_filter.to_string())
+            .show_ui(ui, |ui| {
+                for filter in FILTERS {
+                    ui.selectable_value(&mut settings.upscale_filter, filter, filter.to_string());
+                }
+            });
+        egui::ComboBox::from_label("Downscaling filter")
+            .selected_text(settings.downscale_filter.to_string())
+            .show_ui(ui, |ui| {
+                for filter in FILTERS {
+                    ui.selectable_value(&mut settings.downscale_filter, filter, filter.to_string());
+                }
+            });
     }
 }
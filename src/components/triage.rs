@@ -0,0 +1,49 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use egui::Ui;
+
+use crate::{structs::error::ConvertError, util::pattern::matches};
+
+/// Failed files grouped by error category. Returns the files of a group when its retry button is clicked.
+pub fn failure_triage(
+    ui: &mut Ui,
+    failed: &[(PathBuf, ConvertError)],
+    filter: &str,
+    can_retry: bool,
+) -> Option<Vec<PathBuf>> {
+    let mut groups: BTreeMap<&'static str, Vec<&(PathBuf, ConvertError)>> = BTreeMap::new();
+    for entry in failed {
+        groups.entry(entry.1.category()).or_default().push(entry);
+    }
+
+    let mut retry = None;
+
+    for (category, entries) in groups {
+        egui::CollapsingHeader::new(format!("{} ({})", category, entries.len()))
+            .id_salt(category)
+            .show(ui, |ui| {
+                if ui
+                    .add_enabled(can_retry, egui::Button::new("Retry"))
+                    .clicked()
+                {
+                    retry = Some(entries.iter().map(|(path, _)| path.clone()).collect());
+                }
+
+                egui::ScrollArea::vertical()
+                    .id_salt(category)
+                    .max_height(120.0)
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        for (path, error) in &entries {
+                            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                            if matches(filter, &file_name) {
+                                ui.label(path.display().to_string())
+                                    .on_hover_text(error.to_string());
+                            }
+                        }
+                    });
+            });
+    }
+
+    retry
+}
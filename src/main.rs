@@ -1,17 +1,27 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::path::PathBuf;
+
 use eframe::egui;
 
 mod components;
 mod process;
+mod storage;
 mod structs;
-mod util;
 mod ui;
+mod util;
 
 pub static INPUT_FOLDER: &str = "input";
 pub static OUTPUT_FOLDER: &str = "output";
+// Subfolder originals are moved to after conversion
+pub static ORIGINALS_FOLDER: &str = "originals";
 
 fn main() -> eframe::Result {
+    // Files and folders passed in, e.g. through "Open with"
+    let paths: Vec<PathBuf> = std::env::args_os().skip(1).map(PathBuf::from).collect();
+
+    util::crash::install();
+
     let options = eframe::NativeOptions {
         centered: true,
         viewport: egui::ViewportBuilder::default()
@@ -24,6 +34,6 @@ fn main() -> eframe::Result {
     eframe::run_native(
         "Image converter",
         options,
-        Box::new(|_cc| Ok(Box::<ui::App>::default())),
+        Box::new(|_cc| Ok(Box::new(ui::App::with_paths(paths)))),
     )
 }
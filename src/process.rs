@@ -1,96 +1,1308 @@
 use std::{
-    error::Error,
     fs::{self},
     path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc,
+        mpsc::Sender,
+        Arc, Mutex,
     },
 };
 
 use image::{
-    codecs::{avif::AvifEncoder, jpeg::JpegEncoder},
+    codecs::{
+        avif::AvifEncoder,
+        bmp::BmpEncoder,
+        png::{CompressionType, FilterType as PngFilterType, PngEncoder},
+        qoi::QoiEncoder,
+    },
     imageops::FilterType,
-    GenericImageView,
+    GenericImageView, ImageFormat,
 };
 
 use rayon::prelude::*;
 
 use crate::{
+    storage::{run_id, Catalog},
     structs::{
-        file_type::EncodingOptions,
-        settings::{ResizeOptions, Settings}, update::Update,
+        error::ConvertError,
+        file_type::{AvifBitDepth, BmpDepth, EncodingOptions, PngCompression},
+        settings::{
+            ChannelMode, InputFolder, PassthroughOption, ResampleFilter, ResizeOptions, Settings,
+        },
+        update::{Asset, Converted, Job, JobId, Stage, Timings, Update},
+    },
+    util::{
+        affinity, animation,
+        archive::{extract_images, is_archive},
+        atlas::{self, ATLAS_INDEX_FILE},
+        avif, channels, color,
+        contact_sheet::{self, CONTACT_SHEET_FILE},
+        content::adaptive_quality,
+        dds,
+        document::{self, Page, COMBINED_PDF_FILE},
+        download::{download_image, is_timeout, is_url},
+        exif, favicon,
+        files::{
+            handle_original, hash_file, output_dir, remove_duplicates, scan_folder, sniff_format,
+            sort_files,
+        },
+        gif_output, humanize, icons, interlace, jpeg, jxl,
+        manifest::blurhash,
+        mpo, multipage, passthrough, provenance, psd, quantize,
+        scratch::{finish_staged, session_dir, staging_file, write_staged},
+        sequence, svg, tiff_output, tiles, xmp,
     },
     OUTPUT_FOLDER,
 };
 
+/// What workers collect for documents built from the whole run, tagged with their job so
+/// they can be put in queue order at the end.
+#[derive(Default)]
+struct RunDocuments {
+    pages: Mutex<Vec<(JobId, Page)>>,
+    thumbnails: Mutex<Vec<(JobId, String, image::RgbaImage)>>,
+    sprites: Mutex<Vec<(JobId, String, image::RgbaImage)>>,
+    // Set once the contact sheet has as many cells as it takes
+    sheet_full: AtomicBool,
+}
+
+/// Converts every job in `jobs`. Jobs without a sequence number are numbered from
+/// `first_number`, the number the next run continues from is returned.
 pub fn convert_images(
-    sender: std::sync::mpsc::Sender<Update>,
+    sender: Sender<Update>,
     stop_flag: Arc<AtomicBool>,
-    files: Vec<PathBuf>,
+    mut jobs: Vec<Job>,
     settings: Settings,
-) {
+    first_number: u64,
+) -> u64 {
+    sender.send(Update::Queued(jobs.clone())).unwrap();
+
+    sort_files(&mut jobs, settings.sort_order);
+
+    if settings.skip_duplicates {
+        for (duplicate, original) in remove_duplicates(&mut jobs) {
+            sender
+                .send(Update::Duplicate(duplicate.id, original.id))
+                .unwrap();
+        }
+    }
+
+    let mut next_number = first_number;
+    if let Some(pattern) = &settings.rename_pattern {
+        if !sequence::is_valid(pattern) {
+            sender
+                .send(Update::Message(format!(
+                    "The rename pattern '{}' needs {{name}} or {{seq}}, every file would get the same name",
+                    pattern
+                )))
+                .unwrap();
+            sender
+                .send(Update::QueueCompleted(std::time::Duration::ZERO))
+                .unwrap();
+            return first_number;
+        }
+
+        next_number = sequence::number_jobs(&mut jobs, &settings, first_number);
+    }
+
     let queue_start_time = std::time::Instant::now();
+    let run = run_id();
     sender
         .send(Update::Message(format!(
             "Processing {} files...",
-            files.len()
+            jobs.len()
         )))
         .unwrap();
 
-    files.par_iter().for_each(|file| {
-        let start_time = std::time::Instant::now();
+    let documents = RunDocuments::default();
+
+    // Same content and settings as an earlier run, even when renamed or touched since.
+    // One connection for the run, sqlite only lets one writer in at a time anyway
+    let catalog = match settings.use_catalog {
+        true => match Catalog::open() {
+            Ok(catalog) => Some(Mutex::new(catalog)),
+            Err(e) => {
+                sender
+                    .send(Update::Message(format!("Failed to open catalog: {}", e)))
+                    .unwrap();
+                None
+            }
+        },
+        false => None,
+    };
+
+    // Bridged so files are picked up in queue order, par_iter would split the list up front
+    // Threads are set up per run so the affinity setting applies to the next one
+    let pool = match affinity::thread_pool(settings.core_affinity) {
+        Ok(pool) => pool,
+        Err(e) => {
+            sender
+                .send(Update::Message(format!("Failed to start workers: {}", e)))
+                .unwrap();
+            sender
+                .send(Update::QueueCompleted(queue_start_time.elapsed()))
+                .unwrap();
+            return next_number;
+        }
+    };
+
+    pool.install(|| {
+        jobs.iter().par_bridge().for_each(|job| {
+            let file = &job.path;
+            let start_time = std::time::Instant::now();
+
+            if stop_flag.load(Ordering::Relaxed) {
+                let queue_elapsed = queue_start_time.elapsed();
+                sender.send(Update::QueueCompleted(queue_elapsed)).unwrap();
+                return;
+            }
+
+            let file_name = file.file_name().unwrap_or_default().to_string_lossy();
+            // Only names differ between jobs, the catalog keeps the settings of the whole run
+            let numbered = sequence::numbered(&settings, job.sequence);
+
+            if settings.skip_existing && !is_url(file) && is_up_to_date(file, &numbered) {
+                sender
+                    .send(Update::Skipped(job.id, "output is up to date".to_string()))
+                    .unwrap();
+                return;
+            }
+
+            if settings.channels == ChannelMode::Merge && channels::is_merged_channel(file) {
+                sender
+                    .send(Update::Skipped(
+                        job.id,
+                        "merged into its red channel's output".to_string(),
+                    ))
+                    .unwrap();
+                return;
+            }
+
+            // Hashed before converting, the original may be moved or deleted afterwards
+            let hash = match (&catalog, is_url(file)) {
+                (Some(_), false) => hash_file(file).ok(),
+                _ => None,
+            };
+
+            if let (Some(catalog), Some(hash)) = (&catalog, &hash) {
+                if settings.skip_existing
+                    && catalog
+                        .lock()
+                        .unwrap()
+                        .is_converted(hash, &settings)
+                        .unwrap_or(false)
+                {
+                    sender
+                        .send(Update::Skipped(job.id, "already converted".to_string()))
+                        .unwrap();
+                    return;
+                }
+            }
+
+            sender.send(Update::StartProcessing(job.id)).unwrap();
+
+            if is_url(file) {
+                sender
+                    .send(Update::Message(format!("Downloading '{}'", file.display())))
+                    .unwrap();
+            }
+
+            let result = match is_archive(file) {
+                true => convert_archive(file, job.id, &numbered, &sender, &documents),
+                false => convert_image(file, job.id, &numbered, &sender, &documents),
+            };
+
+            match &result {
+                Ok(_) => println!("Processed '{}'", file_name),
+                Err(e) => eprintln!("Failed to process '{}': {}", file_name, e),
+            }
+
+            if let (Some(catalog), Ok(converted)) = (&catalog, &result) {
+                let recorded = catalog
+                    .lock()
+                    .unwrap()
+                    .record(run, file, hash, converted, &settings);
+                if let Err(e) = recorded {
+                    sender
+                        .send(Update::Message(format!(
+                            "Failed to add '{}' to the catalog: {}",
+                            file_name, e
+                        )))
+                        .unwrap();
+                }
+            }
+
+            // Originals are only touched once every output has been written
+            if result.is_ok() && !is_url(file) {
+                if let Err(e) = handle_original(file, &settings) {
+                    sender
+                        .send(Update::Message(format!(
+                            "Failed to clean up '{}': {}",
+                            file.display(),
+                            e
+                        )))
+                        .unwrap();
+                }
+            }
+
+            let elapsed = start_time.elapsed();
+            sender
+                .send(Update::FinishedProcessing(job.id, result, elapsed))
+                .unwrap();
+        })
+    });
+
+    if settings.combine_pdf && !stop_flag.load(Ordering::Relaxed) {
+        // Pages of one job were added in order, a stable sort keeps them that way
+        let mut pages = documents.pages.into_inner().unwrap();
+        pages.sort_by_key(|(id, _)| *id);
+        let pages: Vec<Page> = pages.into_iter().map(|(_, page)| page).collect();
+
+        let message = match write_combined_pdf(&pages, &settings) {
+            Ok(path) => format!("Combined {} pages into '{}'", pages.len(), path.display()),
+            Err(e) => format!("Failed to combine outputs into a PDF: {}", e),
+        };
+        sender.send(Update::Message(message)).unwrap();
+
+        for page in &pages {
+            let _ = fs::remove_file(&page.file);
+        }
+    }
+
+    if settings.contact_sheet && !stop_flag.load(Ordering::Relaxed) {
+        let mut thumbnails = documents.thumbnails.into_inner().unwrap();
+        thumbnails.sort_by_key(|(id, _, _)| *id);
+        let cells: Vec<(String, image::RgbaImage)> = thumbnails
+            .into_iter()
+            .map(|(_, name, thumb)| (name, thumb))
+            .collect();
+
+        let message = match write_contact_sheet(&cells, &settings) {
+            Ok(path) => format!("Tiled {} images into '{}'", cells.len(), path.display()),
+            Err(e) => format!("Failed to build the contact sheet: {}", e),
+        };
+        sender.send(Update::Message(message)).unwrap();
+    }
+
+    if settings.sprite_sheet && !stop_flag.load(Ordering::Relaxed) {
+        let mut sprites = documents.sprites.into_inner().unwrap();
+        sprites.sort_by_key(|(id, _, _)| *id);
+
+        let mut sprites: Vec<(String, image::RgbaImage)> = sprites
+            .into_iter()
+            .map(|(_, name, sprite)| (name, sprite))
+            .collect();
+        atlas::unique_names(&mut sprites);
+
+        let count = sprites.len();
+        let message = match write_sprite_sheets(sprites, &settings) {
+            Ok(sheets) => format!("Packed {} sprites onto {} sheets", count, sheets),
+            Err(e) => format!("Failed to build the sprite sheet: {}", e),
+        };
+        sender.send(Update::Message(message)).unwrap();
+    }
+
+    let queue_elapsed = queue_start_time.elapsed();
+    sender.send(Update::QueueCompleted(queue_elapsed)).unwrap();
+
+    next_number
+}
+
+/// Writes every page in `pages` into one PDF in the output folder.
+fn write_combined_pdf(
+    pages: &[Page],
+    settings: &Settings,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if pages.is_empty() {
+        return Err("nothing was converted".into());
+    }
+
+    let path = Path::new(OUTPUT_FOLDER).join(COMBINED_PDF_FILE);
+    fs::create_dir_all(OUTPUT_FOLDER)?;
+
+    // Streamed to disk, a run can hold more pages than fit in memory
+    let staged = staging_file(&path, settings)?;
+    let written = fs::File::create(&staged)
+        .map_err(Into::into)
+        .and_then(|file| {
+            document::combine(
+                pages,
+                settings.pdf_page,
+                settings.pdf_margin,
+                std::io::BufWriter::new(file),
+            )
+        });
+    if let Err(e) = written {
+        let _ = fs::remove_file(&staged);
+        return Err(e);
+    }
+    finish_staged(&staged, &path)?;
+
+    Ok(path)
+}
+
+/// Keeps a thumbnail of `img` for the contact sheet, when there is one. The sheet is built in
+/// memory, so images past `contact_sheet::MAX_CELLS` are left off it.
+fn add_thumbnail(
+    img: &image::DynamicImage,
+    path: &Path,
+    job: JobId,
+    settings: &Settings,
+    sender: &Sender<Update>,
+    documents: &RunDocuments,
+) {
+    if !settings.contact_sheet {
+        return;
+    }
+
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let is_full = || documents.thumbnails.lock().unwrap().len() >= contact_sheet::MAX_CELLS;
+
+    if !is_full() {
+        let thumb = img
+            .thumbnail(settings.sheet_cell_size, settings.sheet_cell_size)
+            .to_rgba8();
 
-        if stop_flag.load(Ordering::Relaxed) {
-            let queue_elapsed = queue_start_time.elapsed();
-            sender.send(Update::QueueCompleted(queue_elapsed)).unwrap();
+        let mut thumbnails = documents.thumbnails.lock().unwrap();
+        if thumbnails.len() < contact_sheet::MAX_CELLS {
+            thumbnails.push((job, name.to_string(), thumb));
             return;
         }
+    }
+
+    if !documents.sheet_full.swap(true, Ordering::Relaxed) {
+        sender
+            .send(Update::Message(format!(
+                "The contact sheet is full at {} images, '{}' and later images are left off",
+                humanize::count(contact_sheet::MAX_CELLS),
+                name
+            )))
+            .unwrap();
+    }
+}
 
-        let file_name = file.file_name().unwrap().to_str().unwrap();
+/// Keeps `img` for the sprite sheet, when there is one. Images larger than a sheet can't be
+/// packed and are left out.
+fn add_sprite(
+    img: &image::DynamicImage,
+    path: &Path,
+    job: JobId,
+    settings: &Settings,
+    sender: &Sender<Update>,
+    documents: &RunDocuments,
+) {
+    if !settings.sprite_sheet {
+        return;
+    }
+
+    if img.width().max(img.height()) > settings.atlas_size {
+        sender
+            .send(Update::Message(format!(
+                "'{}' is larger than the sprite sheet and was left out",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            )))
+            .unwrap();
+        return;
+    }
 
-        sender.send(Update::StartProcessing(file.clone())).unwrap();
+    let name = path.file_stem().unwrap_or_default().to_string_lossy();
+    documents
+        .sprites
+        .lock()
+        .unwrap()
+        .push((job, name.to_string(), img.to_rgba8()));
+}
+
+/// Adds `path` to the documents built from the whole run, for files written without decoding.
+fn add_source_documents(
+    path: &Path,
+    job: JobId,
+    settings: &Settings,
+    sender: &Sender<Update>,
+    documents: &RunDocuments,
+) {
+    add_page(path, job, settings, sender, documents, |file| {
+        document::file_page(path, file)
+    });
 
-        let success = match convert_image(file, &settings) {
-            Ok(_) => {
-                println!("Processed '{}'", file_name);
-                true
+    if settings.contact_sheet || settings.sprite_sheet {
+        match get_image(path) {
+            Ok(img) => {
+                add_thumbnail(&img, path, job, settings, sender, documents);
+                add_sprite(&img, path, job, settings, sender, documents);
             }
-            Err(e) => {
-                eprintln!("Failed to process '{}': {}", file_name, e);
-                false
+            Err(e) => sender
+                .send(Update::Message(format!(
+                    "Left '{}' off the contact and sprite sheets: {}",
+                    path.file_name().unwrap_or_default().to_string_lossy(),
+                    e
+                )))
+                .unwrap(),
+        }
+    }
+}
+
+/// Adds the page made by `page` to the combined PDF, when there is one. A page that fails is
+/// left out with a message, the file and the rest of the PDF are still fine.
+fn add_page(
+    path: &Path,
+    job: JobId,
+    settings: &Settings,
+    sender: &Sender<Update>,
+    documents: &RunDocuments,
+    page: impl FnOnce(&Path) -> Result<Page, Box<dyn std::error::Error>>,
+) {
+    if !settings.combine_pdf {
+        return;
+    }
+
+    let page = staging_file(Path::new("page.jpg"), settings)
+        .map_err(Into::into)
+        .and_then(|file| {
+            let page = page(&file);
+            if page.is_err() {
+                let _ = fs::remove_file(&file);
+            }
+            page
+        });
+
+    match page {
+        Ok(page) => documents.pages.lock().unwrap().push((job, page)),
+        Err(e) => sender
+            .send(Update::Message(format!(
+                "Left '{}' out of the combined PDF: {}",
+                path.file_name().unwrap_or_default().to_string_lossy(),
+                e
+            )))
+            .unwrap(),
+    }
+}
+
+fn write_contact_sheet(
+    cells: &[(String, image::RgbaImage)],
+    settings: &Settings,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let sheet = contact_sheet::build(
+        cells,
+        settings.sheet_columns,
+        settings.sheet_cell_size,
+        settings.sheet_labels,
+    )?;
+
+    let mut data = Vec::new();
+    sheet.write_to(
+        &mut std::io::Cursor::new(&mut data),
+        image::ImageFormat::Png,
+    )?;
+
+    let path = Path::new(OUTPUT_FOLDER).join(CONTACT_SHEET_FILE);
+    fs::create_dir_all(OUTPUT_FOLDER)?;
+    write_staged(&data, &path, settings)?;
+
+    Ok(path)
+}
+
+/// Writes the packed sheets and their index to the output folder, returns how many sheets
+/// there are.
+fn write_sprite_sheets(
+    sprites: Vec<(String, image::RgbaImage)>,
+    settings: &Settings,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    if sprites.is_empty() {
+        return Err("nothing was converted".into());
+    }
+
+    let sheets = atlas::pack(sprites, settings.atlas_size, settings.atlas_padding);
+    let output_dir = Path::new(OUTPUT_FOLDER);
+    fs::create_dir_all(output_dir)?;
+
+    for (i, sheet) in sheets.iter().enumerate() {
+        let mut data = Vec::new();
+        let mut cursor = std::io::Cursor::new(&mut data);
+        sheet.image.write_to(&mut cursor, image::ImageFormat::Png)?;
+        write_staged(&data, &output_dir.join(atlas::sheet_file(i)), settings)?;
+    }
+
+    let index = atlas::index(&sheets);
+    write_staged(
+        index.as_bytes(),
+        &output_dir.join(ATLAS_INDEX_FILE),
+        settings,
+    )?;
+
+    Ok(sheets.len())
+}
+
+fn convert_image(
+    path: &Path,
+    job: JobId,
+    settings: &Settings,
+    sender: &Sender<Update>,
+    documents: &RunDocuments,
+) -> Result<Converted, ConvertError> {
+    let downloaded;
+    let path =
+        match is_url(path) {
+            true => {
+                downloaded = download_image(&path.to_string_lossy(), &session_dir(settings))
+                    .map_err(|e| match is_timeout(e.as_ref()) {
+                        true => ConvertError::Timeout(e.to_string()),
+                        false => ConvertError::Download(e.to_string()),
+                    })?;
+                downloaded.as_path()
             }
+            false => path,
         };
 
-        let elapsed = start_time.elapsed();
+    let input_size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+    // Shortcuts that skip decoding only write the main format
+    let single_format = settings.extra_formats.is_empty();
+
+    if single_format
+        && settings.passthrough != PassthroughOption::Off
+        && passthrough::is_unchanged(path, settings)
+    {
+        let converted = write_unchanged(path, input_size, settings, sender)?;
+        add_source_documents(path, job, settings, sender, documents);
+        return Ok(converted);
+    }
+
+    // JPEGs can be repacked as JPEG XL without decoding, and restored exactly later
+    #[cfg(feature = "jxl-output")]
+    if let EncodingOptions::Jxl(options) = &settings.encoding_options {
+        if single_format
+            && options.recompress_jpeg
+            && sniff_format(path) == Some(ImageFormat::Jpeg)
+            && passthrough::keeps_pixels(path, settings)
+        {
+            let converted = write_recompressed_jpeg(path, options, input_size, settings, sender)?;
+            add_source_documents(path, job, settings, sender, documents);
+            return Ok(converted);
+        }
+    }
+
+    // Adam7 interlaced PNGs decode a lot slower than regular ones
+    let interlaced = interlace::is_interlaced(path);
+    if interlaced {
         sender
-            .send(Update::FinishedProcessing(file.clone(), success, elapsed))
+            .send(Update::Message(format!(
+                "'{}' is an interlaced PNG, decoding will be slow",
+                file_name
+            )))
             .unwrap();
+    }
+
+    let mut timings = Timings::default();
+    sender.send(Update::Stage(job, Stage::Decoding)).unwrap();
+
+    let formats = settings.per_format();
+    let mut outputs = Vec::new();
+
+    // Animated GIFs stay animated in the formats that store animations, the other formats
+    // get the first frame below
+    let mut animated = vec![false; formats.len()];
+    if sniff_format(path) == Some(ImageFormat::Gif)
+        && formats
+            .iter()
+            .any(|settings| animates(&settings.encoding_options))
+    {
+        let animation = timings
+            .time("decode", || animation::gif_frames(path))
+            .map_err(|e| ConvertError::Decode(e.to_string()))?;
+
+        if let Some(frames) = animation {
+            sender.send(Update::Stage(job, Stage::Encoding)).unwrap();
+            add_thumbnail(&frames[0].image, path, job, settings, sender, documents);
+            add_sprite(&frames[0].image, path, job, settings, sender, documents);
+
+            for (format, settings) in formats.iter().enumerate() {
+                if !animates(&settings.encoding_options) {
+                    continue;
+                }
+
+                outputs.push(write_animation(
+                    &frames,
+                    path,
+                    settings,
+                    sender,
+                    &mut timings,
+                )?);
+                animated[format] = true;
+
+                if format == 0 {
+                    add_page(path, job, settings, sender, documents, |file| {
+                        document::image_page(&resize_image(frames[0].image.clone(), settings), file)
+                    });
+                }
+            }
+
+            if animated.iter().all(|&animated| animated) {
+                return Ok(Converted {
+                    input_size,
+                    output_size: outputs.iter().map(|output| output.size).sum(),
+                    outputs,
+                    timings,
+                    unchanged: false,
+                });
+            }
+        }
+    }
+
+    let frames = timings.time("decode", || {
+        match interlaced && settings.fast_interlaced_png {
+            true => interlace::decode_fast(path)
+                .map(|img| vec![img])
+                .map_err(|e| ConvertError::Decode(e.to_string())),
+            false => get_frames(path, settings),
+        }
+    })?;
+
+    let sidecar = match settings.use_sidecars {
+        true => xmp::read_sidecar(path),
+        false => None,
+    };
+
+    let numbered = frames.len() > 1;
+
+    for (i, img) in frames.into_iter().enumerate() {
+        sender.send(Update::Stage(job, Stage::Resizing)).unwrap();
+
+        let img = match &sidecar {
+            Some(sidecar) => timings.time("sidecar", || xmp::apply(img, sidecar)),
+            None => img,
+        };
+        let img = color::tone_map(img, settings.tone_mapping, settings.exposure);
+        let img = timings.time("resize", || resize_image(img, settings));
+        if i == 0 {
+            add_thumbnail(&img, path, job, settings, sender, documents);
+            add_sprite(&img, path, job, settings, sender, documents);
+        }
+
+        let suffix = match numbered {
+            true => format!("-{}", i + 1),
+            false => String::new(),
+        };
+
+        #[cfg(feature = "pdf")]
+        let suffix = match crate::util::pdf::is_rendered(path, settings) {
+            true => crate::util::pdf::page_suffix(i),
+            false => suffix,
+        };
+
+        sender.send(Update::Stage(job, Stage::Encoding)).unwrap();
+
+        if settings.favicon_package {
+            let dir = favicon_dir(path, &suffix, settings);
+            let package = timings
+                .time("encode", || favicon::write_package(&img, &dir, settings))
+                .map_err(|e| ConvertError::Encode(e.to_string()))?;
+            outputs.extend(package);
+            continue;
+        }
+
+        for (format, settings) in formats.iter().enumerate() {
+            if animated[format] {
+                continue;
+            }
+
+            let img = fit_limit(img.clone(), path, settings)?;
+
+            // HDR sources are expected to lose range in tone mapping, 16 bit ones are worth a note
+            let max_depth = settings.encoding_options.max_bit_depth();
+            if i == 0 && color::bit_depth(&img) == 16 && max_depth < 16 {
+                sender
+                    .send(Update::Message(format!(
+                        "'{}' has 16 bits per channel, the {} output stores {}",
+                        file_name, settings.encoding_options, max_depth
+                    )))
+                    .unwrap();
+            }
+            let img = timings.time("bit depth", || {
+                color::reduce_depth(img, max_depth, settings.dither)
+            });
+
+            // One page per image of the main format, from the pixels about to be encoded
+            if format == 0 {
+                add_page(path, job, settings, sender, documents, |file| {
+                    document::image_page(&img, file)
+                });
+            }
+
+            let tile_size = settings
+                .encoding_options
+                .max_dimension()
+                .filter(|&max| settings.split_oversized && img.width().max(img.height()) > max);
+
+            match (settings.channels, tile_size) {
+                (ChannelMode::Split, _) => {
+                    for (channel, img) in timings.time("split", || channels::split(&img)) {
+                        let suffix = format!("{}{}", suffix, channel);
+                        outputs.push(write_output(
+                            &img,
+                            path,
+                            &suffix,
+                            settings,
+                            sender,
+                            &mut timings,
+                        )?);
+                    }
+                }
+                (_, Some(tile_size)) => outputs.extend(write_tiles(
+                    &img,
+                    tile_size,
+                    path,
+                    &suffix,
+                    settings,
+                    sender,
+                    &mut timings,
+                )?),
+                _ => outputs.push(write_output(
+                    &img,
+                    path,
+                    &suffix,
+                    settings,
+                    sender,
+                    &mut timings,
+                )?),
+            }
+        }
+    }
+
+    let output_size = outputs.iter().map(|output| output.size).sum();
+
+    if interlaced || settings.verbose {
+        sender
+            .send(Update::Message(format!("'{}': {}", file_name, timings)))
+            .unwrap();
+    }
+
+    Ok(Converted {
+        input_size,
+        output_size,
+        outputs,
+        timings,
+        unchanged: false,
+    })
+}
+
+/// Encodes `img` and writes it as an output of `path`, named with `suffix`.
+fn write_output(
+    img: &image::DynamicImage,
+    path: &Path,
+    suffix: &str,
+    settings: &Settings,
+    sender: &Sender<Update>,
+    timings: &mut Timings,
+) -> Result<Asset, ConvertError> {
+    let data = timings.time("encode", || encode_image(img, settings))?;
+    write_encoded(data, img, path, suffix, settings, sender, timings)
+}
+
+/// Files in `files` that come out larger than one of the output formats stores, so they can
+/// be pointed out before a run. Only headers are read, sources whose size can't be told that
+/// way are left out.
+pub fn oversized(files: &[PathBuf], settings: &Settings) -> Vec<PathBuf> {
+    if settings.split_oversized || settings.fit_to_limit {
+        return Vec::new();
+    }
+
+    let Some(max) = settings
+        .per_format()
+        .iter()
+        .filter_map(|settings| settings.encoding_options.max_dimension())
+        .min()
+    else {
+        return Vec::new();
+    };
+
+    files
+        .par_iter()
+        .filter(|file| !is_url(file))
+        .filter(|file| {
+            image::image_dimensions(file).is_ok_and(|(width, height)| {
+                let (width, height) = target_size(width, height, &settings.resize_options);
+                width.max(height) > max
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+/// Checks `img` against the size limit of the output format before the encoder fails on it,
+/// downscaling it to fit when that's enabled. Oversized images that get tiled pass as is.
+fn fit_limit(
+    img: image::DynamicImage,
+    path: &Path,
+    settings: &Settings,
+) -> Result<image::DynamicImage, ConvertError> {
+    let max = match settings.encoding_options.max_dimension() {
+        Some(max) if img.width().max(img.height()) > max && !settings.split_oversized => max,
+        _ => return Ok(img),
+    };
+
+    match settings.fit_to_limit {
+        true => Ok(img.resize(max, max, filter_type(settings.downscale_filter))),
+        false => Err(ConvertError::Encode(format!(
+            "'{}' is {}x{}, {} stores at most {} pixels per side. Split it into tiles or downscale it to fit",
+            path.file_name().unwrap_or_default().to_string_lossy(),
+            img.width(),
+            img.height(),
+            settings.encoding_options,
+            max
+        ))),
+    }
+}
+
+/// Writes `img` as tiles of at most `tile_size` pixels, with a JSON index of where each tile goes.
+fn write_tiles(
+    img: &image::DynamicImage,
+    tile_size: u32,
+    path: &Path,
+    suffix: &str,
+    settings: &Settings,
+    sender: &Sender<Update>,
+    timings: &mut Timings,
+) -> Result<Vec<Asset>, ConvertError> {
+    let pieces = timings.time("tiles", || tiles::split(img, tile_size));
+
+    let mut outputs = Vec::with_capacity(pieces.len());
+    for tile in &pieces {
+        let suffix = format!("{}{}", suffix, tiles::suffix(tile));
+        outputs.push(write_output(
+            &tile.image,
+            path,
+            &suffix,
+            settings,
+            sender,
+            timings,
+        )?);
+    }
+
+    let files: Vec<String> = outputs
+        .iter()
+        .map(|output| {
+            output
+                .path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+    let index = tiles::index(img.width(), img.height(), &pieces, &files);
+    let index_path =
+        output_path(path, Some(&format!("{}-tiles", suffix)), settings)?.with_extension("json");
+    let replaced = index_path.exists();
+    write_staged(index.as_bytes(), &index_path, settings)
+        .map_err(|e| ConvertError::Write(e.to_string()))?;
+
+    // Listed with the tiles so the catalog, undo and the manifest know about it too
+    outputs.push(Asset {
+        path: index_path,
+        width: img.width(),
+        height: img.height(),
+        size: index.len() as u64,
+        blurhash: None,
+        replaced,
     });
 
-    let queue_elapsed = queue_start_time.elapsed();
-    sender.send(Update::QueueCompleted(queue_elapsed)).unwrap();
+    sender
+        .send(Update::Message(format!(
+            "'{}' is larger than {} allows, split into {} tiles",
+            path.file_name().unwrap_or_default().to_string_lossy(),
+            settings.encoding_options,
+            pieces.len()
+        )))
+        .unwrap();
+
+    Ok(outputs)
+}
+
+/// Links or copies `path` to its output as is, for files converting wouldn't change.
+fn write_unchanged(
+    path: &Path,
+    input_size: u64,
+    settings: &Settings,
+    sender: &Sender<Update>,
+) -> Result<Converted, ConvertError> {
+    let mut timings = Timings::default();
+    let output = output_path(path, None, settings)?;
+    let replaced = output.exists();
+
+    if let Some(output_dir) = output.parent() {
+        fs::create_dir_all(output_dir).map_err(|e| ConvertError::Write(e.to_string()))?;
+    }
+
+    timings
+        .time("write", || {
+            passthrough::write(path, &output, settings.passthrough)
+        })
+        .map_err(|e| ConvertError::Write(e.to_string()))?;
+
+    if settings.record_provenance {
+        if let Err(e) = provenance::record(&output, path, settings) {
+            sender
+                .send(Update::Message(format!(
+                    "Failed to record provenance of '{}': {}",
+                    output.display(),
+                    e
+                )))
+                .unwrap();
+        }
+    }
+
+    let (width, height) = image::image_dimensions(path).map_err(ConvertError::from_decoding)?;
+    // Optimized outputs are smaller than their input
+    let output_size = fs::metadata(&output).map(|m| m.len()).unwrap_or(input_size);
+
+    Ok(Converted {
+        input_size,
+        output_size,
+        outputs: vec![Asset {
+            path: output,
+            width,
+            height,
+            size: output_size,
+            blurhash: None,
+            replaced,
+        }],
+        timings,
+        unchanged: true,
+    })
+}
+
+/// Writes the JPEG at `path` losslessly recompressed as JPEG XL.
+#[cfg(feature = "jxl-output")]
+fn write_recompressed_jpeg(
+    path: &Path,
+    options: &crate::structs::file_type::JxlSettings,
+    input_size: u64,
+    settings: &Settings,
+    sender: &Sender<Update>,
+) -> Result<Converted, ConvertError> {
+    let mut timings = Timings::default();
+
+    let data = timings
+        .time("encode", || {
+            crate::util::jxl_output::recompress_jpeg(path, options)
+        })
+        .map_err(|e| ConvertError::Encode(e.to_string()))?;
+    let (output, replaced) = timings.time("write", || save_image(&data, path, None, settings))?;
+
+    if settings.record_provenance {
+        if let Err(e) = provenance::record(&output, path, settings) {
+            sender
+                .send(Update::Message(format!(
+                    "Failed to record provenance of '{}': {}",
+                    output.display(),
+                    e
+                )))
+                .unwrap();
+        }
+    }
+
+    let (width, height) = image::image_dimensions(path).map_err(ConvertError::from_decoding)?;
+
+    Ok(Converted {
+        input_size,
+        output_size: data.len() as u64,
+        outputs: vec![Asset {
+            path: output,
+            width,
+            height,
+            size: data.len() as u64,
+            blurhash: None,
+            replaced,
+        }],
+        timings,
+        unchanged: false,
+    })
+}
+
+/// Resizes every frame of an animation and writes it as a single animated WebP or GIF.
+fn write_animation(
+    frames: &[animation::Frame],
+    path: &Path,
+    settings: &Settings,
+    sender: &Sender<Update>,
+    timings: &mut Timings,
+) -> Result<Asset, ConvertError> {
+    let frames: Vec<animation::Frame> = timings.time("resize", || {
+        frames
+            .iter()
+            .map(|frame| animation::Frame {
+                image: resize_image(frame.image.clone(), settings),
+                delay: frame.delay,
+            })
+            .collect()
+    });
+
+    let data = timings
+        .time("encode", || match &settings.encoding_options {
+            EncodingOptions::WebP(options) => animation::encode_webp(&frames, options),
+            EncodingOptions::Gif(options) => {
+                let frames: Vec<_> = frames
+                    .iter()
+                    .map(|frame| (&frame.image, frame.delay))
+                    .collect();
+                gif_output::encode(&frames, options).map_err(|e| e.to_string())
+            }
+            _ => Err(format!(
+                "{} can't store animations",
+                settings.encoding_options
+            )),
+        })
+        .map_err(ConvertError::Encode)?;
+
+    write_encoded(data, &frames[0].image, path, "", settings, sender, timings)
+}
+
+/// Writes already encoded `data` as an output of `path`, named with `suffix`.
+/// `img` is the image it was encoded from.
+fn write_encoded(
+    data: Vec<u8>,
+    img: &image::DynamicImage,
+    path: &Path,
+    suffix: &str,
+    settings: &Settings,
+    sender: &Sender<Update>,
+    timings: &mut Timings,
+) -> Result<Asset, ConvertError> {
+    let data = timings.time("metadata", || exif::apply(data, path, img, settings))?;
+
+    let suffix = (!suffix.is_empty()).then_some(suffix);
+    let (output, replaced) = timings.time("write", || save_image(&data, path, suffix, settings))?;
+
+    if settings.record_provenance {
+        if let Err(e) = provenance::record(&output, path, settings) {
+            sender
+                .send(Update::Message(format!(
+                    "Failed to record provenance of '{}': {}",
+                    output.display(),
+                    e
+                )))
+                .unwrap();
+        }
+    }
+
+    let blurhash = match settings.write_manifest {
+        true => timings.time("blurhash", || blurhash(img)),
+        false => None,
+    };
+
+    Ok(Asset {
+        path: output,
+        width: img.width(),
+        height: img.height(),
+        size: data.len() as u64,
+        blurhash,
+        replaced,
+    })
 }
 
-fn convert_image(path: &Path, settings: &Settings) -> Result<(), Box<dyn Error>> {
-    let img = get_image(path)?;
-    let img = resize_image(img, settings);
-    let data = encode_image(img, settings)?;
-    save_image(&data, path, settings)?;
-    Ok(())
+/// Converts every image in a ZIP archive, writing the outputs to a folder named after the archive.
+fn convert_archive(
+    path: &Path,
+    job: JobId,
+    settings: &Settings,
+    sender: &Sender<Update>,
+    documents: &RunDocuments,
+) -> Result<Converted, ConvertError> {
+    let root = extract_images(path, &session_dir(settings))
+        .map_err(|e| ConvertError::Decode(e.to_string()))?;
+
+    // The extracted folder is treated as an input folder mapped to output/<archive name>
+    let mut archive_settings = settings.clone();
+    archive_settings.input_folders = vec![InputFolder {
+        path: root.clone(),
+        recursive: true,
+        output: Some(output_dir(path, settings).join(path.file_stem().unwrap_or_default())),
+    }];
+
+    let entries = scan_folder(&root, true, &archive_settings)
+        .map_err(|e| ConvertError::Decode(e.to_string()))?;
+
+    let mut converted = Converted {
+        input_size: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        output_size: 0,
+        outputs: Vec::new(),
+        timings: Timings::default(),
+        unchanged: false,
+    };
+    let mut first_error = None;
+
+    for entry in &entries {
+        match convert_image(entry, job, &archive_settings, sender, documents) {
+            Ok(result) => {
+                converted.output_size += result.output_size;
+                converted.outputs.extend(result.outputs);
+                converted.timings.merge(result.timings);
+            }
+            Err(e) => {
+                sender
+                    .send(Update::Message(format!(
+                        "Failed to process '{}' in '{}': {}",
+                        entry.strip_prefix(&root).unwrap_or(entry).display(),
+                        path.display(),
+                        e
+                    )))
+                    .unwrap();
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    if let Err(e) = fs::remove_dir_all(&root) {
+        eprintln!("Failed to remove '{}': {}", root.display(), e);
+    }
+
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(converted),
+    }
+}
+
+/// Decodes every image that should be exported from `path`, usually just one.
+fn get_frames(path: &Path, settings: &Settings) -> Result<Vec<image::DynamicImage>, ConvertError> {
+    if settings.channels == ChannelMode::Merge && channels::is_merge_source(path) {
+        return channels::merge(path)
+            .map(|img| vec![img])
+            .map_err(|e| ConvertError::Decode(e.to_string()));
+    }
+
+    // Before sniffing, most RAW files look like TIFFs
+    #[cfg(feature = "raw")]
+    if crate::util::raw::is_raw(path) {
+        return crate::util::raw::decode(path)
+            .map(|img| vec![img])
+            .map_err(ConvertError::Decode);
+    }
+
+    if svg::is_svg(path) {
+        return svg::rasterize(path, settings.svg_size)
+            .map(|img| vec![img])
+            .map_err(|e| ConvertError::Decode(e.to_string()));
+    }
+
+    if icons::is_icon(path) {
+        return icons::icon_images(path, settings.icon_all_sizes)
+            .map_err(|e| ConvertError::Decode(e.to_string()));
+    }
+
+    if psd::is_psd(path) {
+        return psd::composite(path)
+            .map(|img| vec![img])
+            .map_err(|e| ConvertError::Decode(e.to_string()));
+    }
+
+    if dds::is_dds(path) {
+        return dds::decode(path)
+            .map(|img| vec![img])
+            .map_err(|e| ConvertError::Decode(e.to_string()));
+    }
+
+    if jxl::is_jxl(path) {
+        return jxl::decode(path)
+            .map(|img| vec![img])
+            .map_err(|e| ConvertError::Decode(e.to_string()));
+    }
+
+    #[cfg(feature = "pdf")]
+    if crate::util::pdf::is_pdf(path) {
+        return crate::util::pdf::pdf_images(path, settings)
+            .map_err(|e| ConvertError::Decode(e.to_string()));
+    }
+
+    #[cfg(feature = "heif")]
+    if crate::util::heif::is_heif(path) {
+        return crate::util::heif::decode(path)
+            .map(|img| vec![img])
+            .map_err(|e| ConvertError::Decode(e.to_string()));
+    }
+
+    // Chat apps and downloads often get the extension wrong, so trust the content
+    match sniff_format(path) {
+        Some(ImageFormat::Jpeg) => {}
+        Some(ImageFormat::Tiff) if settings.tiff_all_pages => {
+            return multipage::tiff_pages(path).map_err(|e| ConvertError::Decode(e.to_string()));
+        }
+        _ => return get_image(path).map(|img| vec![img]),
+    }
+
+    let data = fs::read(path).map_err(|e| ConvertError::Decode(e.to_string()))?;
+
+    // Stereoscopic (MPO) files hold an image per eye, regular JPEGs decode as a whole
+    let ranges = match mpo::frames(&data) {
+        Some(frames) if settings.mpo_all_frames => frames,
+        Some(frames) => frames.into_iter().take(1).collect(),
+        None => std::iter::once(0..data.len()).collect(),
+    };
+
+    ranges
+        .into_iter()
+        .map(|range| {
+            image::load_from_memory_with_format(&data[range], ImageFormat::Jpeg)
+                .map_err(ConvertError::from_decoding)
+        })
+        .collect()
 }
 
-fn get_image(image_path: &Path) -> Result<image::DynamicImage, Box<dyn Error>> {
-    image::open(image_path).map_err(|e| {
+fn get_image(image_path: &Path) -> Result<image::DynamicImage, ConvertError> {
+    let reader = image::ImageReader::open(image_path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| ConvertError::Decode(e.to_string()))?;
+
+    reader.decode().map_err(|e| {
         eprintln!("Failed to open image '{}': {}", image_path.display(), e);
-        e.into()
+        ConvertError::from_decoding(e)
     })
 }
 
 fn resize_image(img: image::DynamicImage, settings: &Settings) -> image::DynamicImage {
-    let (width, height) = img.dimensions();
+    if settings.resize_options == ResizeOptions::None {
+        return img;
+    }
+
+    // Seams are found on the 8 bit pixels
+    #[cfg(feature = "seam-carving")]
+    if let ResizeOptions::SeamCarve(width, height) = settings.resize_options {
+        return crate::util::seam::retarget(img, width, height);
+    }
 
-    match settings.resize_options {
+    let color_type = img.color();
+    if !settings.linear_resize && !color_type.has_alpha() {
+        return resize(img, settings);
+    }
+
+    // Averaging gamma encoded values darkens fine detail, so resample in linear light
+    let mut img = match settings.linear_resize {
+        true => color::to_linear(&img),
+        false => image::DynamicImage::ImageRgba32F(img.into_rgba32f()),
+    };
+
+    // Transparent pixels would otherwise bleed their color into the edges
+    if color_type.has_alpha() {
+        color::premultiply(&mut img);
+    }
+
+    let mut resized = resize(img, settings);
+
+    if color_type.has_alpha() {
+        color::unpremultiply(&mut resized);
+    }
+
+    match settings.linear_resize {
+        true => color::from_linear(resized, color_type),
+        false => color::convert(resized, color_type),
+    }
+}
+
+/// Size a `width` by `height` image ends up at after resizing with `options`.
+pub fn target_size(width: u32, height: u32, options: &ResizeOptions) -> (u32, u32) {
+    match *options {
         ResizeOptions::Smallest(size) => {
             let new_width = if width < height {
                 size
@@ -102,11 +1314,7 @@ fn resize_image(img: image::DynamicImage, settings: &Settings) -> image::Dynamic
             } else {
                 size * height / width
             };
-            img.resize(new_width, new_height, FilterType::Lanczos3)
-        }
-
-        ResizeOptions::Exact(new_width, new_height) => {
-            img.resize_to_fill(new_width, new_height, FilterType::Lanczos3)
+            (new_width, new_height)
         }
 
         ResizeOptions::Largest(size) => {
@@ -120,8 +1328,56 @@ fn resize_image(img: image::DynamicImage, settings: &Settings) -> image::Dynamic
             } else {
                 size * height / width
             };
+            (new_width, new_height)
+        }
+
+        ResizeOptions::Exact(new_width, new_height) => (new_width, new_height),
+        #[cfg(feature = "seam-carving")]
+        ResizeOptions::SeamCarve(new_width, new_height) => (new_width, new_height),
+        ResizeOptions::None => (width, height),
+    }
+}
+
+fn filter_type(filter: ResampleFilter) -> FilterType {
+    match filter {
+        ResampleFilter::Nearest => FilterType::Nearest,
+        ResampleFilter::Triangle => FilterType::Triangle,
+        ResampleFilter::CatmullRom => FilterType::CatmullRom,
+        ResampleFilter::Gaussian => FilterType::Gaussian,
+        ResampleFilter::Lanczos3 => FilterType::Lanczos3,
+    }
+}
+
+fn resize(img: image::DynamicImage, settings: &Settings) -> image::DynamicImage {
+    let options = &settings.resize_options;
+    let (width, height) = img.dimensions();
+    let (new_width, new_height) = target_size(width, height, options);
+
+    // Sharp filters ring when enlarging, so upscales get their own. Judged by the scale the
+    // resize below applies, filling covers the target and crops so it scales by the larger side
+    let (x_scale, y_scale) = (
+        new_width as f64 / width as f64,
+        new_height as f64 / height as f64,
+    );
+    let scale = match options {
+        ResizeOptions::Exact(_, _) => x_scale.max(y_scale),
+        _ => x_scale.min(y_scale),
+    };
+    let filter = match scale > 1.0 {
+        true => filter_type(settings.upscale_filter),
+        false => filter_type(settings.downscale_filter),
+    };
+
+    match *options {
+        ResizeOptions::Smallest(_) | ResizeOptions::Largest(_) => {
+            img.resize(new_width, new_height, filter)
+        }
+
+        ResizeOptions::Exact(_, _) => img.resize_to_fill(new_width, new_height, filter),
 
-            img.resize(new_width, new_height, FilterType::Lanczos3)
+        #[cfg(feature = "seam-carving")]
+        ResizeOptions::SeamCarve(new_width, new_height) => {
+            crate::util::seam::retarget(img, new_width, new_height)
         }
 
         // No resize
@@ -129,39 +1385,124 @@ fn resize_image(img: image::DynamicImage, settings: &Settings) -> image::Dynamic
     }
 }
 
-fn encode_image(img: image::DynamicImage, settings: &Settings) -> Result<Vec<u8>, Box<dyn Error>> {
+fn encode_image(img: &image::DynamicImage, settings: &Settings) -> Result<Vec<u8>, ConvertError> {
     let mut buf = Vec::new();
 
+    let quality = |quality: u8| match settings.adaptive_quality {
+        true => adaptive_quality(quality, img),
+        false => quality,
+    };
+
     let data = match &settings.encoding_options {
         // Webp
         EncodingOptions::WebP(options) => {
-            let encoder = webp::Encoder::from_image(&img).inspect_err(|&e| {
+            let encoder = webp::Encoder::from_image(img).map_err(|e| {
                 eprintln!("Failed to encode image: {}", e);
+                ConvertError::UnsupportedColor(e.to_string())
             })?;
 
             let buffer = match options.lossless {
                 true => encoder.encode_lossless().to_vec(),
-                false => encoder.encode(options.quality as f32).to_vec(),
+                false => encoder.encode(quality(options.quality) as f32).to_vec(),
             };
 
             buffer.to_vec()
         }
 
         // Avif
+        EncodingOptions::Avif(options) if options.lossless => {
+            avif::encode_lossless(img, options.speed, options.bit_depth.bits())
+                .map_err(|e| ConvertError::Encode(e.to_string()))?
+        }
+        EncodingOptions::Avif(options) if options.bit_depth == AvifBitDepth::Ten => {
+            avif::encode_ten_bit(img, quality(options.quality), options.speed)
+                .map_err(|e| ConvertError::Encode(e.to_string()))?
+        }
         EncodingOptions::Avif(options) => {
             img.write_with_encoder(AvifEncoder::new_with_speed_quality(
                 &mut buf,
                 options.speed,
-                options.quality,
+                quality(options.quality),
             ))
-            .map_err(|e| format!("Failed to encode AVIF: {}", e))?;
+            .map_err(ConvertError::from_encoding)?;
             buf
         }
 
         // Jpeg
-        EncodingOptions::Jpeg(options) => {
-            img.write_with_encoder(JpegEncoder::new_with_quality(&mut buf, options.quality))
-                .map_err(|e| format!("Failed to encode JPEG: {}", e))?;
+        #[cfg(feature = "mozjpeg")]
+        EncodingOptions::Jpeg(options) if options.mozjpeg => {
+            jpeg::encode_mozjpeg(img, options, quality(options.quality))
+                .map_err(|e| ConvertError::Encode(e.to_string()))?
+        }
+        EncodingOptions::Jpeg(options) => jpeg::encode(img, options, quality(options.quality))
+            .map_err(|e| ConvertError::Encode(e.to_string()))?,
+
+        // Png
+        EncodingOptions::Png(options) if options.quantize => {
+            let compression = match options.compression {
+                PngCompression::Fast => png::Compression::Fast,
+                PngCompression::Default => png::Compression::Default,
+                PngCompression::Best => png::Compression::Best,
+            };
+
+            quantize::indexed_png(img, options.colors, compression, options.adaptive_filter)
+                .map_err(|e| ConvertError::Encode(e.to_string()))?
+        }
+        EncodingOptions::Png(options) => {
+            let compression = match options.compression {
+                PngCompression::Fast => CompressionType::Fast,
+                PngCompression::Default => CompressionType::Default,
+                PngCompression::Best => CompressionType::Best,
+            };
+            let filter = match options.adaptive_filter {
+                true => PngFilterType::Adaptive,
+                false => PngFilterType::Sub,
+            };
+
+            img.write_with_encoder(PngEncoder::new_with_quality(&mut buf, compression, filter))
+                .map_err(ConvertError::from_encoding)?;
+            buf
+        }
+
+        // Tiff
+        EncodingOptions::Tiff(options) => {
+            tiff_output::encode(img, options).map_err(|e| ConvertError::Encode(e.to_string()))?
+        }
+
+        // Gif
+        EncodingOptions::Gif(options) => gif_output::encode(&[(img, 0)], options)
+            .map_err(|e| ConvertError::Encode(e.to_string()))?,
+
+        // Ico
+        EncodingOptions::Ico(options) => icons::encode_ico(img, &options.sizes)
+            .map_err(|e| ConvertError::Encode(e.to_string()))?,
+
+        // Bmp
+        EncodingOptions::Bmp(depth) => {
+            let img = match depth {
+                BmpDepth::Rgb24 => image::DynamicImage::ImageRgb8(img.to_rgb8()),
+                BmpDepth::Rgba32 => image::DynamicImage::ImageRgba8(img.to_rgba8()),
+            };
+
+            img.write_with_encoder(BmpEncoder::new(&mut buf))
+                .map_err(ConvertError::from_encoding)?;
+            buf
+        }
+
+        // Jpeg XL
+        #[cfg(feature = "jxl-output")]
+        EncodingOptions::Jxl(options) => crate::util::jxl_output::encode(img, options)
+            .map_err(|e| ConvertError::Encode(e.to_string()))?,
+
+        // Qoi, which only stores 8 bit rgb and rgba
+        EncodingOptions::Qoi => {
+            let rgb = match img.color().has_alpha() {
+                true => image::DynamicImage::ImageRgba8(img.to_rgba8()),
+                false => image::DynamicImage::ImageRgb8(img.to_rgb8()),
+            };
+
+            rgb.write_with_encoder(QoiEncoder::new(&mut buf))
+                .map_err(ConvertError::from_encoding)?;
             buf
         }
     };
@@ -169,7 +1510,39 @@ fn encode_image(img: image::DynamicImage, settings: &Settings) -> Result<Vec<u8>
     Ok(data)
 }
 
-fn save_image(data: &[u8], image_path: &Path, settings: &Settings) -> Result<(), Box<dyn Error>> {
+/// Writes `data` as the output of `image_path`, returning where it went and whether a file
+/// was already there.
+fn save_image(
+    data: &[u8],
+    image_path: &Path,
+    suffix: Option<&str>,
+    settings: &Settings,
+) -> Result<(PathBuf, bool), ConvertError> {
+    let output_file_path = output_path(image_path, suffix, settings)?;
+    let replaced = output_file_path.exists();
+
+    if let Some(output_dir) = output_file_path.parent() {
+        fs::create_dir_all(output_dir).map_err(|e| ConvertError::Write(e.to_string()))?;
+    }
+
+    // Attempt to write the file
+    write_staged(data, &output_file_path, settings).map_err(|e| {
+        eprintln!(
+            "Failed to write output file '{}': {}",
+            output_file_path.display(),
+            e
+        );
+        ConvertError::Write(e.to_string())
+    })?;
+    Ok((output_file_path, replaced))
+}
+
+/// Path the output of `image_path` is written to, `suffix` numbering multi-frame outputs.
+pub fn output_path(
+    image_path: &Path,
+    suffix: Option<&str>,
+    settings: &Settings,
+) -> Result<PathBuf, ConvertError> {
     let mut output_file_name = image_path
         .file_stem()
         .and_then(|stem| stem.to_str())
@@ -178,10 +1551,24 @@ fn save_image(data: &[u8], image_path: &Path, settings: &Settings) -> Result<(),
                 "Failed to extract file stem from '{}'",
                 image_path.display()
             );
-            "Invalid file stem"
+            ConvertError::Write("Invalid file stem".to_string())
         })?
         .to_owned();
 
+    // Merged channel sets are named after the set, without the _r
+    if settings.channels == ChannelMode::Merge && channels::is_merge_source(image_path) {
+        output_file_name = channels::merged_stem(&output_file_name).to_string();
+    }
+
+    // Numbered settings have the number filled in already, `{seq}` left over has none
+    if let Some(pattern) = &settings.rename_pattern {
+        output_file_name = sequence::apply(pattern, &output_file_name, None);
+    }
+
+    if let Some(suffix) = suffix {
+        output_file_name.push_str(suffix);
+    }
+
     if let Some(name_extension) = &settings.name_extension {
         output_file_name.push_str(name_extension);
     }
@@ -190,20 +1577,74 @@ fn save_image(data: &[u8], image_path: &Path, settings: &Settings) -> Result<(),
         EncodingOptions::WebP(_) => ".webp",
         EncodingOptions::Avif(_) => ".avif",
         EncodingOptions::Jpeg(_) => ".jpg",
+        EncodingOptions::Png(_) => ".png",
+        EncodingOptions::Qoi => ".qoi",
+        EncodingOptions::Tiff(_) => ".tif",
+        EncodingOptions::Gif(_) => ".gif",
+        EncodingOptions::Ico(_) => ".ico",
+        EncodingOptions::Bmp(_) => ".bmp",
+        #[cfg(feature = "jxl-output")]
+        EncodingOptions::Jxl(_) => ".jxl",
     };
 
     output_file_name.push_str(extension);
 
-    let output_file_path = Path::new(OUTPUT_FOLDER).join(output_file_name);
+    Ok(output_dir(image_path, settings).join(output_file_name))
+}
 
-    // Attempt to write the file
-    fs::write(&output_file_path, data).map_err(|e| {
-        eprintln!(
-            "Failed to write output file '{}': {}",
-            output_file_path.display(),
-            e
+/// Folder the favicon package of `path` goes in, `suffix` numbering multi-frame sources.
+fn favicon_dir(path: &Path, suffix: &str, settings: &Settings) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    output_dir(path, settings).join(format!("{}{}-favicon", stem, suffix))
+}
+
+/// Paths the first output of `path` in the format of `settings` can have. Multi-frame sources
+/// are numbered from 1 and rendered PDF pages padded to 3 digits, split channels, tiles and
+/// favicon packages are found by their first file.
+pub fn first_outputs(path: &Path, settings: &Settings) -> Vec<PathBuf> {
+    let mut outputs = Vec::new();
+
+    for suffix in ["", "-1", "-page-001"] {
+        if settings.favicon_package {
+            outputs.push(favicon_dir(path, suffix, settings).join(favicon::ICO_FILE));
+            continue;
+        }
+
+        let mut suffixes = match settings.channels {
+            ChannelMode::Split => vec![format!("{}{}", suffix, channels::SUFFIXES[0])],
+            _ => vec![suffix.to_string()],
+        };
+        // Only images too large for the format are tiled, others keep their usual name
+        if settings.split_oversized && settings.channels != ChannelMode::Split {
+            suffixes.push(format!("{}{}", suffix, tiles::FIRST_SUFFIX));
+        }
+
+        outputs.extend(
+            suffixes
+                .iter()
+                .filter_map(|suffix| output_path(path, Some(suffix), settings).ok()),
         );
-        e
-    })?;
-    Ok(())
+    }
+
+    outputs
+}
+
+/// Whether `options` is a format animated GIFs can be converted to without losing frames.
+fn animates(options: &EncodingOptions) -> bool {
+    matches!(options, EncodingOptions::WebP(_) | EncodingOptions::Gif(_))
+}
+
+/// Whether every output format of `path` has an output that already exists and is at least
+/// as new as the source.
+fn is_up_to_date(path: &Path, settings: &Settings) -> bool {
+    let Ok(modified) = fs::metadata(path).and_then(|m| m.modified()) else {
+        return false;
+    };
+
+    settings.per_format().iter().all(|settings| {
+        first_outputs(path, settings)
+            .into_iter()
+            .filter_map(|output| fs::metadata(output).and_then(|m| m.modified()).ok())
+            .any(|output_modified| output_modified >= modified)
+    })
 }
@@ -0,0 +1,239 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{
+    structs::{settings::Settings, update::Converted},
+    util::provenance::Record,
+    OUTPUT_FOLDER,
+};
+
+pub static CATALOG_FILE: &str = "catalog.sqlite";
+
+/// Totals over everything in the catalog.
+pub struct Stats {
+    pub conversions: u64,
+    pub input_size: u64,
+    pub output_size: u64,
+}
+
+/// Embedded database of every conversion, kept in the output folder.
+pub struct Catalog {
+    connection: Connection,
+}
+
+impl Catalog {
+    pub fn open() -> rusqlite::Result<Self> {
+        // Creating the folder is left to sqlite's error when it fails
+        let _ = fs::create_dir_all(OUTPUT_FOLDER);
+
+        let connection = Connection::open(Path::new(OUTPUT_FOLDER).join(CATALOG_FILE))?;
+
+        // Runs share one connection, the verify tool and statistics read next to it
+        connection.busy_timeout(std::time::Duration::from_secs(5))?;
+        connection.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS conversions (
+                id INTEGER PRIMARY KEY,
+                run INTEGER NOT NULL,
+                input TEXT NOT NULL,
+                input_hash TEXT NOT NULL,
+                input_size INTEGER NOT NULL,
+                output TEXT NOT NULL,
+                output_size INTEGER NOT NULL,
+                settings TEXT NOT NULL,
+                converted_at INTEGER NOT NULL,
+                created INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS conversions_input_hash ON conversions (input_hash);
+            CREATE INDEX IF NOT EXISTS conversions_output ON conversions (output);",
+        )?;
+
+        // Older catalogs didn't know which outputs replaced a file, undo leaves those alone
+        if connection
+            .prepare("SELECT created FROM conversions LIMIT 0")
+            .is_err()
+        {
+            connection.execute_batch(
+                "ALTER TABLE conversions ADD COLUMN created INTEGER NOT NULL DEFAULT 0",
+            )?;
+        }
+
+        Ok(Self { connection })
+    }
+
+    /// Stores every output of a successful conversion of `input` as part of `run`. `hash` is
+    /// the content hash of `input`, taken before it was converted.
+    pub fn record(
+        &self,
+        run: u64,
+        input: &Path,
+        hash: Option<blake3::Hash>,
+        converted: &Converted,
+        settings: &Settings,
+    ) -> rusqlite::Result<()> {
+        let hash = hash
+            .map(|hash| hash.to_hex().to_string())
+            .unwrap_or_default();
+        let settings = settings.fingerprint();
+
+        for output in &converted.outputs {
+            self.connection.execute(
+                "INSERT INTO conversions
+                    (run, input, input_hash, input_size, output, output_size, settings,
+                    converted_at, created)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    run as i64,
+                    input.to_string_lossy(),
+                    hash,
+                    converted.input_size as i64,
+                    output.path.to_string_lossy(),
+                    output.size as i64,
+                    settings,
+                    now() as i64,
+                    !output.replaced,
+                ],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether a file with content `hash` was already converted with the same settings,
+    /// and an output of every format still exists.
+    pub fn is_converted(&self, hash: &blake3::Hash, settings: &Settings) -> rusqlite::Result<bool> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT output FROM conversions WHERE input_hash = ?1 AND settings = ?2")?;
+        let outputs = statement
+            .query_map(
+                params![hash.to_hex().to_string(), settings.fingerprint()],
+                |row| row.get::<_, String>(0),
+            )?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+
+        let existing: Vec<&Path> = outputs
+            .iter()
+            .map(Path::new)
+            .filter(|output| output.exists())
+            .collect();
+
+        // Favicon packages are named after the icons, not the output formats
+        if settings.favicon_package {
+            return Ok(!existing.is_empty());
+        }
+
+        Ok(settings.per_format().iter().all(|settings| {
+            let extension = settings.encoding_options.to_string();
+            existing.iter().any(|output| {
+                output
+                    .extension()
+                    .is_some_and(|ext| ext == extension.as_str())
+            })
+        }))
+    }
+
+    /// Every output ever recorded, including ones deleted since.
+    pub fn outputs(&self) -> rusqlite::Result<Vec<PathBuf>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT DISTINCT output FROM conversions")?;
+        let outputs = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+
+        Ok(outputs.into_iter().map(PathBuf::from).collect())
+    }
+
+    /// Latest conversion that wrote `output`.
+    pub fn lookup(&self, output: &Path) -> rusqlite::Result<Option<Record>> {
+        self.connection
+            .query_row(
+                "SELECT converted_at, input, settings FROM conversions
+                WHERE output = ?1 ORDER BY id DESC LIMIT 1",
+                params![output.to_string_lossy()],
+                |row| {
+                    Ok(Record {
+                        time: row.get::<_, i64>(0)? as u64,
+                        source: row.get(1)?,
+                        settings: row.get(2)?,
+                    })
+                },
+            )
+            .optional()
+    }
+
+    pub fn stats(&self) -> rusqlite::Result<Stats> {
+        self.connection.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(input_size), 0), COALESCE(SUM(output_size), 0)
+            FROM conversions",
+            [],
+            |row| {
+                Ok(Stats {
+                    conversions: row.get::<_, i64>(0)? as u64,
+                    input_size: row.get::<_, i64>(1)? as u64,
+                    output_size: row.get::<_, i64>(2)? as u64,
+                })
+            },
+        )
+    }
+
+    /// Outputs the most recent run created, leaving out files that were already there and
+    /// only got overwritten.
+    pub fn last_run_outputs(&self) -> rusqlite::Result<Vec<PathBuf>> {
+        let Some(run) = self.last_run()? else {
+            return Ok(Vec::new());
+        };
+
+        let mut statement = self
+            .connection
+            .prepare("SELECT output FROM conversions WHERE run = ?1 AND created = 1")?;
+        let outputs = statement
+            .query_map(params![run], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+
+        Ok(outputs.into_iter().map(PathBuf::from).collect())
+    }
+
+    /// Deletes the outputs the most recent run created and forgets about the run.
+    ///
+    /// Returns the outputs that were removed.
+    pub fn undo_last_run(&self) -> rusqlite::Result<Vec<PathBuf>> {
+        let Some(run) = self.last_run()? else {
+            return Ok(Vec::new());
+        };
+
+        let removed = self
+            .last_run_outputs()?
+            .into_iter()
+            .filter(|output| fs::remove_file(output).is_ok())
+            .collect();
+
+        self.connection
+            .execute("DELETE FROM conversions WHERE run = ?1", params![run])?;
+
+        Ok(removed)
+    }
+
+    fn last_run(&self) -> rusqlite::Result<Option<i64>> {
+        self.connection
+            .query_row("SELECT MAX(run) FROM conversions", [], |row| row.get(0))
+    }
+}
+
+/// Identifies a run by the time it started, in milliseconds.
+pub fn run_id() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn now() -> u64 {
+    run_id() / 1000
+}
@@ -0,0 +1,63 @@
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub enum ConvertError {
+    Download(String),
+    // The server stopped responding, usually worth retrying later
+    Timeout(String),
+    Decode(String),
+    UnsupportedColor(String),
+    Encode(String),
+    Write(String),
+}
+
+impl ConvertError {
+    pub fn category(&self) -> &'static str {
+        match self {
+            ConvertError::Download(_) => "Download error",
+            ConvertError::Timeout(_) => "Timeout",
+            ConvertError::Decode(_) => "Decode error",
+            ConvertError::UnsupportedColor(_) => "Unsupported color type",
+            ConvertError::Encode(_) => "Encode error",
+            ConvertError::Write(_) => "Write error",
+        }
+    }
+
+    pub fn from_decoding(error: image::ImageError) -> Self {
+        match is_unsupported_color(&error) {
+            true => ConvertError::UnsupportedColor(error.to_string()),
+            false => ConvertError::Decode(error.to_string()),
+        }
+    }
+
+    pub fn from_encoding(error: image::ImageError) -> Self {
+        match is_unsupported_color(&error) {
+            true => ConvertError::UnsupportedColor(error.to_string()),
+            false => ConvertError::Encode(error.to_string()),
+        }
+    }
+}
+
+fn is_unsupported_color(error: &image::ImageError) -> bool {
+    match error {
+        image::ImageError::Unsupported(e) => {
+            matches!(e.kind(), image::error::UnsupportedErrorKind::Color(_))
+        }
+        _ => false,
+    }
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertError::Download(e)
+            | ConvertError::Timeout(e)
+            | ConvertError::Decode(e)
+            | ConvertError::UnsupportedColor(e)
+            | ConvertError::Encode(e)
+            | ConvertError::Write(e) => write!(f, "{}: {}", self.category(), e),
+        }
+    }
+}
+
+impl std::error::Error for ConvertError {}
@@ -1,8 +1,16 @@
-#[derive(Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub enum EncodingOptions {
     Avif(AvifSettings),
     WebP(WebpSettings),
     Jpeg(JpegSettings),
+    Png(PngSettings),
+    Qoi,
+    Tiff(TiffSettings),
+    Gif(GifSettings),
+    Ico(IcoSettings),
+    Bmp(BmpDepth),
+    #[cfg(feature = "jxl-output")]
+    Jxl(JxlSettings),
 }
 
 impl std::fmt::Display for EncodingOptions {
@@ -11,16 +19,101 @@ impl std::fmt::Display for EncodingOptions {
             EncodingOptions::Avif(_) => write!(f, "avif"),
             EncodingOptions::WebP(_) => write!(f, "webp"),
             EncodingOptions::Jpeg(_) => write!(f, "jpg"),
+            EncodingOptions::Png(_) => write!(f, "png"),
+            EncodingOptions::Qoi => write!(f, "qoi"),
+            EncodingOptions::Tiff(_) => write!(f, "tif"),
+            EncodingOptions::Gif(_) => write!(f, "gif"),
+            EncodingOptions::Ico(_) => write!(f, "ico"),
+            EncodingOptions::Bmp(_) => write!(f, "bmp"),
+            #[cfg(feature = "jxl-output")]
+            EncodingOptions::Jxl(_) => write!(f, "jxl"),
+        }
+    }
+}
+
+impl EncodingOptions {
+    /// Largest width and height the format can store, `None` when there's no practical limit.
+    pub fn max_dimension(&self) -> Option<u32> {
+        match self {
+            EncodingOptions::WebP(_) => Some(16383),
+            // Most JPEG readers are built on libjpeg, which stops at 65500
+            EncodingOptions::Jpeg(_) => Some(65500),
+            EncodingOptions::Gif(_) => Some(65535),
+            _ => None,
+        }
+    }
+
+    /// The same format with its default options.
+    pub fn defaults(&self) -> EncodingOptions {
+        match self {
+            EncodingOptions::Avif(_) => EncodingOptions::Avif(AvifSettings::default()),
+            EncodingOptions::WebP(_) => EncodingOptions::WebP(WebpSettings::default()),
+            EncodingOptions::Jpeg(_) => EncodingOptions::Jpeg(JpegSettings::default()),
+            EncodingOptions::Png(_) => EncodingOptions::Png(PngSettings::default()),
+            EncodingOptions::Qoi => EncodingOptions::Qoi,
+            EncodingOptions::Tiff(_) => EncodingOptions::Tiff(TiffSettings::default()),
+            EncodingOptions::Gif(_) => EncodingOptions::Gif(GifSettings::default()),
+            EncodingOptions::Ico(_) => EncodingOptions::Ico(IcoSettings::default()),
+            EncodingOptions::Bmp(_) => EncodingOptions::Bmp(BmpDepth::Rgb24),
+            #[cfg(feature = "jxl-output")]
+            EncodingOptions::Jxl(_) => EncodingOptions::Jxl(JxlSettings::default()),
+        }
+    }
+
+    /// Most bits per channel the format is written with, deeper images are reduced to this.
+    pub fn max_bit_depth(&self) -> u8 {
+        match self {
+            EncodingOptions::WebP(_)
+            | EncodingOptions::Jpeg(_)
+            | EncodingOptions::Qoi
+            | EncodingOptions::Gif(_)
+            | EncodingOptions::Ico(_)
+            | EncodingOptions::Bmp(_) => 8,
+            EncodingOptions::Avif(options) => options.bit_depth.bits(),
+            // Palettes are built from 8 bit colors
+            EncodingOptions::Png(options) if options.quantize => 8,
+            EncodingOptions::Png(_) => 16,
+            EncodingOptions::Tiff(options) if options.sixteen_bit => 16,
+            EncodingOptions::Tiff(_) => 8,
+            #[cfg(feature = "jxl-output")]
+            EncodingOptions::Jxl(_) => 16,
         }
     }
 }
 
 // Avif settings
-#[derive(Clone, PartialEq)]
+// No 12 bit, ravif's `with_depth` asserts the depth is 8 or 10
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+pub enum AvifBitDepth {
+    Eight,
+    Ten,
+}
+
+impl AvifBitDepth {
+    pub fn bits(self) -> u8 {
+        match self {
+            AvifBitDepth::Eight => 8,
+            AvifBitDepth::Ten => 10,
+        }
+    }
+}
+
+impl std::fmt::Display for AvifBitDepth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AvifBitDepth::Eight => write!(f, "8 bit"),
+            AvifBitDepth::Ten => write!(f, "10 bit"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub struct AvifSettings {
     pub quality: u8,
     pub speed: u8,
     pub lossless: bool,
+    // Precision the image is encoded at, 10 bit avoids banding in gradients
+    pub bit_depth: AvifBitDepth,
 }
 
 impl Default for AvifSettings {
@@ -29,13 +122,13 @@ impl Default for AvifSettings {
             quality: 75,
             speed: 3,
             lossless: false,
+            bit_depth: AvifBitDepth::Eight,
         }
     }
 }
 
-
 // Webp settings
-#[derive(Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub struct WebpSettings {
     pub quality: u8,
     pub lossless: bool,
@@ -51,13 +144,217 @@ impl Default for WebpSettings {
 }
 
 // Jpeg settings
-#[derive(Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+pub enum ChromaSubsampling {
+    Yuv444,
+    Yuv422,
+    Yuv420,
+}
+
+impl std::fmt::Display for ChromaSubsampling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChromaSubsampling::Yuv444 => write!(f, "4:4:4"),
+            ChromaSubsampling::Yuv422 => write!(f, "4:2:2"),
+            ChromaSubsampling::Yuv420 => write!(f, "4:2:0"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub struct JpegSettings {
     pub quality: u8,
+    pub thumbnail: bool,
+    pub progressive: bool,
+    // Resolution colour is stored at, relative to brightness
+    pub subsampling: ChromaSubsampling,
+    // Encoded with mozjpeg when the feature is built, `image`'s encoder otherwise
+    pub mozjpeg: bool,
 }
 
 impl Default for JpegSettings {
     fn default() -> Self {
-        Self { quality: 90 }
+        Self {
+            quality: 90,
+            thumbnail: false,
+            progressive: false,
+            subsampling: ChromaSubsampling::Yuv420,
+            mozjpeg: false,
+        }
+    }
+}
+
+// Png settings
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+pub enum PngCompression {
+    Fast,
+    Default,
+    Best,
+}
+
+impl std::fmt::Display for PngCompression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PngCompression::Fast => write!(f, "Fast"),
+            PngCompression::Default => write!(f, "Default"),
+            PngCompression::Best => write!(f, "Best"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct PngSettings {
+    pub compression: PngCompression,
+    pub adaptive_filter: bool,
+    // Reduce to a palette of `colors` colors
+    pub quantize: bool,
+    pub colors: u16,
+}
+
+impl Default for PngSettings {
+    fn default() -> Self {
+        Self {
+            compression: PngCompression::Default,
+            adaptive_filter: true,
+            quantize: false,
+            colors: 256,
+        }
+    }
+}
+
+// Tiff settings
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+pub enum TiffCompression {
+    None,
+    Lzw,
+    Deflate,
+}
+
+impl std::fmt::Display for TiffCompression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TiffCompression::None => write!(f, "None"),
+            TiffCompression::Lzw => write!(f, "LZW"),
+            TiffCompression::Deflate => write!(f, "Deflate"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct TiffSettings {
+    pub compression: TiffCompression,
+    // Keep 16 bit sources at 16 bits per channel
+    pub sixteen_bit: bool,
+}
+
+impl Default for TiffSettings {
+    fn default() -> Self {
+        Self {
+            compression: TiffCompression::Lzw,
+            sixteen_bit: false,
+        }
+    }
+}
+
+// Gif settings
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+pub enum PaletteDither {
+    None,
+    FloydSteinberg,
+    // Bayer pattern, doesn't crawl between animation frames
+    Ordered,
+}
+
+impl std::fmt::Display for PaletteDither {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaletteDither::None => write!(f, "None"),
+            PaletteDither::FloydSteinberg => write!(f, "Floyd-Steinberg"),
+            PaletteDither::Ordered => write!(f, "Ordered"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct GifSettings {
+    pub colors: u16,
+    pub dither: PaletteDither,
+}
+
+impl Default for GifSettings {
+    fn default() -> Self {
+        Self {
+            colors: 256,
+            dither: PaletteDither::FloydSteinberg,
+        }
+    }
+}
+
+// Ico settings
+pub const ICO_SIZES: [u32; 6] = [16, 32, 48, 64, 128, 256];
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct IcoSettings {
+    // Square sizes packed into the icon, from `ICO_SIZES`
+    pub sizes: Vec<u32>,
+}
+
+impl Default for IcoSettings {
+    fn default() -> Self {
+        Self {
+            sizes: ICO_SIZES.to_vec(),
+        }
+    }
+}
+
+// Bmp settings
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+pub enum BmpDepth {
+    Rgb24,
+    // Keeps transparency, not every reader supports it
+    Rgba32,
+}
+
+impl std::fmt::Display for BmpDepth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BmpDepth::Rgb24 => write!(f, "24 bit"),
+            BmpDepth::Rgba32 => write!(f, "32 bit with alpha"),
+        }
+    }
+}
+
+// Jpeg XL settings
+#[cfg(feature = "jxl-output")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct JxlSettings {
+    // Butteraugli distance, 0 is lossless and 1 visually lossless
+    pub distance: f32,
+    pub effort: u8,
+    pub lossless: bool,
+    // Repack JPEG sources without decoding them when nothing else changes
+    pub recompress_jpeg: bool,
+}
+
+// By hand, floats have no `Hash`
+#[cfg(feature = "jxl-output")]
+impl std::hash::Hash for JxlSettings {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.distance.to_bits().hash(state);
+        self.effort.hash(state);
+        self.lossless.hash(state);
+        self.recompress_jpeg.hash(state);
+    }
+}
+
+#[cfg(feature = "jxl-output")]
+impl Default for JxlSettings {
+    fn default() -> Self {
+        Self {
+            distance: 1.0,
+            effort: 7,
+            lossless: false,
+            recompress_jpeg: true,
+        }
     }
 }
@@ -1,3 +1,4 @@
-pub mod settings;
+pub mod error;
 pub mod file_type;
-pub mod update;
\ No newline at end of file
+pub mod settings;
+pub mod update;
@@ -1,28 +1,483 @@
+use std::{
+    hash::{Hash, Hasher},
+    path::PathBuf,
+};
+
+use crate::INPUT_FOLDER;
+
 use super::file_type::{AvifSettings, EncodingOptions};
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct Settings {
     pub encoding_options: EncodingOptions,
+    // Also written from the same decode, e.g. a WebP fallback next to AVIF
+    pub extra_formats: Vec<EncodingOptions>,
     pub resize_options: ResizeOptions,
+    pub linear_resize: bool,
+    pub upscale_filter: ResampleFilter,
+    pub downscale_filter: ResampleFilter,
     pub name_extension: Option<String>,
+    // Replaces the file name, {name} is the original and {seq:0000} a counter
+    pub rename_pattern: Option<String>,
+    pub sequence_start: u64,
+    pub sequence_step: u64,
+    pub sequence_order: SequenceOrder,
     pub keep_exif: bool,
+    pub use_sidecars: bool,
+    // Seconds to move EXIF timestamps by
+    pub time_shift: i64,
+    pub skip_existing: bool,
+    pub skip_duplicates: bool,
+    pub write_manifest: bool,
+    // Every output of a run on its own page of one PDF
+    pub combine_pdf: bool,
+    pub pdf_page: PageSize,
+    // Millimetres
+    pub pdf_margin: u32,
+    // Thumbnails of every converted image tiled into one image
+    pub contact_sheet: bool,
+    pub sheet_columns: u32,
+    // Pixels, the longest side of each thumbnail
+    pub sheet_cell_size: u32,
+    pub sheet_labels: bool,
+    // Every converted image packed into power of two sheets with a JSON index
+    pub sprite_sheet: bool,
+    // Pixels, largest width and height of a sheet
+    pub atlas_size: u32,
+    // Pixels between sprites, keeps filtering from bleeding neighbours in
+    pub atlas_padding: u32,
+    // Posted a summary when a run finishes
+    pub webhook_url: Option<String>,
+    // Total size of the output folders in bytes that watch mode keeps to, 0 for no limit
+    pub output_quota: u64,
+    pub record_provenance: bool,
+    pub use_catalog: bool,
+    pub originals: OriginalsOption,
+    // What happens to files that are already in the target format and size
+    pub passthrough: PassthroughOption,
+    pub input_folders: Vec<InputFolder>,
+    pub symlinks: SymlinkPolicy,
+    pub sort_order: SortOrder,
+    pub file_filter: Option<String>,
+    // Size limits in bytes, 0 for no limit
+    pub min_size: u64,
+    pub max_size: u64,
+    // Unix time in seconds
+    pub modified_after: Option<u64>,
+    pub mpo_all_frames: bool,
+    pub tiff_all_pages: bool,
+    pub icon_all_sizes: bool,
+    #[cfg(feature = "pdf")]
+    pub pdf_mode: PdfMode,
+    #[cfg(feature = "pdf")]
+    pub pdf_dpi: u32,
+    // Longest side SVGs are rendered at
+    pub svg_size: u32,
+    pub fast_interlaced_png: bool,
+    pub verbose: bool,
+    // Where temporary files go, the system temp folder when empty
+    pub scratch_dir: Option<PathBuf>,
+    // Which cores of hybrid CPUs workers run on
+    pub core_affinity: CoreAffinity,
+    pub adaptive_quality: bool,
+    pub dither: bool,
+    pub channels: ChannelMode,
+    // Cut images too large for the output format into tiles instead of failing
+    pub split_oversized: bool,
+    // A folder of standard site icons per input instead of the regular outputs
+    pub favicon_package: bool,
+    // Downscale images too large for the output format until they fit
+    pub fit_to_limit: bool,
+    pub tone_mapping: ToneMapping,
+    // Stops
+    pub exposure: f32,
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Self {
             encoding_options: EncodingOptions::Avif(AvifSettings::default()),
+            extra_formats: Vec::new(),
             resize_options: ResizeOptions::None,
+            linear_resize: false,
+            upscale_filter: ResampleFilter::CatmullRom,
+            downscale_filter: ResampleFilter::Lanczos3,
             name_extension: None,
+            rename_pattern: None,
+            sequence_start: 1,
+            sequence_step: 1,
+            sequence_order: SequenceOrder::Name,
             keep_exif: false,
+            use_sidecars: false,
+            time_shift: 0,
+            skip_existing: false,
+            skip_duplicates: false,
+            write_manifest: false,
+            combine_pdf: false,
+            pdf_page: PageSize::A4,
+            pdf_margin: 10,
+            contact_sheet: false,
+            sheet_columns: 6,
+            sheet_cell_size: 256,
+            sheet_labels: true,
+            sprite_sheet: false,
+            atlas_size: 2048,
+            atlas_padding: 2,
+            webhook_url: None,
+            output_quota: 0,
+            record_provenance: false,
+            use_catalog: false,
+            originals: OriginalsOption::Keep,
+            passthrough: PassthroughOption::Off,
+            input_folders: vec![InputFolder::new(PathBuf::from(INPUT_FOLDER))],
+            symlinks: SymlinkPolicy::Follow,
+            sort_order: SortOrder::Name,
+            file_filter: None,
+            min_size: 0,
+            max_size: 0,
+            modified_after: None,
+            mpo_all_frames: false,
+            tiff_all_pages: false,
+            icon_all_sizes: false,
+            #[cfg(feature = "pdf")]
+            pdf_mode: PdfMode::Rasterize,
+            #[cfg(feature = "pdf")]
+            pdf_dpi: 150,
+            svg_size: 1024,
+            fast_interlaced_png: false,
+            verbose: false,
+            scratch_dir: None,
+            core_affinity: CoreAffinity::Any,
+            adaptive_quality: false,
+            dither: false,
+            channels: ChannelMode::Off,
+            split_oversized: false,
+            favicon_package: false,
+            fit_to_limit: false,
+            tone_mapping: ToneMapping::Aces,
+            exposure: 0.0,
+        }
+    }
+}
+
+impl Settings {
+    /// Everything that decides what gets written, to tell whether an output was written with
+    /// these settings. What only picks the files, steers the run or adds run-wide extras like
+    /// the contact sheet is left out, and so are the per-run sequence numbers.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Fingerprint(blake3::Hasher::new());
+
+        // Format and pixels
+        self.encoding_options.hash(&mut hasher);
+        self.extra_formats.hash(&mut hasher);
+        self.resize_options.hash(&mut hasher);
+        self.linear_resize.hash(&mut hasher);
+        self.upscale_filter.hash(&mut hasher);
+        self.downscale_filter.hash(&mut hasher);
+        self.adaptive_quality.hash(&mut hasher);
+        self.dither.hash(&mut hasher);
+        self.tone_mapping.hash(&mut hasher);
+        self.exposure.to_bits().hash(&mut hasher);
+        self.channels.hash(&mut hasher);
+        self.split_oversized.hash(&mut hasher);
+        self.fit_to_limit.hash(&mut hasher);
+        self.favicon_package.hash(&mut hasher);
+        self.passthrough.hash(&mut hasher);
+
+        // Metadata
+        self.keep_exif.hash(&mut hasher);
+        self.use_sidecars.hash(&mut hasher);
+        self.time_shift.hash(&mut hasher);
+
+        // Names
+        self.name_extension.hash(&mut hasher);
+        self.rename_pattern.hash(&mut hasher);
+
+        // Sources with several frames, pages or sizes
+        self.mpo_all_frames.hash(&mut hasher);
+        self.tiff_all_pages.hash(&mut hasher);
+        self.icon_all_sizes.hash(&mut hasher);
+        self.svg_size.hash(&mut hasher);
+        #[cfg(feature = "pdf")]
+        {
+            self.pdf_mode.hash(&mut hasher);
+            self.pdf_dpi.hash(&mut hasher);
+        }
+
+        hasher.0.finalize().to_hex().to_string()
+    }
+
+    /// A copy that's safe to write to logs and reports, without anything that names files
+    /// or servers.
+    pub fn redacted(&self) -> Settings {
+        let mut settings = self.clone();
+        for folder in &mut settings.input_folders {
+            folder.path = PathBuf::from("<redacted>");
+            folder.output = folder.output.as_ref().map(|_| PathBuf::from("<redacted>"));
+        }
+        settings.scratch_dir = settings.scratch_dir.map(|_| PathBuf::from("<redacted>"));
+        settings.file_filter = settings.file_filter.map(|_| "<redacted>".to_string());
+        settings.webhook_url = settings.webhook_url.map(|_| "<redacted>".to_string());
+
+        settings
+    }
+
+    /// These settings once per output format, the main format first.
+    pub fn per_format(&self) -> Vec<Settings> {
+        let mut formats = vec![self.clone()];
+        // The same format twice would write over its own output
+        let extras = self
+            .extra_formats
+            .iter()
+            .filter(|format| format.to_string() != self.encoding_options.to_string());
+
+        formats.extend(extras.map(|format| Settings {
+            encoding_options: format.clone(),
+            extra_formats: Vec::new(),
+            ..self.clone()
+        }));
+
+        formats
+    }
+}
+
+// Feeds `Hash` implementations into blake3, which unlike the standard hasher stays the same
+// between builds, fingerprints are kept in the catalog
+struct Fingerprint(blake3::Hasher);
+
+impl Hasher for Fingerprint {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let hash = self.0.finalize();
+        u64::from_le_bytes(hash.as_bytes()[..8].try_into().unwrap())
+    }
+}
+
+/// A folder images are read from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputFolder {
+    pub path: PathBuf,
+    pub recursive: bool,
+    // Where its outputs go, the output folder when empty
+    pub output: Option<PathBuf>,
+}
+
+impl InputFolder {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            recursive: false,
+            output: None,
+        }
+    }
+}
+
+/// How symlinked (and on Windows junctioned) files and folders are treated while scanning.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymlinkPolicy {
+    Follow,
+    Skip,
+    // Follow, but queue every file once however many links lead to it
+    Resolve,
+}
+
+impl std::fmt::Display for SymlinkPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SymlinkPolicy::Follow => write!(f, "Follow"),
+            SymlinkPolicy::Skip => write!(f, "Skip"),
+            SymlinkPolicy::Resolve => write!(f, "Resolve and deduplicate"),
+        }
+    }
+}
+
+/// How images are taken from PDF input.
+#[cfg(feature = "pdf")]
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+pub enum PdfMode {
+    // Every page rendered at `pdf_dpi`
+    Rasterize,
+    // The raster images embedded in the pages
+    Extract,
+}
+
+/// Page size of combined PDFs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PageSize {
+    A4,
+    Letter,
+    // Each page the size of its image at 96 dpi
+    Fit,
+}
+
+impl std::fmt::Display for PageSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PageSize::A4 => write!(f, "A4"),
+            PageSize::Letter => write!(f, "Letter"),
+            PageSize::Fit => write!(f, "Fit to image"),
+        }
+    }
+}
+
+/// Operator mapping HDR sources to the 0 to 1 range.
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+pub enum ToneMapping {
+    Clamp,
+    Reinhard,
+    Aces,
+}
+
+impl std::fmt::Display for ToneMapping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ToneMapping::Clamp => write!(f, "Clamp"),
+            ToneMapping::Reinhard => write!(f, "Reinhard"),
+            ToneMapping::Aces => write!(f, "ACES filmic"),
+        }
+    }
+}
+
+/// Utility modes working on the color channels of images, named with `_r`, `_g`, `_b` and `_a` suffixes.
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+pub enum ChannelMode {
+    Off,
+    // An output per channel
+    Split,
+    // One output from a set of channel files
+    Merge,
+}
+
+impl std::fmt::Display for ChannelMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChannelMode::Off => write!(f, "Off"),
+            ChannelMode::Split => write!(f, "Split into channels"),
+            ChannelMode::Merge => write!(f, "Merge channels"),
+        }
+    }
+}
+
+/// What happens to a source file once it has been converted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OriginalsOption {
+    Keep,
+    Move,
+    Delete,
+}
+
+/// How files that converting wouldn't change are written to the output folder.
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+pub enum PassthroughOption {
+    // Re-encoded like every other file
+    Off,
+    Copy,
+    // Falls back to copying across drives
+    HardLink,
+    // Recompressed losslessly, only offered for PNG outputs
+    Optimize,
+}
+
+impl std::fmt::Display for PassthroughOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PassthroughOption::Off => write!(f, "Re-encode"),
+            PassthroughOption::Copy => write!(f, "Copy"),
+            PassthroughOption::HardLink => write!(f, "Hard link"),
+            PassthroughOption::Optimize => write!(f, "Optimize losslessly"),
+        }
+    }
+}
+
+/// Order files are taken from the queue in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    Name,
+    SmallestFirst,
+    LargestFirst,
+    NewestFirst,
+    OldestFirst,
+    Shuffle,
+}
+
+impl std::fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortOrder::Name => write!(f, "Name"),
+            SortOrder::SmallestFirst => write!(f, "Smallest first"),
+            SortOrder::LargestFirst => write!(f, "Largest first"),
+            SortOrder::NewestFirst => write!(f, "Newest first"),
+            SortOrder::OldestFirst => write!(f, "Oldest first"),
+            SortOrder::Shuffle => write!(f, "Shuffle"),
+        }
+    }
+}
+
+/// Cores workers are kept to on CPUs with performance and efficiency cores.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CoreAffinity {
+    Any,
+    Performance,
+    Efficiency,
+}
+
+impl std::fmt::Display for CoreAffinity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CoreAffinity::Any => write!(f, "Any core"),
+            CoreAffinity::Performance => write!(f, "Performance cores"),
+            CoreAffinity::Efficiency => write!(f, "Efficiency cores"),
+        }
+    }
+}
+
+/// Order `{seq}` counts files in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SequenceOrder {
+    Name,
+    DateTaken,
+}
+
+impl std::fmt::Display for SequenceOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SequenceOrder::Name => write!(f, "Name"),
+            SequenceOrder::DateTaken => write!(f, "Date taken"),
+        }
+    }
+}
+
+/// Filter images are resampled with when resizing.
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+pub enum ResampleFilter {
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    Lanczos3,
+}
+
+impl std::fmt::Display for ResampleFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResampleFilter::Nearest => write!(f, "Nearest neighbor"),
+            ResampleFilter::Triangle => write!(f, "Bilinear"),
+            ResampleFilter::CatmullRom => write!(f, "Catmull-Rom"),
+            ResampleFilter::Gaussian => write!(f, "Gaussian"),
+            ResampleFilter::Lanczos3 => write!(f, "Lanczos"),
         }
     }
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub enum ResizeOptions {
     None,
     Largest(u32),
     Exact(u32, u32),
     Smallest(u32),
+    #[cfg(feature = "seam-carving")]
+    SeamCarve(u32, u32),
 }
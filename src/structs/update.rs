@@ -1,8 +1,157 @@
-use std::{path::PathBuf, time::Duration};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use super::error::ConvertError;
+use crate::util::{humanize, stats::FileStats};
 
 pub enum Update {
     Message(String),
-    StartProcessing(PathBuf),
-    FinishedProcessing(PathBuf, bool, Duration),
+    // Files about to be converted, in queue order
+    Queued(Vec<Job>),
+    StartProcessing(JobId),
+    Stage(JobId, Stage),
+    FinishedProcessing(JobId, Result<Converted, ConvertError>, Duration),
+    // A file that wasn't converted, with the reason
+    Skipped(JobId, String),
+    // A file with the same content as the second job, which is converted instead
+    Duplicate(JobId, JobId),
     QueueCompleted(Duration),
 }
+
+/// Identifies a queued file for the rest of the session, so its updates still find it
+/// after the file was renamed or moved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JobId(usize);
+
+impl JobId {
+    fn next() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        JobId(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// A file in the queue, as it was when it was queued.
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: JobId,
+    pub path: PathBuf,
+    // Counter value for the rename pattern, given out when its run starts
+    pub sequence: Option<u64>,
+}
+
+impl Job {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            id: JobId::next(),
+            path,
+            sequence: None,
+        }
+    }
+}
+
+impl AsRef<Path> for Job {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Where a job is in the pipeline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    Queued,
+    Decoding,
+    Resizing,
+    Encoding,
+    Done,
+    Failed,
+    Skipped,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stage::Queued => write!(f, "Queued"),
+            Stage::Decoding => write!(f, "Decoding"),
+            Stage::Resizing => write!(f, "Resizing"),
+            Stage::Encoding => write!(f, "Encoding"),
+            Stage::Done => write!(f, "Done"),
+            Stage::Failed => write!(f, "Failed"),
+            Stage::Skipped => write!(f, "Skipped"),
+        }
+    }
+}
+
+pub enum ScanUpdate {
+    // Newly found files and their total size
+    Found(Vec<PathBuf>, u64),
+    // A subfolder that couldn't be read, the rest of the scan carries on
+    Skipped(PathBuf, String),
+    // Breakdown of files found earlier, sent after them since reading headers takes a while
+    Inspected(FileStats),
+    Failed(String),
+    Completed(Duration),
+}
+
+pub struct Converted {
+    pub input_size: u64,
+    pub output_size: u64,
+    pub outputs: Vec<Asset>,
+    pub timings: Timings,
+    // Linked or copied as is instead of re-encoded
+    pub unchanged: bool,
+}
+
+/// A single file written to the output folder.
+pub struct Asset {
+    pub path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    pub size: u64,
+    pub blurhash: Option<String>,
+    // Overwrote a file that was already there
+    pub replaced: bool,
+}
+
+/// Time spent per pipeline stage, in the order the stages first ran.
+#[derive(Default)]
+pub struct Timings(Vec<(&'static str, Duration)>);
+
+impl Timings {
+    pub fn add(&mut self, stage: &'static str, duration: Duration) {
+        match self.0.iter_mut().find(|(name, _)| *name == stage) {
+            Some((_, total)) => *total += duration,
+            None => self.0.push((stage, duration)),
+        }
+    }
+
+    /// Adds every stage of `other` to these timings.
+    pub fn merge(&mut self, other: Timings) {
+        for (stage, duration) in other.0 {
+            self.add(stage, duration);
+        }
+    }
+
+    /// Runs `f`, adding the time it took to `stage`.
+    pub fn time<T>(&mut self, stage: &'static str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.add(stage, start.elapsed());
+        result
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stages: Vec<String> = self
+            .0
+            .iter()
+            .map(|(stage, duration)| format!("{} {}", stage, humanize::duration(*duration)))
+            .collect();
+
+        write!(f, "{}", stages.join(", "))
+    }
+}
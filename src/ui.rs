@@ -1,39 +1,108 @@
+use std::collections::{BTreeMap, HashSet};
 use std::fmt::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::{
     sync::{
         atomic::{AtomicBool, Ordering},
-        mpsc::channel,
+        mpsc::{channel, TryRecvError},
         Arc,
     },
     thread,
+    time::{Duration, Instant},
 };
 
 use eframe::egui;
 
-use crate::structs::update::Update;
-use crate::util::files::get_files;
+use crate::structs::{
+    error::ConvertError,
+    update::{Converted, Job, JobId, ScanUpdate, Stage, Update},
+};
+use crate::util::{
+    capabilities::Capability,
+    clipboard::paste_image,
+    crash,
+    exif::{civil_from_days, days_from_civil},
+    files::{expand_paths, total_size},
+    humanize,
+    manifest::write_manifest,
+    naming::{check_names, NamingIssues},
+    provenance::inspect,
+    report::{results_table, TableFormat},
+    scan::scan_inputs,
+    scratch::{cleanup, session_dir},
+    sequence,
+    session::Session,
+    share::{self, Share},
+    stats::FileStats,
+    verify::{verify, Finding},
+    watch::watch_folder,
+    webhook::{self, RunSummary},
+};
 use crate::{
-    components::resize::resize_input,
-    process::convert_images,
+    components::{
+        credits::{build_info, credits},
+        file_list::file_list,
+        job_table::{job_table, JobRow},
+        qr::qr_code,
+        resize::resize_input,
+        triage::failure_triage,
+    },
+    process::{convert_images, oversized},
+    storage::Catalog,
     structs::{
-        file_type::{EncodingOptions, JpegSettings, WebpSettings},
-        settings::{ResizeOptions, Settings},
+        file_type::{
+            AvifBitDepth, BmpDepth, ChromaSubsampling, EncodingOptions, GifSettings, IcoSettings,
+            JpegSettings, PaletteDither, PngCompression, PngSettings, TiffCompression,
+            TiffSettings, WebpSettings, ICO_SIZES,
+        },
+        settings::{
+            ChannelMode, CoreAffinity, InputFolder, OriginalsOption, PageSize, PassthroughOption,
+            ResizeOptions, SequenceOrder, Settings, SortOrder, SymlinkPolicy, ToneMapping,
+        },
     },
+    util::pattern::matches,
+    OUTPUT_FOLDER,
 };
 
 const FORBIDDEN_CHARS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
 const LOG_LENGTH: usize = 18;
+const MEGABYTE: f64 = 1024.0 * 1024.0;
+// Seconds between checks for UI state to autosave
+const SESSION_INTERVAL: f64 = 2.0;
 
 #[derive(PartialEq, Clone, Copy)]
 enum Page {
     Home,
+    Files,
     Encoding,
     Export,
     Resize,
     About,
 }
 
+impl Page {
+    // In navigation order
+    const ALL: [Page; 6] = [
+        Page::Home,
+        Page::Files,
+        Page::Resize,
+        Page::Encoding,
+        Page::Export,
+        Page::About,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Page::Home => "Home",
+            Page::Files => "Files",
+            Page::Resize => "Resize",
+            Page::Encoding => "Encoding",
+            Page::Export => "Export",
+            Page::About => "About",
+        }
+    }
+}
+
 pub struct App {
     settings: Settings,
 
@@ -42,13 +111,49 @@ pub struct App {
     // Communication
     stop_flag: Arc<AtomicBool>,
     receiver: Option<std::sync::mpsc::Receiver<Update>>,
+    watch_flag: Arc<AtomicBool>,
+    watch_receiver: Option<std::sync::mpsc::Receiver<Update>>,
+    scan_flag: Arc<AtomicBool>,
+    scan_receiver: Option<std::sync::mpsc::Receiver<ScanUpdate>>,
 
     // Messages
     messages: Vec<String>,
+    filter: String,
+    urls: String,
+    new_folder: String,
+    modified_after: String,
+    inspect_path: String,
+    inspected: Option<String>,
+    // How many outputs undoing the last run would delete, while waiting for the user to confirm
+    undo_count: Option<usize>,
+    // Queued images too large for the output format, asked about before a run starts
+    oversized_receiver: Option<std::sync::mpsc::Receiver<usize>>,
+    oversized_count: Option<usize>,
+    naming: Option<NamingIssues>,
+    // Outputs that need converting again, checked in the background
+    verify_receiver: Option<std::sync::mpsc::Receiver<Vec<Finding>>>,
+    verified: Option<Vec<Finding>>,
+    // Crash report the user hasn't dismissed yet
+    crash_report: Option<PathBuf>,
+    // Output folder served on the local network
+    share: Option<Share>,
+    // Last autosaved UI state and when it was last compared, in seconds since start
+    saved_session: Option<Session>,
+    session_checked: f64,
 
     files: Vec<PathBuf>,
-    success: Vec<PathBuf>,
-    failed: Vec<PathBuf>,
+    excluded: HashSet<PathBuf>,
+    files_size: u64,
+    // Filled in by the scan, shortly after the files themselves
+    stats: FileStats,
+    queue_len: usize,
+    processed: usize,
+    // Ordered like the queue, ids are handed out in queue order
+    jobs: BTreeMap<JobId, JobRow>,
+    success: Vec<(PathBuf, Converted)>,
+    skipped: Vec<PathBuf>,
+    duplicates: Vec<PathBuf>,
+    failed: Vec<(PathBuf, ConvertError)>,
 }
 
 impl Default for App {
@@ -61,153 +166,1657 @@ impl Default for App {
             // Communication
             stop_flag: Arc::new(AtomicBool::new(false)),
             receiver: None,
+            watch_flag: Arc::new(AtomicBool::new(false)),
+            watch_receiver: None,
+            scan_flag: Arc::new(AtomicBool::new(false)),
+            scan_receiver: None,
             messages: Vec::new(),
+            filter: String::new(),
+            urls: String::new(),
+            new_folder: String::new(),
+            modified_after: String::new(),
+            inspect_path: String::new(),
+            inspected: None,
+            undo_count: None,
+            oversized_receiver: None,
+            oversized_count: None,
+            naming: None,
+            verify_receiver: None,
+            verified: None,
+            crash_report: None,
+            share: None,
+            saved_session: None,
+            session_checked: 0.0,
 
-            files: get_files().unwrap(),
+            excluded: HashSet::new(),
+            files_size: 0,
+            stats: FileStats::default(),
+            files: Vec::new(),
+            queue_len: 0,
+            processed: 0,
+            jobs: BTreeMap::new(),
             success: Vec::new(),
+            skipped: Vec::new(),
+            duplicates: Vec::new(),
             failed: Vec::new(),
         }
-    }
-}
+    }
+}
+
+impl App {
+    /// App queueing `paths` instead of the input folder, used when files are opened with the app.
+    pub fn with_paths(paths: Vec<PathBuf>) -> Self {
+        let mut app = Self::default();
+
+        // Before scanning, the filters decide what's found
+        if let Some(session) = Session::load() {
+            app.restore_session(&session);
+            app.saved_session = Some(session);
+        }
+
+        match paths.is_empty() {
+            true => app.rescan(),
+            false => {
+                app.files = expand_paths(&paths, &app.settings);
+                app.files_size = total_size(&app.files);
+            }
+        }
+
+        app.crash_report = crash::take_unseen();
+        app
+    }
+
+    fn session(&self) -> Session {
+        let mut excluded: Vec<PathBuf> = self.excluded.iter().cloned().collect();
+        excluded.sort();
+
+        Session {
+            page: self.page.label().to_string(),
+            filter: self.filter.clone(),
+            file_filter: self.settings.file_filter.clone(),
+            sort_order: self.settings.sort_order,
+            min_size: self.settings.min_size,
+            max_size: self.settings.max_size,
+            modified_after: self.settings.modified_after,
+            excluded,
+        }
+    }
+
+    fn restore_session(&mut self, session: &Session) {
+        if let Some(page) = Page::ALL
+            .into_iter()
+            .find(|page| page.label() == session.page)
+        {
+            self.page = page;
+        }
+        self.filter = session.filter.clone();
+        self.settings.file_filter = session.file_filter.clone();
+        self.settings.sort_order = session.sort_order;
+        self.settings.min_size = session.min_size;
+        self.settings.max_size = session.max_size;
+        self.settings.modified_after = session.modified_after;
+        self.modified_after = session
+            .modified_after
+            .map(|time| {
+                let (year, month, day) = civil_from_days((time / 86400) as i64);
+                format!("{:04}-{:02}-{:02}", year, month, day)
+            })
+            .unwrap_or_default();
+        self.excluded = session.excluded.iter().cloned().collect();
+    }
+
+    /// Writes the UI state to disk when it changed since it was last saved.
+    fn save_session(&mut self) {
+        let session = self.session();
+        if self.saved_session.as_ref() == Some(&session) {
+            return;
+        }
+
+        if let Err(e) = session.save() {
+            eprintln!("Failed to save session: {}", e);
+        }
+        self.saved_session = Some(session);
+    }
+
+    fn stop_processing(&mut self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+    }
+
+    fn handle_completion(&mut self) {
+        self.receiver = None;
+        self.stop_flag.store(false, Ordering::Relaxed);
+
+        // Converted originals may have been moved or deleted
+        if self.settings.originals != OriginalsOption::Keep {
+            self.files.retain(|path| path.exists());
+            self.files_size = total_size(&self.files);
+        }
+    }
+
+    /// Settings are cloned into the worker, so edits wouldn't apply until the next run.
+    fn is_locked(&self) -> bool {
+        self.receiver.is_some() || self.watch_receiver.is_some()
+    }
+
+    fn queued_files(&self) -> Vec<PathBuf> {
+        self.files
+            .iter()
+            .filter(|path| !self.excluded.contains(*path))
+            .cloned()
+            .collect()
+    }
+
+    fn start_processing(&mut self) {
+        self.success.clear();
+        self.skipped.clear();
+        self.duplicates.clear();
+        self.failed.clear();
+
+        // Urls are downloaded by the processing thread
+        let mut files = self.queued_files();
+        files.extend(
+            self.urls
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(PathBuf::from),
+        );
+
+        self.process(files.into_iter().map(Job::new).collect());
+    }
+
+    fn retry(&mut self, files: Vec<PathBuf>) {
+        self.failed.retain(|(path, _)| !files.contains(path));
+        self.process(files.into_iter().map(Job::new).collect());
+    }
+
+    fn process(&mut self, jobs: Vec<Job>) {
+        self.stop_flag.store(false, Ordering::Relaxed);
+        let (sender, receiver) = channel::<Update>();
+        self.receiver = Some(receiver);
+
+        self.messages.clear();
+        self.queue_len = jobs.len();
+        self.processed = 0;
+        self.jobs.clear();
+
+        let settings = self.settings.clone();
+        crash::set_settings(&settings);
+        let stop_flag = Arc::clone(&self.stop_flag);
+
+        thread::spawn(move || {
+            let first_number = settings.sequence_start;
+            convert_images(sender, stop_flag, jobs, settings, first_number);
+        });
+    }
+
+    fn start_watching(&mut self) {
+        // Fresh flag so a watcher that is still shutting down keeps its own
+        self.watch_flag = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = channel::<Update>();
+        self.watch_receiver = Some(receiver);
+
+        let settings = self.settings.clone();
+        crash::set_settings(&settings);
+        let stop_flag = Arc::clone(&self.watch_flag);
+
+        thread::spawn(move || {
+            if let Err(e) = watch_folder(sender.clone(), stop_flag, settings) {
+                sender
+                    .send(Update::Message(format!(
+                        "Failed to watch input folder: {}",
+                        e
+                    )))
+                    .unwrap();
+            }
+        });
+    }
+
+    fn stop_watching(&mut self) {
+        self.watch_flag.store(true, Ordering::Relaxed);
+    }
+
+    fn handle_messages(&mut self) {
+        if let Some(receiver) = &self.verify_receiver {
+            match receiver.try_recv() {
+                Ok(findings) => self.handle_verified(findings),
+                Err(TryRecvError::Disconnected) => self.verify_receiver = None,
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+
+        if let Some(receiver) = &self.oversized_receiver {
+            match receiver.try_recv() {
+                Ok(count) => self.handle_size_check(count),
+                Err(TryRecvError::Disconnected) => self.oversized_receiver = None,
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+
+        // Scans can find thousands of files between frames
+        while let Some(receiver) = &self.scan_receiver {
+            match receiver.try_recv() {
+                Ok(update) => self.handle_scan_update(update),
+                Err(TryRecvError::Disconnected) => self.scan_receiver = None,
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+
+        // Every file sends a few stage updates, so take all of them
+        while let Some(receiver) = &self.receiver {
+            match receiver.try_recv() {
+                Ok(received) => self.handle_update(received, false),
+                // The processing thread died without finishing the queue
+                Err(TryRecvError::Disconnected) => {
+                    self.handle_disconnect();
+                    break;
+                }
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+
+        // Watched files arrive in bursts, each with its own stage updates
+        while let Some(receiver) = &self.watch_receiver {
+            match receiver.try_recv() {
+                Ok(received) => self.handle_update(received, true),
+                // The watcher thread has exited
+                Err(TryRecvError::Disconnected) => self.watch_receiver = None,
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+    }
+
+    /// Fails every job the processing thread left unfinished when it stopped unexpectedly.
+    fn handle_disconnect(&mut self) {
+        let mut unfinished = 0;
+        for row in self.jobs.values_mut() {
+            if !matches!(row.stage, Stage::Done | Stage::Failed | Stage::Skipped) {
+                row.stage = Stage::Failed;
+                row.result = Some("Processing stopped unexpectedly".to_string());
+                unfinished += 1;
+            }
+        }
+
+        self.push_message(format!(
+            "Processing stopped unexpectedly, {} files were left unfinished",
+            humanize::count(unfinished)
+        ));
+        self.handle_completion();
+    }
+
+    /// Path `job` was queued with.
+    fn job_path(&self, job: JobId) -> PathBuf {
+        self.jobs
+            .get(&job)
+            .map(|row| row.path.clone())
+            .unwrap_or_default()
+    }
+
+    fn set_stage(&mut self, job: JobId, stage: Stage) {
+        if let Some(row) = self.jobs.get_mut(&job) {
+            row.stage = stage;
+        }
+    }
+
+    /// How many files are in each active stage, like "2 decoding, 1 encoding".
+    fn stage_summary(&self) -> String {
+        [Stage::Decoding, Stage::Resizing, Stage::Encoding]
+            .into_iter()
+            .map(|stage| {
+                (
+                    stage,
+                    self.jobs.values().filter(|row| row.stage == stage).count(),
+                )
+            })
+            .filter(|(_, count)| *count > 0)
+            .map(|(stage, count)| format!("{} {}", count, stage.to_string().to_lowercase()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn handle_update(&mut self, update: Update, from_watcher: bool) {
+        let message = match update {
+            Update::Queued(jobs) => {
+                for job in jobs {
+                    self.jobs.insert(job.id, JobRow::new(job.path));
+                }
+                return;
+            }
+            Update::Stage(job, stage) => {
+                self.set_stage(job, stage);
+                return;
+            }
+            Update::StartProcessing(job) => {
+                if let Some(row) = self.jobs.get_mut(&job) {
+                    row.started = Some(Instant::now());
+                }
+
+                let path = self.job_path(job);
+                let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                format!("Processing '{}'", file_name)
+            }
+            Update::FinishedProcessing(job, result, duration) => {
+                let path = self.job_path(job);
+                let file_name = path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string();
+                if !from_watcher {
+                    self.processed += 1;
+                }
+                if let Some(row) = self.jobs.get_mut(&job) {
+                    row.elapsed = Some(duration);
+                    row.result = Some(match &result {
+                        Ok(converted) => job_result(converted),
+                        Err(e) => e.to_string(),
+                    });
+                }
+
+                let message = match result {
+                    Ok(converted) => {
+                        let message = format!("Processed '{}'", file_name);
+                        self.set_stage(job, Stage::Done);
+                        self.success.push((path, converted));
+                        message
+                    }
+                    Err(e) => {
+                        let message = format!("Failed to process '{}': {}", file_name, e);
+                        self.set_stage(job, Stage::Failed);
+                        self.failed.push((path, e));
+                        message
+                    }
+                };
+                format!("{} ({})", message, humanize::duration(duration))
+            }
+            Update::Skipped(job, reason) => {
+                let path = self.job_path(job);
+                let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                let message = format!("Skipped '{}', {}", file_name, reason);
+                if !from_watcher {
+                    self.processed += 1;
+                }
+                self.set_stage(job, Stage::Skipped);
+                if let Some(row) = self.jobs.get_mut(&job) {
+                    row.result = Some(reason);
+                }
+                self.skipped.push(path);
+                message
+            }
+            Update::Duplicate(job, original) => {
+                let path = self.job_path(job);
+                let original = self.job_path(original);
+                let message = format!(
+                    "Skipped '{}', same content as '{}'",
+                    path.display(),
+                    original.display()
+                );
+                if !from_watcher {
+                    self.processed += 1;
+                }
+                self.set_stage(job, Stage::Skipped);
+                if let Some(row) = self.jobs.get_mut(&job) {
+                    let file_name = original.file_name().unwrap_or_default().to_string_lossy();
+                    row.result = Some(format!("Same as '{}'", file_name));
+                }
+                self.duplicates.push(path);
+                message
+            }
+            Update::Message(msg) => msg,
+            Update::QueueCompleted(duration) if from_watcher => {
+                self.update_manifest();
+                format!("Completed in {}", humanize::duration(duration))
+            }
+            Update::QueueCompleted(duration) => {
+                let message = match self.stop_flag.load(Ordering::Relaxed) {
+                    true => "Stopped".to_string(),
+                    false => format!("Completed in {}", humanize::duration(duration)),
+                };
+                self.update_manifest();
+                self.notify_webhook(duration);
+                self.handle_completion();
+                message.to_string()
+            }
+        };
+
+        self.push_message(message);
+    }
+
+    fn handle_scan_update(&mut self, update: ScanUpdate) {
+        match update {
+            ScanUpdate::Found(files, size) => {
+                self.files.extend(files);
+                self.files_size += size;
+            }
+            ScanUpdate::Inspected(stats) => self.stats.merge(stats),
+            ScanUpdate::Skipped(path, e) => {
+                self.push_message(format!("Skipped '{}': {}", path.display(), e));
+            }
+            ScanUpdate::Failed(e) => {
+                self.scan_receiver = None;
+                self.push_message(format!("Failed to scan input folder: {}", e));
+            }
+            ScanUpdate::Completed(duration) => {
+                self.scan_receiver = None;
+                self.push_message(format!(
+                    "Found {} files in {}",
+                    humanize::count(self.files.len()),
+                    humanize::duration(duration)
+                ));
+            }
+        }
+    }
+
+    fn update_manifest(&mut self) {
+        if !self.settings.write_manifest || self.success.is_empty() {
+            return;
+        }
+
+        match write_manifest(&self.success) {
+            Ok(path) => self.push_message(format!("Wrote '{}'", path.display())),
+            Err(e) => self.push_message(format!("Failed to write manifest: {}", e)),
+        }
+    }
+
+    fn notify_webhook(&self, duration: Duration) {
+        let Some(url) = self.settings.webhook_url.clone() else {
+            return;
+        };
+
+        let json = RunSummary {
+            success: &self.success,
+            failed: &self.failed,
+            skipped: self.skipped.len(),
+            duplicates: self.duplicates.len(),
+            duration,
+            stopped: self.stop_flag.load(Ordering::Relaxed),
+        }
+        .to_json();
+
+        // Slow endpoints shouldn't hold up the UI
+        thread::spawn(move || {
+            if let Err(e) = webhook::post(&url, &json) {
+                eprintln!("Failed to notify '{}': {}", url, e);
+            }
+        });
+    }
+
+    fn paste_from_clipboard(&mut self) {
+        match paste_image(&session_dir(&self.settings)) {
+            Ok(path) => {
+                self.push_message(format!("Added '{}' from clipboard", path.display()));
+                self.files_size += total_size(std::slice::from_ref(&path));
+                self.files.push(path);
+            }
+            Err(e) => self.push_message(format!("Failed to paste image: {}", e)),
+        }
+    }
+
+    /// Replaces the queue with the contents of the input folders, scanned in the background.
+    fn rescan(&mut self) {
+        // Fresh flag so the scan being replaced stops on its own
+        self.scan_flag.store(true, Ordering::Relaxed);
+        self.scan_flag = Arc::new(AtomicBool::new(false));
+
+        let (sender, receiver) = channel::<ScanUpdate>();
+        self.scan_receiver = Some(receiver);
+        self.files.clear();
+        self.files_size = 0;
+        self.stats = FileStats::default();
+
+        let settings = self.settings.clone();
+        let stop_flag = Arc::clone(&self.scan_flag);
+
+        thread::spawn(move || scan_inputs(sender, stop_flag, settings));
+    }
+
+    fn cancel_scan(&mut self) {
+        self.scan_flag.store(true, Ordering::Relaxed);
+        self.scan_receiver = None;
+        self.push_message(format!(
+            "Scan cancelled after {} files",
+            humanize::count(self.files.len())
+        ));
+    }
+
+    fn check_names(&mut self) {
+        let issues = check_names(&self.queued_files(), &self.settings);
+
+        let message = match issues.is_empty() {
+            true => "No output issues".to_string(),
+            false => format!(
+                "{} output names are shared by several files, {} paths are too long, {} images are too large",
+                issues.collisions.len(),
+                issues.too_long.len(),
+                issues.too_large.len()
+            ),
+        };
+
+        self.push_message(message);
+        self.naming = Some(issues);
+    }
+
+    /// Reads the header of every queued image in the background to find the ones too large for
+    /// the output format, before a run starts.
+    fn check_sizes(&mut self) {
+        let (sender, receiver) = channel::<usize>();
+        self.oversized_receiver = Some(receiver);
+
+        let files = self.queued_files();
+        let settings = self.settings.clone();
+
+        thread::spawn(move || {
+            let _ = sender.send(oversized(&files, &settings).len());
+        });
+    }
+
+    fn handle_size_check(&mut self, count: usize) {
+        self.oversized_receiver = None;
+
+        match count {
+            0 => self.start_processing(),
+            count => self.oversized_count = Some(count),
+        }
+    }
+
+    /// Reads every expected output in the background to find the ones a crash or full disk
+    /// left missing or broken.
+    fn verify_outputs(&mut self) {
+        let (sender, receiver) = channel::<Vec<Finding>>();
+        self.verify_receiver = Some(receiver);
+        self.verified = None;
+
+        let files = self.queued_files();
+        let settings = self.settings.clone();
+
+        thread::spawn(move || {
+            let _ = sender.send(verify(&files, &settings));
+        });
+    }
+
+    fn handle_verified(&mut self, findings: Vec<Finding>) {
+        self.verify_receiver = None;
+
+        let message = match findings.is_empty() {
+            true => "Every output is complete".to_string(),
+            false => format!("{} outputs need converting again", findings.len()),
+        };
+        self.push_message(message);
+        self.verified = Some(findings);
+    }
+
+    /// Converts the sources of broken outputs again, even when their output looks up to date.
+    fn repair(&mut self) {
+        let Some(findings) = self.verified.take() else {
+            return;
+        };
+
+        // Numbered as in the whole queue, not counted again from the start for just these
+        let jobs = findings
+            .into_iter()
+            .map(|finding| Job {
+                sequence: finding.sequence,
+                ..Job::new(finding.source)
+            })
+            .collect();
+        let skip_existing = std::mem::replace(&mut self.settings.skip_existing, false);
+        self.process(jobs);
+        self.settings.skip_existing = skip_existing;
+    }
+
+    fn push_message(&mut self, message: String) {
+        crash::log(&message);
+        self.messages.push(message);
+
+        if self.messages.len() > LOG_LENGTH {
+            self.messages.remove(0);
+        }
+    }
+
+    // Pages
+    fn home_page(&mut self, ui: &mut egui::Ui) {
+        // Encoding
+        let mut summary = String::new();
+
+        write!(
+            summary,
+            "Your images will be saved as {}{}.{}",
+            self.settings.rename_pattern.as_deref().unwrap_or("{name}"),
+            self.settings.name_extension.as_deref().unwrap_or(""),
+            self.settings.encoding_options
+        )
+        .unwrap();
+
+        // Resize options
+        let resize_options = match self.settings.resize_options {
+            ResizeOptions::None => "with their original resolution".to_string(),
+            ResizeOptions::Largest(size) => {
+                format!("and will be resized to {}px on the largest dimension", size)
+            }
+            ResizeOptions::Smallest(size) => {
+                format!(
+                    "and will be resized to {}px on the smallest dimension",
+                    size
+                )
+            }
+            ResizeOptions::Exact(width, height) => {
+                format!("and will be resized to {}px by {}px", width, height)
+            }
+            #[cfg(feature = "seam-carving")]
+            ResizeOptions::SeamCarve(width, height) => {
+                format!("and will be retargeted to {}px by {}px", width, height)
+            }
+        };
+
+        write!(summary, ", {}.", resize_options).unwrap();
+
+        ui.heading("Summary");
+        ui.label(summary);
+
+        ui.add_space(8.0);
+
+        ui.heading("Files");
+        ui.horizontal(|ui| {
+            ui.label("Filter");
+            ui.text_edit_singleline(&mut self.filter)
+                .on_hover_text("Filter by name, use * and ? as wildcards");
+
+            if ui
+                .button("Paste image")
+                .on_hover_text("Add the image on the clipboard to the queue (Ctrl+V)")
+                .clicked()
+            {
+                self.paste_from_clipboard();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} files queued ({} total)",
+                humanize::count(self.files.len()),
+                humanize::size(self.files_size)
+            ));
+
+            if ui
+                .add_enabled(self.receiver.is_none(), egui::Button::new("Rescan"))
+                .on_hover_text("Look for new files in the input folder")
+                .clicked()
+            {
+                self.rescan();
+            }
+
+            if ui
+                .button("Check outputs")
+                .on_hover_text("Lists files that would overwrite each other's output, get paths too long for Windows or be too large for the output format")
+                .clicked()
+            {
+                self.check_names();
+            }
+
+            if ui
+                .add_enabled(
+                    self.verify_receiver.is_none() && self.receiver.is_none(),
+                    egui::Button::new("Verify outputs"),
+                )
+                .on_hover_text("Reads every output to find missing, empty, broken or outdated ones, after a crash or a full disk")
+                .clicked()
+            {
+                self.verify_outputs();
+            }
+
+            if self.verify_receiver.is_some() {
+                ui.spinner();
+            }
+
+            if self.scan_receiver.is_some() {
+                ui.spinner();
+                ui.label("Scanning");
+
+                if ui.button("Cancel").clicked() {
+                    self.cancel_scan();
+                }
+            }
+        });
+
+        egui::CollapsingHeader::new(format!("Queue ({})", self.files.len())).show(ui, |ui| {
+            file_list(ui, "queue", self.files.iter(), &self.filter)
+        });
+
+        if !self.stats.types.is_empty() {
+            egui::CollapsingHeader::new("Queue breakdown").show(ui, |ui| {
+                for (file_type, (count, size)) in &self.stats.types {
+                    ui.label(format!("{} {}, {}", humanize::count(*count), file_type, humanize::size(*size)));
+                }
+
+                if self.stats.with_alpha > 0 {
+                    ui.label(format!("{} with transparency", humanize::count(self.stats.with_alpha)));
+                }
+                if self.stats.large > 0 {
+                    ui.label(format!("{} over 20 megapixels", humanize::count(self.stats.large)))
+                        .on_hover_text("These take a lot of memory and time, consider resizing them");
+                }
+                if self.stats.unknown > 0 {
+                    ui.label(format!("{} need a special decoder", humanize::count(self.stats.unknown)))
+                        .on_hover_text("RAW, HEIC, PDF, SVG and similar files, these fail when their feature isn't built in");
+                }
+            });
+        }
+
+        if let Some(naming) = self.naming.as_ref().filter(|naming| !naming.is_empty()) {
+            egui::CollapsingHeader::new(format!(
+                "Output issues ({})",
+                naming.collisions.len() + naming.too_long.len() + naming.too_large.len()
+            ))
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .id_salt("naming")
+                    .max_height(120.0)
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        for (output, sources) in &naming.collisions {
+                            let sources: Vec<String> = sources
+                                .iter()
+                                .map(|source| source.display().to_string())
+                                .collect();
+                            ui.label(format!("{} ← {}", output.display(), sources.join(", ")));
+                        }
+
+                        for output in &naming.too_long {
+                            ui.label(format!("{} (path too long)", output.display()));
+                        }
+
+                        for (source, width, height) in &naming.too_large {
+                            ui.label(format!(
+                                "{} ({}x{}, too large for {})",
+                                source.display(),
+                                width,
+                                height,
+                                self.settings.encoding_options
+                            ));
+                        }
+                    });
+            });
+        }
+
+        let mut repair = false;
+        if let Some(verified) = self
+            .verified
+            .as_ref()
+            .filter(|verified| !verified.is_empty())
+        {
+            egui::CollapsingHeader::new(format!("Broken outputs ({})", verified.len())).show(
+                ui,
+                |ui| {
+                    egui::ScrollArea::vertical()
+                        .id_salt("verified")
+                        .max_height(120.0)
+                        .auto_shrink([false, true])
+                        .show(ui, |ui| {
+                            for finding in verified {
+                                ui.label(format!(
+                                    "{} ({})",
+                                    finding.output.display(),
+                                    finding.problem
+                                ))
+                                .on_hover_text(finding.source.display().to_string());
+                            }
+                        });
+
+                    let button = egui::Button::new(format!("Convert {} again", verified.len()));
+                    repair = ui.add_enabled(self.receiver.is_none(), button).clicked();
+                },
+            );
+        }
+        if repair {
+            self.repair();
+        }
+
+        egui::CollapsingHeader::new("Download from URLs").show(ui, |ui| {
+            ui.add(
+                egui::TextEdit::multiline(&mut self.urls)
+                    .hint_text("One image URL per line")
+                    .desired_rows(3)
+                    .desired_width(f32::INFINITY),
+            );
+        });
+
+        if !self.success.is_empty() {
+            egui::CollapsingHeader::new(format!("Converted ({})", self.success.len())).show(
+                ui,
+                |ui| {
+                    let files = self.success.iter().map(|(path, _)| path);
+                    file_list(ui, "success", files, &self.filter)
+                },
+            );
+        }
+
+        if !self.skipped.is_empty() {
+            egui::CollapsingHeader::new(format!("Skipped ({})", self.skipped.len()))
+                .show(ui, |ui| {
+                    file_list(ui, "skipped", self.skipped.iter(), &self.filter)
+                });
+        }
+
+        if !self.duplicates.is_empty() {
+            egui::CollapsingHeader::new(format!("Duplicates ({})", self.duplicates.len()))
+                .show(ui, |ui| {
+                    file_list(ui, "duplicates", self.duplicates.iter(), &self.filter)
+                });
+        }
+
+        if !self.success.is_empty() || !self.failed.is_empty() {
+            ui.horizontal(|ui| {
+                for (label, format) in [
+                    ("Copy as Markdown", TableFormat::Markdown),
+                    ("Copy as CSV", TableFormat::Csv),
+                ] {
+                    if ui.button(label).clicked() {
+                        let table = results_table(
+                            &self.success,
+                            &self.failed,
+                            format,
+                            self.settings.verbose,
+                        );
+                        ui.ctx().copy_text(table);
+                    }
+                }
+            });
+        }
+
+        if !self.failed.is_empty() {
+            let can_retry = self.receiver.is_none();
+            let retry = egui::CollapsingHeader::new(format!("Failed ({})", self.failed.len()))
+                .show(ui, |ui| {
+                    failure_triage(ui, &self.failed, &self.filter, can_retry)
+                })
+                .body_returned
+                .flatten();
+
+            if let Some(files) = retry {
+                self.retry(files);
+            }
+        }
+
+        // Share
+        ui.horizontal(|ui| {
+            let mut sharing = self.share.is_some();
+            if ui
+                .toggle_value(&mut sharing, "Share on network")
+                .on_hover_text("Serves the images in the output folder read-only to phones and computers on the same network, under a link that can't be guessed")
+                .changed()
+            {
+                self.share = None;
+                if sharing {
+                    match share::start(PathBuf::from(OUTPUT_FOLDER)) {
+                        Ok(share) => self.share = Some(share),
+                        Err(e) => self.push_message(format!("Failed to share output folder: {}", e)),
+                    }
+                }
+            }
+
+            if let Some(share) = &self.share {
+                ui.hyperlink(&share.url);
+            }
+        });
+        if let Some(share) = &self.share {
+            qr_code(ui, &share.url, 128.0);
+        }
+
+        ui.add_space(8.0);
+
+        if !self.jobs.is_empty() {
+            ui.heading("Progress");
+            job_table(ui, &self.jobs, &self.filter);
+            ui.add_space(8.0);
+        }
+
+        // Diagnostics
+        egui::CollapsingHeader::new("Log")
+            .default_open(self.jobs.is_empty())
+            .show(ui, |ui| {
+                ui.add_enabled(
+                    !self.is_locked(),
+                    egui::Checkbox::new(&mut self.settings.verbose, "Verbose"),
+                )
+                .on_hover_text("Log how long each stage took per file");
+                ui.label(self.messages.join("\n"));
+            });
+    }
+
+    fn files_page(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Files");
+
+        egui::CollapsingHeader::new("Discovery").show(ui, |ui| self.discovery_filters(ui));
+
+        egui::CollapsingHeader::new("Optional formats").show(ui, |ui| {
+            for capability in Capability::ALL {
+                match capability.unavailable_reason() {
+                    None => ui.label(format!("✔ {}", capability)),
+                    Some(reason) => ui
+                        .add_enabled(false, egui::Label::new(format!("✖ {}", capability)))
+                        .on_disabled_hover_text(reason),
+                };
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Filter");
+            ui.text_edit_singleline(&mut self.filter)
+                .on_hover_text("Filter by name, use * and ? as wildcards");
+        });
+
+        let shown: Vec<&PathBuf> = self
+            .files
+            .iter()
+            .filter(|path| {
+                let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                matches(&self.filter, &file_name)
+            })
+            .collect();
+
+        let selected = self
+            .files
+            .iter()
+            .filter(|path| !self.excluded.contains(*path))
+            .count();
+
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} of {} files selected",
+                selected,
+                self.files.len()
+            ));
+
+            // Only affects the files matching the filter
+            if ui.button("Select all").clicked() {
+                for path in &shown {
+                    self.excluded.remove(*path);
+                }
+            }
+
+            if ui.button("Select none").clicked() {
+                self.excluded
+                    .extend(shown.iter().map(|path| (*path).clone()));
+            }
+        });
+
+        ui.add_space(4.0);
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show_rows(
+                ui,
+                ui.spacing().interact_size.y,
+                shown.len(),
+                |ui, range| {
+                    for path in &shown[range] {
+                        let mut included = !self.excluded.contains(*path);
+
+                        if ui
+                            .checkbox(&mut included, path.display().to_string())
+                            .changed()
+                        {
+                            match included {
+                                true => self.excluded.remove(*path),
+                                false => self.excluded.insert((*path).clone()),
+                            };
+                        }
+                    }
+                },
+            );
+    }
+
+    fn discovery_filters(&mut self, ui: &mut egui::Ui) {
+        let mut rescan = false;
+
+        // Sizes are edited in MB
+        for (label, size) in [
+            ("Minimum size", &mut self.settings.min_size),
+            ("Maximum size", &mut self.settings.max_size),
+        ] {
+            let mut megabytes = *size as f64 / MEGABYTE;
+
+            ui.horizontal(|ui| {
+                ui.label(label);
+                let response = ui
+                    .add(
+                        egui::DragValue::new(&mut megabytes)
+                            .range(0.0..=f64::MAX)
+                            .speed(0.1)
+                            .max_decimals(1)
+                            .suffix(" MB"),
+                    )
+                    .on_hover_text("0 for no limit");
+                rescan |= response.drag_stopped() || response.lost_focus();
+            });
+
+            *size = (megabytes * MEGABYTE).round() as u64;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Modified after");
+            let response = ui
+                .add(egui::TextEdit::singleline(&mut self.modified_after).hint_text("YYYY-MM-DD"));
+
+            if response.lost_focus() {
+                let text = self.modified_after.trim().to_string();
+                self.settings.modified_after = match parse_date(&text) {
+                    Some(time) => Some(time),
+                    None if text.is_empty() => None,
+                    None => {
+                        self.push_message(format!("'{}' is not a valid date", text));
+                        None
+                    }
+                };
+                rescan = true;
+            }
+        });
+
+        if rescan {
+            self.rescan();
+        }
+    }
+
+    fn export_page(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Export options");
+        ui.horizontal(|ui| {
+            ui.label("Name extension");
+            ui.text_edit_singleline(self.settings.name_extension.get_or_insert_with(String::new));
+        });
+
+        // Remove forbidden characters
+        if let Some(extension) = self.settings.name_extension.take() {
+            let mut cleaned = extension.trim().to_string();
+
+            for c in FORBIDDEN_CHARS {
+                cleaned.retain(|x| x != *c);
+            }
+
+            self.settings.name_extension = match cleaned.is_empty() {
+                true => None,
+                false => Some(cleaned),
+            };
+        }
+
+        // Renaming
+        ui.horizontal(|ui| {
+            let mut pattern = self.settings.rename_pattern.clone().unwrap_or_default();
+
+            ui.label("Rename to");
+            if ui
+                .add(egui::TextEdit::singleline(&mut pattern).hint_text("holiday_{seq:0000}"))
+                .on_hover_text("Replaces file names, {name} is the original name and {seq:0000} a counter padded to as many digits as there are zeros")
+                .changed()
+            {
+                // Colons are only allowed inside placeholders
+                let mut in_placeholder = false;
+                pattern.retain(|c| {
+                    match c {
+                        '{' => in_placeholder = true,
+                        '}' => in_placeholder = false,
+                        _ => {}
+                    }
+                    !FORBIDDEN_CHARS.contains(&c) || (c == ':' && in_placeholder)
+                });
+
+                self.settings.rename_pattern = match pattern.trim().is_empty() {
+                    true => None,
+                    false => Some(pattern.trim().to_string()),
+                };
+            }
+        });
+
+        if let Some(pattern) = &self.settings.rename_pattern {
+            if !sequence::is_valid(pattern) {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    "The pattern needs {name} or {seq}, otherwise every file gets the same name",
+                );
+            }
+        }
+
+        ui.add_enabled_ui(self.settings.rename_pattern.is_some(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Count from");
+                ui.add(egui::DragValue::new(&mut self.settings.sequence_start));
+                ui.label("in steps of");
+                ui.add(egui::DragValue::new(&mut self.settings.sequence_step).range(1..=1000));
+                egui::ComboBox::from_label("ordered by")
+                    .selected_text(self.settings.sequence_order.to_string())
+                    .show_ui(ui, |ui| {
+                        for order in [SequenceOrder::Name, SequenceOrder::DateTaken] {
+                            ui.selectable_value(
+                                &mut self.settings.sequence_order,
+                                order,
+                                order.to_string(),
+                            );
+                        }
+                    });
+            });
+        });
+
+        // Incremental
+        ui.checkbox(&mut self.settings.skip_existing, "Skip existing outputs")
+            .on_hover_text(
+                "Don't convert files whose output already exists and is newer than the source",
+            );
+
+        // Quota
+        ui.horizontal(|ui| {
+            let mut megabytes = self.settings.output_quota as f64 / MEGABYTE;
+
+            ui.label("Output quota");
+            ui.add(
+                egui::DragValue::new(&mut megabytes)
+                    .range(0.0..=f64::MAX)
+                    .speed(1.0)
+                    .max_decimals(0)
+                    .suffix(" MB"),
+            )
+            .on_hover_text("While watching, the oldest outputs are deleted to stay under this size. Only outputs listed in the catalog or manifest are deleted. 0 for no limit");
+
+            self.settings.output_quota = (megabytes * MEGABYTE).round() as u64;
+        });
+
+        // Provenance
+        ui.checkbox(
+            &mut self.settings.record_provenance,
+            "Record settings per output",
+        )
+        .on_hover_text(
+            "Logs the exact settings used for every output so deliveries can be reproduced",
+        );
+
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.inspect_path).hint_text("Output file"));
+
+            if ui.button("Inspect").clicked() {
+                let path = Path::new(self.inspect_path.trim());
+
+                // The catalog also knows outputs written before the log was turned on
+                let record = match self.settings.use_catalog {
+                    true => match Catalog::open().and_then(|catalog| catalog.lookup(path)) {
+                        Ok(Some(record)) => Ok(Some(record)),
+                        Ok(None) => inspect(path).map_err(|e| e.to_string()),
+                        Err(e) => Err(e.to_string()),
+                    },
+                    false => inspect(path).map_err(|e| e.to_string()),
+                };
+
+                self.inspected = Some(match record {
+                    Ok(Some(record)) => format!(
+                        "Written at {} (unix time) from {}\n{}",
+                        record.time, record.source, record.settings
+                    ),
+                    Ok(None) => "No settings were recorded for this file".to_string(),
+                    Err(e) => format!("Failed to look up provenance: {}", e),
+                });
+            }
+        });
+
+        if let Some(inspected) = &self.inspected {
+            ui.label(inspected);
+        }
+
+        // Catalog
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.settings.use_catalog, "Keep a catalog")
+                .on_hover_text("Stores every conversion in a database in the output folder, skip existing outputs then also recognizes renamed files");
+
+            if ui.button("Statistics").clicked() {
+                let message = match Catalog::open().and_then(|catalog| catalog.stats()) {
+                    Ok(stats) => format!(
+                        "{} conversions, {} in, {} out",
+                        stats.conversions,
+                        humanize::size(stats.input_size),
+                        humanize::size(stats.output_size)
+                    ),
+                    Err(e) => format!("Failed to read catalog: {}", e),
+                };
+                self.push_message(message);
+            }
+
+            if ui.button("Undo last run").on_hover_text("Deletes the outputs the latest cataloged run created, files it overwrote are kept").clicked() {
+                match Catalog::open().and_then(|catalog| catalog.last_run_outputs()) {
+                    Ok(outputs) => self.undo_count = Some(outputs.len()),
+                    Err(e) => self.push_message(format!("Failed to read catalog: {}", e)),
+                }
+            }
+        });
+
+        if let Some(count) = self.undo_count {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "Delete the {} outputs the last run created?",
+                    humanize::count(count)
+                ));
+
+                if ui.button("Delete").clicked() {
+                    let message = match Catalog::open().and_then(|catalog| catalog.undo_last_run())
+                    {
+                        Ok(removed) => {
+                            format!("Removed {} outputs", humanize::count(removed.len()))
+                        }
+                        Err(e) => format!("Failed to undo: {}", e),
+                    };
+                    self.push_message(message);
+                    self.undo_count = None;
+                }
+                if ui.button("Cancel").clicked() {
+                    self.undo_count = None;
+                }
+            });
+        }
+
+        // Duplicates
+        ui.checkbox(&mut self.settings.skip_duplicates, "Skip duplicate files")
+            .on_hover_text("Only converts the first of several byte-identical input files");
+
+        // Manifest
+        ui.checkbox(&mut self.settings.write_manifest, "Write manifest.json")
+            .on_hover_text(
+                "Lists every output with its size, format and blurhash for static site generators",
+            );
+
+        // Combined PDF
+        ui.checkbox(
+            &mut self.settings.combine_pdf,
+            "Combine outputs into one PDF",
+        )
+        .on_hover_text(
+            "Puts every converted image on its own page of output/combined.pdf, in queue order",
+        );
+        ui.add_enabled_ui(self.settings.combine_pdf, |ui| {
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_label("Page size")
+                    .selected_text(self.settings.pdf_page.to_string())
+                    .show_ui(ui, |ui| {
+                        for page in [PageSize::A4, PageSize::Letter, PageSize::Fit] {
+                            ui.selectable_value(
+                                &mut self.settings.pdf_page,
+                                page,
+                                page.to_string(),
+                            );
+                        }
+                    });
+                ui.add(
+                    egui::DragValue::new(&mut self.settings.pdf_margin)
+                        .range(0..=50)
+                        .suffix(" mm"),
+                );
+                ui.label("Margin");
+            });
+        });
+
+        // Contact sheet
+        ui.checkbox(&mut self.settings.contact_sheet, "Build a contact sheet")
+            .on_hover_text("Tiles a thumbnail of every converted image into output/contact-sheet.png, in queue order");
+        ui.add_enabled_ui(self.settings.contact_sheet, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut self.settings.sheet_columns).range(1..=32));
+                ui.label("Columns");
+                ui.add(
+                    egui::DragValue::new(&mut self.settings.sheet_cell_size)
+                        .range(32..=1024)
+                        .suffix(" px"),
+                );
+                ui.label("Cell size");
+                ui.checkbox(&mut self.settings.sheet_labels, "Filenames");
+            });
+        });
+
+        // Sprite sheet
+        ui.checkbox(&mut self.settings.sprite_sheet, "Pack a sprite sheet")
+            .on_hover_text("Packs every converted image into output/atlas-0.png and onwards, with their coordinates in output/atlas.json");
+        ui.add_enabled_ui(self.settings.sprite_sheet, |ui| {
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_label("Sheet size")
+                    .selected_text(format!("{} px", self.settings.atlas_size))
+                    .show_ui(ui, |ui| {
+                        for size in [256, 512, 1024, 2048, 4096, 8192] {
+                            ui.selectable_value(
+                                &mut self.settings.atlas_size,
+                                size,
+                                format!("{} px", size),
+                            );
+                        }
+                    });
+                ui.add(
+                    egui::DragValue::new(&mut self.settings.atlas_padding)
+                        .range(0..=16)
+                        .suffix(" px"),
+                );
+                ui.label("Padding");
+            });
+        });
+
+        // Webhook
+        ui.horizontal(|ui| {
+            let mut url = self.settings.webhook_url.clone().unwrap_or_default();
+
+            ui.label("Notify URL");
+            if ui
+                .add(egui::TextEdit::singleline(&mut url).hint_text("https://ntfy.sh/my-topic"))
+                .on_hover_text("Posts a JSON summary to this URL when a run finishes")
+                .changed()
+            {
+                self.settings.webhook_url = match url.trim().is_empty() {
+                    true => None,
+                    false => Some(url.trim().to_string()),
+                };
+            }
+        });
+
+        // Originals
+        egui::ComboBox::from_label("After converting")
+            .selected_text(match self.settings.originals {
+                OriginalsOption::Keep => "Keep originals",
+                OriginalsOption::Move => "Move originals to subfolder",
+                OriginalsOption::Delete => "Delete originals",
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(
+                    &mut self.settings.originals,
+                    OriginalsOption::Keep,
+                    "Keep originals",
+                );
+                ui.selectable_value(
+                    &mut self.settings.originals,
+                    OriginalsOption::Move,
+                    "Move originals to subfolder",
+                )
+                .on_hover_text("Moves each converted file into an 'originals' folder next to it");
+                ui.selectable_value(
+                    &mut self.settings.originals,
+                    OriginalsOption::Delete,
+                    "Delete originals",
+                );
+            });
+
+        // Passthrough
+        // Only PNGs have a lossless recompressor, JPEG and WebP files would just be copied
+        let can_optimize = matches!(self.settings.encoding_options, EncodingOptions::Png(_));
+        if !can_optimize && self.settings.passthrough == PassthroughOption::Optimize {
+            self.settings.passthrough = PassthroughOption::Copy;
+        }
+
+        egui::ComboBox::from_label("Files already in the target format")
+            .selected_text(self.settings.passthrough.to_string())
+            .show_ui(ui, |ui| {
+                for option in [
+                    PassthroughOption::Off,
+                    PassthroughOption::Copy,
+                    PassthroughOption::HardLink,
+                ] {
+                    ui.selectable_value(&mut self.settings.passthrough, option, option.to_string());
+                }
+
+                ui.add_enabled_ui(can_optimize, |ui| {
+                    ui.selectable_value(
+                        &mut self.settings.passthrough,
+                        PassthroughOption::Optimize,
+                        PassthroughOption::Optimize.to_string(),
+                    )
+                })
+                .response
+                .on_disabled_hover_text("Only PNG outputs can be recompressed losslessly, files in other formats can be copied or hard linked instead");
+            })
+            .response
+            .on_hover_text("Files with the target format and size that need no edits can be copied or hard linked instead of re-encoded. Optimizing recompresses PNGs without losing anything");
+
+        // Exif
+        ui.add(egui::Checkbox::new(
+            &mut self.settings.keep_exif,
+            "Keep EXIF data",
+        ))
+        .on_hover_text("Copies the EXIF data of JPEG and PNG sources into JPEG outputs");
+
+        ui.checkbox(&mut self.settings.use_sidecars, "Apply .xmp sidecar edits")
+            .on_hover_text("Applies the crop and orientation from Lightroom or darktable sidecars next to the source");
+
+        ui.add_enabled_ui(self.settings.keep_exif, |ui| {
+            let mut hours = self.settings.time_shift / 3600;
+            let mut minutes = self.settings.time_shift % 3600 / 60;
+
+            ui.horizontal(|ui| {
+                ui.label("Shift timestamps by");
+                ui.add(
+                    egui::DragValue::new(&mut hours)
+                        .range(-8760..=8760)
+                        .suffix(" h"),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut minutes)
+                        .range(-59..=59)
+                        .suffix(" min"),
+                );
+            })
+            .response
+            .on_hover_text("Corrects the date taken when the camera clock or time zone was wrong");
+
+            self.settings.time_shift = hours * 3600 + minutes * 60;
+        });
+
+        ui.add_space(8.0);
+        ui.heading("Input");
+
+        // Input folders
+        let mut rescan = false;
+        let mut removed = None;
+
+        for (i, folder) in self.settings.input_folders.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.small_button("x").on_hover_text("Remove folder").clicked() {
+                    removed = Some(i);
+                }
+                ui.label(folder.path.display().to_string());
+
+                rescan |= ui
+                    .checkbox(&mut folder.recursive, "Subfolders")
+                    .on_hover_text("Converts images in subfolders and recreates the folder structure in the output folder")
+                    .changed();
+
+                let mut output = folder
+                    .output
+                    .as_ref()
+                    .map(|output| output.display().to_string())
+                    .unwrap_or_default();
+
+                ui.label("→");
+                if ui
+                    .add(egui::TextEdit::singleline(&mut output).hint_text(OUTPUT_FOLDER))
+                    .on_hover_text("Output folder for this input folder")
+                    .changed()
+                {
+                    folder.output = match output.trim().is_empty() {
+                        true => None,
+                        false => Some(PathBuf::from(output.trim())),
+                    };
+                }
+            });
+        }
 
-impl App {
-    fn stop_processing(&mut self) {
-        self.stop_flag.store(true, Ordering::Relaxed);
-    }
+        if let Some(i) = removed {
+            self.settings.input_folders.remove(i);
+            rescan = true;
+        }
 
-    fn handle_completion(&mut self) {
-        self.receiver = None;
-        self.success.clear();
-        self.failed.clear();
-        self.stop_flag.store(false, Ordering::Relaxed);
-    }
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.new_folder).hint_text("Folder path"));
 
-    fn start_processing(&mut self) {
-        self.stop_flag.store(false, Ordering::Relaxed);
-        let (sender, receiver) = channel::<Update>();
-        self.receiver = Some(receiver);
+            if ui.button("Add folder").clicked() {
+                let folder = PathBuf::from(self.new_folder.trim());
 
-        self.messages.clear();
+                if !folder.is_dir() {
+                    self.push_message(format!("'{}' is not a directory", folder.display()));
+                } else if !self
+                    .settings
+                    .input_folders
+                    .iter()
+                    .any(|input| input.path == folder)
+                {
+                    self.settings.input_folders.push(InputFolder::new(folder));
+                    self.new_folder.clear();
+                    rescan = true;
+                }
+            }
+        });
 
-        let settings = self.settings.clone();
-        let files = self.files.clone();
-        let stop_flag = Arc::clone(&self.stop_flag);
+        // Symlinks
+        egui::ComboBox::from_label("Symlinks")
+            .selected_text(self.settings.symlinks.to_string())
+            .show_ui(ui, |ui| {
+                for policy in [
+                    SymlinkPolicy::Follow,
+                    SymlinkPolicy::Skip,
+                    SymlinkPolicy::Resolve,
+                ] {
+                    rescan |= ui
+                        .selectable_value(&mut self.settings.symlinks, policy, policy.to_string())
+                        .changed();
+                }
+            });
 
-        thread::spawn(move || {
-            convert_images(sender, stop_flag, files, settings);
+        // File filter
+        ui.horizontal(|ui| {
+            ui.label("File filter");
+            rescan |= ui
+                .text_edit_singleline(self.settings.file_filter.get_or_insert_with(String::new))
+                .on_hover_text("Only convert matching files, e.g. IMG_*.jpg or *.png")
+                .lost_focus();
         });
-    }
-
-    fn handle_messages(&mut self) {
-        if let Some(receiver) = &self.receiver {
-            if let Ok(received) = receiver.try_recv() {
-                let received = match received {
-                    Update::StartProcessing(path) => {
-                        let file_name = path.file_name().unwrap().to_str().unwrap();
-                        format!("Processing '{}'", file_name)
-                    }
-                    Update::FinishedProcessing(path, success, duration) => {
-                        let file_name = path.file_name().unwrap().to_str().unwrap();
-                        let message = if success {
-                            self.success.push(path.clone());
-                            format!("Processed '{}'", file_name)
-                        } else {
-                            self.failed.push(path.clone());
-                            format!("Failed to process '{}'", file_name)
-                        };
-                        format!("{} ({:#?})", message, duration)
-                    }
-                    Update::Message(msg) => msg,
-                    Update::QueueCompleted(duration) => {
-                        let message = match self.stop_flag.load(Ordering::Relaxed) {
-                            true => "Stopped".to_string(),
-                            false => format!("Completed in {:#?}", duration),
-                        };
-                        self.handle_completion();
-                        message.to_string()
-                    }
-                };
 
-                self.push_message(received);
-            }
+        if let Some(filter) = self.settings.file_filter.take() {
+            let filter = filter.trim().to_string();
+            self.settings.file_filter = match filter.is_empty() {
+                true => None,
+                false => Some(filter),
+            };
         }
-    }
 
-    fn push_message(&mut self, message: String) {
-        self.messages.push(message);
-
-        if self.messages.len() > LOG_LENGTH {
-            self.messages.remove(0);
+        if rescan {
+            self.rescan();
         }
-    }
 
-    // Pages
-    fn home_page(&mut self, ui: &mut egui::Ui) {
-        // Encoding
-        let mut summary = String::new();
+        // Queue order
+        egui::ComboBox::from_label("Queue order")
+            .selected_text(self.settings.sort_order.to_string())
+            .show_ui(ui, |ui| {
+                for order in [
+                    SortOrder::Name,
+                    SortOrder::SmallestFirst,
+                    SortOrder::LargestFirst,
+                    SortOrder::NewestFirst,
+                    SortOrder::OldestFirst,
+                    SortOrder::Shuffle,
+                ] {
+                    ui.selectable_value(&mut self.settings.sort_order, order, order.to_string());
+                }
+            });
 
-        write!(
-            summary,
-            "Your images will be saved as {{name}}{}.{}",
-            self.settings.name_extension.as_deref().unwrap_or(""),
-            self.settings.encoding_options
-        )
-        .unwrap();
+        // Scratch folder
+        ui.horizontal(|ui| {
+            let mut scratch = self
+                .settings
+                .scratch_dir
+                .as_ref()
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default();
 
-        // Resize options
-        let resize_options = match self.settings.resize_options {
-            ResizeOptions::None => "with their original resolution".to_string(),
-            ResizeOptions::Largest(size) => {
-                format!("and will be resized to {}px on the largest dimension", size)
-            }
-            ResizeOptions::Smallest(size) => {
-                format!(
-                    "and will be resized to {}px on the smallest dimension",
-                    size
+            ui.label("Scratch folder");
+            if ui
+                .add(egui::TextEdit::singleline(&mut scratch).hint_text("System temp folder"))
+                .on_hover_text(
+                    "Where downloads, pasted images and outputs are staged, removed on exit",
                 )
+                .changed()
+            {
+                self.settings.scratch_dir = match scratch.trim().is_empty() {
+                    true => None,
+                    false => Some(PathBuf::from(scratch.trim())),
+                };
             }
-            ResizeOptions::Exact(width, height) => {
-                format!("and will be resized to {}px by {}px", width, height)
-            }
-        };
-
-        write!(summary, ", {}.", resize_options).unwrap();
+        });
 
-        ui.heading("Summary");
-        ui.label(summary);
+        // Worker cores
+        egui::ComboBox::from_label("Run workers on")
+            .selected_text(self.settings.core_affinity.to_string())
+            .show_ui(ui, |ui| {
+                for affinity in [CoreAffinity::Any, CoreAffinity::Performance, CoreAffinity::Efficiency] {
+                    ui.selectable_value(&mut self.settings.core_affinity, affinity, affinity.to_string());
+                }
+            })
+            .response
+            .on_hover_text("On CPUs with performance and efficiency cores, keeps conversions to one kind. Pinned on Linux, scheduled by priority on macOS and ignored elsewhere");
 
-        ui.add_space(8.0);
+        // Interlaced PNGs
+        ui.checkbox(
+            &mut self.settings.fast_interlaced_png,
+            "Fast decode for interlaced PNGs",
+        )
+        .on_hover_text("Decodes interlaced PNGs from memory and skips checksum verification");
 
-        ui.heading("Logs");
-        ui.label(self.messages.join("\n"));
-    }
+        // Stereo photos
+        ui.checkbox(&mut self.settings.mpo_all_frames, "Export every frame of MPO (3D) photos")
+            .on_hover_text("Stereoscopic photos are saved as {name}-1 and {name}-2 instead of just the primary image");
 
-    fn export_page(&mut self, ui: &mut egui::Ui) {
-        ui.heading("Export options");
+        // PDFs
+        #[cfg(feature = "pdf")]
         ui.horizontal(|ui| {
-            ui.label("Name extension");
-            ui.text_edit_singleline(self.settings.name_extension.get_or_insert_with(String::new));
-        });
-
-        // Remove forbidden characters
-        if let Some(extension) = self.settings.name_extension.take() {
-            let mut cleaned = extension.trim().to_string();
+            use crate::structs::settings::PdfMode;
 
-            for c in FORBIDDEN_CHARS {
-                cleaned.retain(|x| x != *c);
+            // Compiled in, but pdfium wasn't found
+            if let Some(reason) = Capability::PdfInput.unavailable_reason() {
+                ui.disable();
+                ui.label("PDF files").on_disabled_hover_text(reason);
+            } else {
+                ui.label("PDF files");
             }
+            ui.selectable_value(
+                &mut self.settings.pdf_mode,
+                PdfMode::Rasterize,
+                "Render pages",
+            );
+            ui.selectable_value(
+                &mut self.settings.pdf_mode,
+                PdfMode::Extract,
+                "Extract images",
+            );
 
-            self.settings.name_extension = match cleaned.is_empty() {
-                true => None,
-                false => Some(cleaned),
-            };
-        }
+            ui.add_enabled(
+                self.settings.pdf_mode == PdfMode::Rasterize,
+                egui::DragValue::new(&mut self.settings.pdf_dpi)
+                    .range(36..=1200)
+                    .suffix(" dpi"),
+            )
+            .on_hover_text(
+                "Rendered pages are saved as {name}-page-001, {name}-page-002 and so on",
+            );
+        });
 
-        // Exif
-        // ui.add(egui::Checkbox::new(
-        //     &mut self.settings.keep_exif,
-        //     "Keep EXIF data",
-        // ));
+        // SVGs
+        ui.horizontal(|ui| {
+            ui.label("Render SVGs at");
+            ui.add(
+                egui::DragValue::new(&mut self.settings.svg_size)
+                    .range(16..=8192)
+                    .suffix(" px"),
+            )
+            .on_hover_text("Size of the longest side, before resizing");
+        });
+
+        // Icons
+        ui.checkbox(&mut self.settings.icon_all_sizes, "Export every size of ICO and ICNS files")
+            .on_hover_text("Sizes are saved as {name}-1, {name}-2 and so on, largest first, instead of just the largest");
+
+        // Multi-page TIFFs
+        ui.checkbox(
+            &mut self.settings.tiff_all_pages,
+            "Export every page of TIFF files",
+        )
+        .on_hover_text(
+            "Pages are saved as {name}-1, {name}-2 and so on instead of just the first page",
+        );
     }
 
     fn encoding_page(&mut self, ui: &mut egui::Ui) {
@@ -232,18 +1841,87 @@ impl App {
                         EncodingOptions::Jpeg(JpegSettings::default()),
                         "JPEG",
                     );
+                    ui.selectable_value(
+                        &mut self.settings.encoding_options,
+                        EncodingOptions::Png(PngSettings::default()),
+                        "PNG",
+                    );
+                    ui.selectable_value(
+                        &mut self.settings.encoding_options,
+                        EncodingOptions::Qoi,
+                        "QOI",
+                    );
+                    ui.selectable_value(
+                        &mut self.settings.encoding_options,
+                        EncodingOptions::Tiff(TiffSettings::default()),
+                        "TIFF",
+                    );
+                    ui.selectable_value(
+                        &mut self.settings.encoding_options,
+                        EncodingOptions::Gif(GifSettings::default()),
+                        "GIF",
+                    )
+                    .on_hover_text("Animated GIFs stay animated");
+                    ui.selectable_value(
+                        &mut self.settings.encoding_options,
+                        EncodingOptions::Ico(IcoSettings::default()),
+                        "ICO",
+                    )
+                    .on_hover_text("Windows icon with several sizes in one file");
+                    ui.selectable_value(
+                        &mut self.settings.encoding_options,
+                        EncodingOptions::Bmp(BmpDepth::Rgb24),
+                        "BMP",
+                    );
+                    #[cfg(feature = "jxl-output")]
+                    ui.selectable_value(
+                        &mut self.settings.encoding_options,
+                        EncodingOptions::Jxl(Default::default()),
+                        "JPEG XL",
+                    );
+                    // Listed so it's clear why it can't be picked
+                    if let Some(reason) = Capability::JxlOutput.unavailable_reason() {
+                        ui.add_enabled(false, egui::SelectableLabel::new(false, "JPEG XL"))
+                            .on_disabled_hover_text(reason);
+                    }
                 });
 
+            // Favicons
+            ui.checkbox(&mut self.settings.favicon_package, "Favicon package")
+                .on_hover_text("Writes favicon.ico, the 16, 32, Apple touch and Android PNG icons and a site.webmanifest per image into its own folder, instead of the regular outputs");
+
+            // Bit depth
+            let max_depth = self.settings.encoding_options.max_bit_depth();
+            if max_depth < 16 {
+                ui.label(format!("⚠ Stores {} bits per channel, 16 bit sources lose precision", max_depth))
+                    .on_hover_text("Dithering below hides the banding this can cause in gradients");
+            }
+
             match &mut self.settings.encoding_options {
                 EncodingOptions::Avif(settings) => {
                     // Lossless
-                    // ui.add(egui::Checkbox::new(&mut settings.lossless, "Lossless"));
+                    ui.add(egui::Checkbox::new(&mut settings.lossless, "Lossless"))
+                        .on_hover_text("Keeps every pixel exactly, for archiving. Files are a lot larger");
 
                     // Quality
-                    ui.add(egui::Slider::new(&mut settings.quality, 5..=100).text("Quality"));
+                    ui.add_enabled(
+                        !settings.lossless,
+                        egui::Slider::new(&mut settings.quality, 5..=100).text("Quality"),
+                    );
 
                     // Speed
                     ui.add(egui::Slider::new(&mut settings.speed, 1..=10).text("Speed"));
+
+                    // Bit depth
+                    egui::ComboBox::from_label("Bit depth")
+                        .selected_text(settings.bit_depth.to_string())
+                        .show_ui(ui, |ui| {
+                            for depth in [AvifBitDepth::Eight, AvifBitDepth::Ten] {
+                                ui.selectable_value(&mut settings.bit_depth, depth, depth.to_string());
+                            }
+                        })
+                        .response
+                        .on_hover_text("10 bit avoids banding in skies and gradients, even for 8 bit sources. Lossless files only grow from it. 12 bit isn't supported by the encoder");
                 }
                 EncodingOptions::WebP(settings) => {
                     // Lossless
@@ -259,8 +1937,211 @@ impl App {
                 EncodingOptions::Jpeg(settings) => {
                     // Quality
                     ui.add(egui::Slider::new(&mut settings.quality, 5..=100).text("Quality"));
+
+                    egui::ComboBox::from_label("Chroma subsampling")
+                        .selected_text(settings.subsampling.to_string())
+                        .show_ui(ui, |ui| {
+                            for subsampling in [ChromaSubsampling::Yuv444, ChromaSubsampling::Yuv422, ChromaSubsampling::Yuv420] {
+                                ui.selectable_value(&mut settings.subsampling, subsampling, subsampling.to_string());
+                            }
+                        })
+                        .response
+                        .on_hover_text("4:4:4 keeps colour at full resolution, for screenshots and coloured text. 4:2:0 is smallest and fine for photos");
+
+                    // Encoder
+                    let mozjpeg = Capability::Mozjpeg.unavailable_reason();
+                    ui.add_enabled(
+                        mozjpeg.is_none(),
+                        egui::Checkbox::new(&mut settings.mozjpeg, "Use mozjpeg"),
+                    )
+                    .on_hover_text("Slower, but around a tenth smaller at the same quality")
+                    .on_disabled_hover_text(mozjpeg.unwrap_or_default());
+
+                    ui.checkbox(&mut settings.progressive, "Progressive")
+                        .on_hover_text("Loads as a blurry preview that sharpens, instead of top to bottom. Usually a little smaller too. Baseline JPEGs from mozjpeg skip its trellis quantization");
+
+                    // Thumbnail
+                    ui.checkbox(&mut settings.thumbnail, "Embed EXIF thumbnail")
+                        .on_hover_text("Stores a small preview in the file for viewers that rely on it");
+                }
+
+                EncodingOptions::Png(settings) => {
+                    // Compression
+                    egui::ComboBox::from_label("Compression")
+                        .selected_text(settings.compression.to_string())
+                        .show_ui(ui, |ui| {
+                            for compression in [PngCompression::Fast, PngCompression::Default, PngCompression::Best] {
+                                ui.selectable_value(&mut settings.compression, compression, compression.to_string());
+                            }
+                        });
+
+                    ui.checkbox(&mut settings.adaptive_filter, "Adaptive filtering")
+                        .on_hover_text("Picks the best filter per row, smaller files for a little more time");
+
+                    // Palette
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut settings.quantize, "Reduce to a palette of")
+                            .on_hover_text("Much smaller files for icons, screenshots and flat artwork");
+                        ui.add_enabled(
+                            settings.quantize,
+                            egui::DragValue::new(&mut settings.colors).range(2..=256).suffix(" colors"),
+                        );
+                    });
+                }
+
+                // Lossless, nothing to configure
+                EncodingOptions::Qoi => {}
+
+                EncodingOptions::Tiff(settings) => {
+                    // Compression
+                    egui::ComboBox::from_label("Compression")
+                        .selected_text(settings.compression.to_string())
+                        .show_ui(ui, |ui| {
+                            for compression in [TiffCompression::None, TiffCompression::Lzw, TiffCompression::Deflate] {
+                                ui.selectable_value(&mut settings.compression, compression, compression.to_string());
+                            }
+                        })
+                        .response
+                        .on_hover_text("Both are lossless, LZW is read by every print workflow");
+
+                    ui.checkbox(&mut settings.sixteen_bit, "16 bits per channel")
+                        .on_hover_text("Keeps 16 bit sources at full precision, 8 bit sources are written as is");
+                }
+
+                EncodingOptions::Ico(settings) => {
+                    // Sizes
+                    ui.label("Sizes");
+                    ui.horizontal_wrapped(|ui| {
+                        for size in ICO_SIZES {
+                            let mut included = settings.sizes.contains(&size);
+                            if ui.checkbox(&mut included, format!("{0}x{0}", size)).changed() {
+                                settings.sizes.retain(|&s| s != size);
+                                if included {
+                                    settings.sizes.push(size);
+                                    settings.sizes.sort_unstable();
+                                }
+                            }
+                        }
+                    });
+                }
+
+                EncodingOptions::Bmp(depth) => {
+                    // Bit depth
+                    egui::ComboBox::from_label("Bit depth")
+                        .selected_text(depth.to_string())
+                        .show_ui(ui, |ui| {
+                            for option in [BmpDepth::Rgb24, BmpDepth::Rgba32] {
+                                ui.selectable_value(depth, option, option.to_string());
+                            }
+                        });
+                }
+
+                EncodingOptions::Gif(settings) => {
+                    // Palette
+                    ui.add(egui::Slider::new(&mut settings.colors, 2..=256).text("Colors"));
+
+                    // Dithering
+                    egui::ComboBox::from_label("Dithering")
+                        .selected_text(settings.dither.to_string())
+                        .show_ui(ui, |ui| {
+                            for dither in [PaletteDither::None, PaletteDither::FloydSteinberg, PaletteDither::Ordered] {
+                                ui.selectable_value(&mut settings.dither, dither, dither.to_string());
+                            }
+                        })
+                        .response
+                        .on_hover_text("Ordered dithering keeps a fixed pattern, so animations don't shimmer");
+                }
+
+                #[cfg(feature = "jxl-output")]
+                EncodingOptions::Jxl(settings) => {
+                    // Lossless
+                    ui.add(egui::Checkbox::new(&mut settings.lossless, "Lossless"));
+
+                    // Distance
+                    ui.add_enabled(
+                        !settings.lossless,
+                        egui::Slider::new(&mut settings.distance, 0.1..=15.0).text("Distance"),
+                    )
+                    .on_hover_text("1 is visually lossless, higher is smaller and blurrier");
+
+                    // Effort
+                    ui.add(egui::Slider::new(&mut settings.effort, 1..=9).text("Effort"));
+
+                    ui.checkbox(&mut settings.recompress_jpeg, "Recompress JPEGs losslessly")
+                        .on_hover_text("JPEGs that aren't resized or edited are repacked about 20% smaller and can be turned back into the exact original");
+                }
+            }
+
+            // Extra formats
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Also export as");
+
+                let mut removed = None;
+                for (i, format) in self.settings.extra_formats.iter().enumerate() {
+                    if ui.small_button(format!("{} ✖", format)).on_hover_text("Remove").clicked() {
+                        removed = Some(i);
+                    }
                 }
+                if let Some(i) = removed {
+                    self.settings.extra_formats.remove(i);
+                }
+
+                let current = self.settings.encoding_options.clone();
+                let can_add = !self.settings.extra_formats.iter().any(|format| format.to_string() == current.to_string());
+                if ui
+                    .add_enabled(can_add, egui::Button::new(format!("+ {}", current)).small())
+                    .on_hover_text("Adds the format above with its current options, pick another main format afterwards")
+                    .clicked()
+                {
+                    self.settings.extra_formats.push(current);
+                }
+            });
+
+            // Adaptive quality
+            ui.checkbox(&mut self.settings.adaptive_quality, "Content-adaptive quality")
+                .on_hover_text("Raises the quality for detailed images and lowers it for flat ones, by up to 10");
+
+            // Tiles
+            if let Some(max) = self.settings.encoding_options.max_dimension() {
+                ui.checkbox(&mut self.settings.split_oversized, "Split oversized images into tiles")
+                    .on_hover_text(format!(
+                        "Images over {} pixels wide or high are written as tiles with a JSON index instead of failing, for panoramas",
+                        max
+                    ));
+                ui.add_enabled(
+                    !self.settings.split_oversized,
+                    egui::Checkbox::new(&mut self.settings.fit_to_limit, "Downscale oversized images to fit"),
+                );
             }
+
+            // Channels
+            egui::ComboBox::from_label("Channels")
+                .selected_text(self.settings.channels.to_string())
+                .show_ui(ui, |ui| {
+                    for mode in [ChannelMode::Off, ChannelMode::Split, ChannelMode::Merge] {
+                        ui.selectable_value(&mut self.settings.channels, mode, mode.to_string());
+                    }
+                })
+                .response
+                .on_hover_text("Splits images into {name}_r, _g, _b and _a grayscale files, or merges such sets into one image");
+
+            // HDR
+            egui::CollapsingHeader::new("HDR sources").show(ui, |ui| {
+                egui::ComboBox::from_label("Tone mapping")
+                    .selected_text(self.settings.tone_mapping.to_string())
+                    .show_ui(ui, |ui| {
+                        for operator in [ToneMapping::Clamp, ToneMapping::Reinhard, ToneMapping::Aces] {
+                            ui.selectable_value(&mut self.settings.tone_mapping, operator, operator.to_string());
+                        }
+                    });
+
+                ui.add(egui::Slider::new(&mut self.settings.exposure, -5.0..=5.0).text("Exposure (stops)"))
+                    .on_hover_text("Applies to EXR and Radiance HDR files");
+            });
+
+            // Dithering
+            ui.checkbox(&mut self.settings.dither, "Dither 16-bit and HDR sources")
+                .on_hover_text("Adds a fine pattern when reducing to 8 bits, preventing banding in gradients");
         });
     }
 
@@ -276,18 +2157,75 @@ impl App {
             ui.label("Made with <3 by");
             ui.hyperlink_to("Owlive", "https://owlive.eu/");
         });
+        ui.label("Licensed under the GNU Affero General Public License v3.");
+
+        ui.add_space(8.0);
+        build_info(ui);
+
+        ui.add_space(8.0);
+        ui.heading("Credits");
+        egui::ScrollArea::vertical().show(ui, credits);
     }
 }
 
 impl eframe::App for App {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.save_session();
+
+        if let Err(e) = cleanup() {
+            eprintln!("Failed to clean up temporary files: {}", e);
+        }
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // State
         self.handle_messages();
 
-        let total_processed = self.success.len() + self.failed.len();
+        let now = ctx.input(|i| i.time);
+        if now - self.session_checked > SESSION_INTERVAL {
+            self.session_checked = now;
+            self.save_session();
+        }
+
+        // Worker threads that panic leave the app running
+        if crash::crashed() {
+            self.crash_report = crash::take_unseen();
+        }
+
+        // Ctrl+V outside of text fields. Clipboards holding text only produce a Paste event
+        if !ctx.wants_keyboard_input() {
+            let pasted = ctx.input(|i| {
+                i.events.iter().any(|event| match event {
+                    egui::Event::Paste(_) => true,
+                    egui::Event::Key {
+                        key: egui::Key::V,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } => modifiers.command,
+                    _ => false,
+                })
+            });
+
+            if pasted {
+                self.paste_from_clipboard();
+            }
+        }
+
+        // Worker, watcher and scan updates arrive without user input
+        if self.receiver.is_some()
+            || self.watch_receiver.is_some()
+            || self.scan_receiver.is_some()
+            || self.verify_receiver.is_some()
+            || self.oversized_receiver.is_some()
+        {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+
+        let total_processed = self.processed;
 
         let percentage = if total_processed > 0 {
-            total_processed as f32 / self.files.len() as f32
+            total_processed as f32 / self.queue_len as f32
         } else {
             0.0
         };
@@ -298,32 +2236,34 @@ impl eframe::App for App {
 
             // Nav
             ui.horizontal(|ui| {
-                for page in &[
-                    Page::Home,
-                    Page::Resize,
-                    Page::Encoding,
-                    Page::Export,
-                    Page::About,
-                ] {
-                    let label = match page {
-                        Page::Home => "Home",
-                        Page::Resize => "Resize",
-                        Page::Encoding => "Encoding",
-                        Page::Export => "Export",
-                        Page::About => "About",
-                    };
-
-                    if ui.selectable_label(self.page == *page, label).clicked() {
-                        self.page = *page;
+                for page in Page::ALL {
+                    if ui.selectable_label(self.page == page, page.label()).clicked() {
+                        self.page = page;
                     }
                 }
 
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Max), |ui| {
                     ui.add_space(10.0);
                     if self.receiver.is_none() {
-                        // Start button
-                        if ui.button("Run").clicked() {
-                            self.start_processing();
+                        // Start button (disabled until the queue is complete and checked)
+                        let hover = if self.watch_receiver.is_some() {
+                            "Stop the watcher to run the whole queue"
+                        } else if self.scan_receiver.is_some() {
+                            "Waiting for the input folder scan to finish"
+                        } else {
+                            "Checking image sizes against the output format"
+                        };
+                        if ui
+                            .add_enabled(
+                                self.watch_receiver.is_none()
+                                    && self.scan_receiver.is_none()
+                                    && self.oversized_receiver.is_none(),
+                                egui::Button::new("Run"),
+                            )
+                            .on_disabled_hover_text(hover)
+                            .clicked()
+                        {
+                            self.check_sizes();
                         }
                     } else {
                         // Stop button (disabled if stop_flag is set)
@@ -334,26 +2274,143 @@ impl eframe::App for App {
                         });
                     }
 
+                    // Watch toggle (disabled while the watcher shuts down)
+                    let stopping = self.watch_flag.load(Ordering::Relaxed);
+                    let mut watching = self.watch_receiver.is_some() && !stopping;
+                    let toggle = ui
+                        .add_enabled_ui(!(stopping && self.watch_receiver.is_some()), |ui| {
+                            ui.toggle_value(&mut watching, "Watch")
+                                .on_hover_text("Automatically convert new images added to the input folder")
+                        })
+                        .inner;
+
+                    if toggle.changed() {
+                        match watching {
+                            true => self.start_watching(),
+                            false => self.stop_watching(),
+                        }
+                    }
+
                     if total_processed > 0 {
-                        ui.label(format!("{:.0}%", percentage * 100.0));
+                        ui.label(format!("{:.0}%", percentage * 100.0))
+                            .on_hover_text(self.stage_summary());
                     }
                 });
             });
 
             ui.add_space(8.0);
 
+            if let Some(count) = self.oversized_count {
+                ui.horizontal(|ui| {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!(
+                            "{} images are larger than an output format stores and will fail",
+                            humanize::count(count)
+                        ),
+                    );
+
+                    if ui.button("Downscale to fit").clicked() {
+                        self.settings.fit_to_limit = true;
+                        self.oversized_count = None;
+                        self.start_processing();
+                    }
+                    if ui.button("Run anyway").clicked() {
+                        self.oversized_count = None;
+                        self.start_processing();
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.oversized_count = None;
+                    }
+                });
+                ui.add_space(8.0);
+            }
+
             ui.add(egui::ProgressBar::new(percentage).desired_height(8.0));
 
             ui.add_space(8.0);
 
+            let locked = self.is_locked();
+            if locked && !matches!(self.page, Page::Home | Page::About) {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    "Settings are locked while files are being converted, stop the run or watcher to change them",
+                );
+                ui.add_space(8.0);
+            }
+
             // Content
             match self.page {
-                Page::Home => self.home_page(ui),
-                Page::Encoding => self.encoding_page(ui),
-                Page::Resize => self.resize_page(ui),
-                Page::Export => self.export_page(ui),
+                Page::Home => {
+                    egui::ScrollArea::vertical()
+                        .id_salt("home")
+                        .show(ui, |ui| self.home_page(ui));
+                }
+                Page::Files => {
+                    ui.add_enabled_ui(!locked, |ui| self.files_page(ui));
+                }
+                Page::Encoding => {
+                    ui.add_enabled_ui(!locked, |ui| self.encoding_page(ui));
+                }
+                Page::Resize => {
+                    ui.add_enabled_ui(!locked, |ui| self.resize_page(ui));
+                }
+                Page::Export => {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        ui.add_enabled_ui(!locked, |ui| self.export_page(ui));
+                    });
+                }
                 Page::About => self.about_page(ui),
             }
         });
+
+        // Crash report
+        if let Some(report) = self.crash_report.clone() {
+            egui::Window::new("Something went wrong")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label("The converter crashed, a report was saved to");
+                    ui.monospace(report.display().to_string());
+                    ui.label("Attaching it to a bug report helps a lot. Paths are left out of the settings, the log lines at the end may name files.");
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Open folder").clicked() {
+                            if let Err(e) = crash::open_folder() {
+                                self.push_message(format!("Failed to open crash report folder: {}", e));
+                            }
+                        }
+                        if ui.button("Dismiss").clicked() {
+                            self.crash_report = None;
+                        }
+                    });
+                });
+        }
+    }
+}
+
+/// Unix time of midnight UTC on a YYYY-MM-DD date.
+fn parse_date(text: &str) -> Option<u64> {
+    let mut parts = text.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    u64::try_from(days_from_civil(year, month, day) * 86400).ok()
+}
+
+/// Result column of a converted file, its new size and how much it changed.
+fn job_result(converted: &Converted) -> String {
+    match (converted.unchanged, converted.input_size) {
+        (true, _) => "Unchanged".to_string(),
+        (false, 0) => humanize::size(converted.output_size),
+        (false, input_size) => format!(
+            "{} ({:+.0}%)",
+            humanize::size(converted.output_size),
+            (converted.output_size as f64 / input_size as f64 - 1.0) * 100.0
+        ),
     }
 }
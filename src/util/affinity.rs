@@ -0,0 +1,135 @@
+use std::error::Error;
+
+use crate::structs::settings::CoreAffinity;
+
+/// Worker pool for a run, its threads are kept to the cores `affinity` asks for where the
+/// platform allows it and run anywhere otherwise.
+pub fn thread_pool(affinity: CoreAffinity) -> Result<rayon::ThreadPool, Box<dyn Error>> {
+    let builder = rayon::ThreadPoolBuilder::new();
+
+    let builder = match affinity {
+        CoreAffinity::Any => builder,
+        _ => match cores(affinity) {
+            // One thread per allowed core, more would only wait on each other
+            Some(cores) if !cores.is_empty() => builder
+                .num_threads(cores.len())
+                .start_handler(move |_| pin(affinity, &cores)),
+            _ => builder.start_handler(move |_| pin(affinity, &[])),
+        },
+    };
+
+    Ok(builder.build()?)
+}
+
+/// Logical CPUs of the kind `affinity` asks for, `None` when they can't be told apart.
+#[cfg(target_os = "linux")]
+fn cores(affinity: CoreAffinity) -> Option<Vec<usize>> {
+    use std::fs;
+
+    // Intel hybrid CPUs list their core types directly
+    let list = match affinity {
+        CoreAffinity::Performance => "/sys/devices/cpu_core/cpus",
+        CoreAffinity::Efficiency => "/sys/devices/cpu_atom/cpus",
+        CoreAffinity::Any => return None,
+    };
+    if let Ok(list) = fs::read_to_string(list) {
+        return Some(parse_cpu_list(&list));
+    }
+
+    // ARM big.LITTLE reports a relative capacity per core. Clock speeds aren't used, they
+    // differ between cores of the same kind and would split them up
+    let cpus = parse_cpu_list(&fs::read_to_string("/sys/devices/system/cpu/online").ok()?);
+    let speeds: Vec<(usize, u64)> = cpus
+        .iter()
+        .filter_map(|&cpu| {
+            let capacity =
+                fs::read_to_string(format!("/sys/devices/system/cpu/cpu{}/cpu_capacity", cpu))
+                    .ok()?;
+            Some((cpu, capacity.trim().parse().ok()?))
+        })
+        .collect();
+
+    // Without a capacity for every core they can't be told apart
+    if speeds.len() != cpus.len() {
+        return None;
+    }
+
+    let fastest = speeds.iter().map(|(_, speed)| *speed).max()?;
+    if speeds.iter().all(|(_, speed)| *speed == fastest) {
+        return None;
+    }
+
+    Some(
+        speeds
+            .into_iter()
+            .filter(|(_, speed)| match affinity {
+                CoreAffinity::Performance => *speed == fastest,
+                _ => *speed < fastest,
+            })
+            .map(|(cpu, _)| cpu)
+            .collect(),
+    )
+}
+
+#[cfg(not(target_os = "linux"))]
+fn cores(_affinity: CoreAffinity) -> Option<Vec<usize>> {
+    None
+}
+
+/// Parses lists like `0-7,16,18-19`.
+#[cfg(target_os = "linux")]
+fn parse_cpu_list(list: &str) -> Vec<usize> {
+    list.trim()
+        .split(',')
+        .filter_map(|range| match range.split_once('-') {
+            Some((start, end)) => Some((start.parse().ok()?..=end.parse().ok()?).collect()),
+            None => range.parse().ok().map(|cpu| vec![cpu]),
+        })
+        .flatten()
+        .collect()
+}
+
+/// Keeps the calling thread on `cores`, failures leave it wherever the scheduler puts it.
+#[cfg(target_os = "linux")]
+fn pin(_affinity: CoreAffinity, cores: &[usize]) {
+    if cores.is_empty() {
+        return;
+    }
+
+    // SAFETY: `cpu_set_t` is a plain bit mask that is valid all zeroed, cores past its size
+    // are skipped before `CPU_SET` and the set outlives the call that reads it. Pid 0 is the
+    // calling thread.
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        let capacity = 8 * std::mem::size_of::<libc::cpu_set_t>();
+        for &core in cores.iter().filter(|&&core| core < capacity) {
+            libc::CPU_SET(core, &mut set);
+        }
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            eprintln!(
+                "Failed to set worker affinity: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+}
+
+/// Threads can't be pinned on macOS, the quality of service class decides which cores
+/// they're scheduled on instead.
+#[cfg(target_os = "macos")]
+fn pin(affinity: CoreAffinity, _cores: &[usize]) {
+    let class = match affinity {
+        CoreAffinity::Performance => libc::qos_class_t::QOS_CLASS_USER_INITIATED,
+        CoreAffinity::Efficiency => libc::qos_class_t::QOS_CLASS_BACKGROUND,
+        CoreAffinity::Any => return,
+    };
+
+    // SAFETY: only changes the scheduling class of the calling thread, with a relative
+    // priority of 0 which is always in range
+    unsafe {
+        libc::pthread_set_qos_class_self_np(class, 0);
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn pin(_affinity: CoreAffinity, _cores: &[usize]) {}
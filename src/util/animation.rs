@@ -0,0 +1,61 @@
+use std::{error::Error, fs::File, io::BufReader, path::Path};
+
+use image::{codecs::gif::GifDecoder, AnimationDecoder, DynamicImage};
+use webp::{AnimEncoder, AnimFrame, WebPConfig};
+
+use crate::structs::file_type::WebpSettings;
+
+/// A decoded animation frame and how long it is shown, in milliseconds.
+pub struct Frame {
+    pub image: DynamicImage,
+    pub delay: u32,
+}
+
+/// Every frame of an animated GIF, composed onto the full canvas.
+/// Returns `None` for GIFs with a single frame.
+pub fn gif_frames(path: &Path) -> Result<Option<Vec<Frame>>, Box<dyn Error>> {
+    let decoder = GifDecoder::new(BufReader::new(File::open(path)?))?;
+
+    let frames = decoder
+        .into_frames()
+        .map(|frame| {
+            let frame = frame?;
+            let (numerator, denominator) = frame.delay().numer_denom_ms();
+
+            Ok(Frame {
+                delay: numerator / denominator.max(1),
+                image: DynamicImage::ImageRgba8(frame.into_buffer()),
+            })
+        })
+        .collect::<Result<Vec<_>, image::ImageError>>()?;
+
+    Ok((frames.len() > 1).then_some(frames))
+}
+
+/// Encodes `frames` as a looping animated WebP.
+pub fn encode_webp(frames: &[Frame], options: &WebpSettings) -> Result<Vec<u8>, String> {
+    let first = frames.first().ok_or("Animation has no frames")?;
+    let (width, height) = (first.image.width(), first.image.height());
+
+    let mut config = WebPConfig::new().map_err(|_| "Failed to create WebP config")?;
+    config.lossless = options.lossless as i32;
+    config.quality = options.quality as f32;
+
+    // The encoder borrows the pixels, so they have to outlive it
+    let pixels: Vec<_> = frames.iter().map(|frame| frame.image.to_rgba8()).collect();
+
+    let mut encoder = AnimEncoder::new(width, height, &config);
+    encoder.set_loop_count(0);
+
+    let mut timestamp = 0;
+    for (frame, pixels) in frames.iter().zip(&pixels) {
+        encoder.add_frame(AnimFrame::from_rgba(pixels, width, height, timestamp));
+        timestamp += frame.delay as i32;
+    }
+
+    let data = encoder
+        .try_encode()
+        .map_err(|e| format!("Failed to encode animation: {:?}", e))?;
+
+    Ok(data.to_vec())
+}
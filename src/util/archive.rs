@@ -0,0 +1,103 @@
+use std::{
+    error::Error,
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+// Archives can claim any size, these stop one from filling the disk
+const MAX_ENTRY_SIZE: u64 = 256 * 1024 * 1024;
+const MAX_TOTAL_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+// Enough for every signature `image` recognizes
+const HEADER_SIZE: u64 = 32;
+
+static ARCHIVE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+pub fn is_archive(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// Unpacks the images in a ZIP archive into a folder in `scratch`, keeping their folder structure.
+///
+/// Only entries that start like an image are written out, anything else is skipped. Nothing is
+/// left behind when extracting fails.
+pub fn extract_images(archive: &Path, scratch: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let mut zip = zip::ZipArchive::new(fs::File::open(archive)?)?;
+
+    // Unique folder per archive so equally named archives don't collide
+    let count = ARCHIVE_COUNT.fetch_add(1, Ordering::Relaxed);
+    let root = scratch.join("archives").join(format!(
+        "{}-{}",
+        count,
+        archive.file_stem().unwrap_or_default().to_string_lossy()
+    ));
+    fs::create_dir_all(&root)?;
+
+    match extract_into(&mut zip, &root) {
+        Ok(()) => Ok(root),
+        Err(e) => {
+            if let Err(e) = fs::remove_dir_all(&root) {
+                eprintln!("Failed to remove '{}': {}", root.display(), e);
+            }
+            Err(e)
+        }
+    }
+}
+
+fn extract_into(zip: &mut zip::ZipArchive<fs::File>, root: &Path) -> Result<(), Box<dyn Error>> {
+    let mut total = 0;
+
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+
+        // Entries pointing outside of the archive are ignored
+        let Some(name) = entry.enclosed_name() else {
+            continue;
+        };
+        if !entry.is_file() {
+            continue;
+        }
+
+        // The declared size can't be trusted, so only the header is read before deciding
+        let mut header = Vec::new();
+        entry.by_ref().take(HEADER_SIZE).read_to_end(&mut header)?;
+
+        if image::guess_format(&header).is_err() {
+            continue;
+        }
+
+        let path = root.join(&name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // One byte past the limit tells an entry of exactly the limit from a larger one
+        let mut file = fs::File::create(&path)?;
+        file.write_all(&header)?;
+        let size = header.len() as u64
+            + io::copy(&mut entry.by_ref().take(MAX_ENTRY_SIZE + 1), &mut file)?;
+
+        if size > MAX_ENTRY_SIZE {
+            return Err(format!(
+                "'{}' unpacks to more than {} MB",
+                name.display(),
+                MAX_ENTRY_SIZE / 1024 / 1024
+            )
+            .into());
+        }
+
+        total += size;
+        if total > MAX_TOTAL_SIZE {
+            return Err(format!(
+                "the archive unpacks to more than {} GB of images",
+                MAX_TOTAL_SIZE / 1024 / 1024 / 1024
+            )
+            .into());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+
+use image::{imageops, RgbaImage};
+
+use crate::util::manifest::json_string;
+
+pub static ATLAS_INDEX_FILE: &str = "atlas.json";
+
+/// Where a sprite ended up on its sheet.
+pub struct Placement {
+    pub name: String,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub struct Sheet {
+    pub image: RgbaImage,
+    pub sprites: Vec<Placement>,
+}
+
+/// File name of the sheet at `index`.
+pub fn sheet_file(index: usize) -> String {
+    format!("atlas-{}.png", index)
+}
+
+/// Gives sprites that share a name a `-2`, `-3`, ... suffix so every key in the index is
+/// unique, the first one keeps its name.
+pub fn unique_names(sprites: &mut [(String, RgbaImage)]) {
+    let mut taken: HashSet<String> = sprites.iter().map(|(name, _)| name.clone()).collect();
+    let mut seen = HashSet::new();
+
+    for (name, _) in sprites.iter_mut() {
+        if seen.insert(name.clone()) {
+            continue;
+        }
+
+        let unique = (2..)
+            .map(|n| format!("{}-{}", name, n))
+            .find(|candidate| !taken.contains(candidate))
+            .unwrap();
+        taken.insert(unique.clone());
+        seen.insert(unique.clone());
+        *name = unique;
+    }
+}
+
+/// Packs `sprites` onto as few sheets of at most `max_size` pixels as they fit on, in rows
+/// of similar height. Sheets are shrunk to the smallest power of two that holds them.
+/// Sprites that don't fit on an empty sheet have to be left out by the caller.
+pub fn pack(mut sprites: Vec<(String, RgbaImage)>, max_size: u32, padding: u32) -> Vec<Sheet> {
+    // Tallest first keeps rows from wasting space below shorter sprites
+    sprites.sort_by_key(|(_, sprite)| std::cmp::Reverse(sprite.height()));
+
+    let mut sheets: Vec<Vec<(String, RgbaImage, u32, u32)>> = vec![Vec::new()];
+    let (mut x, mut y, mut row_height) = (0, 0, 0);
+
+    for (name, sprite) in sprites {
+        let (width, height) = sprite.dimensions();
+
+        if x + width > max_size {
+            x = 0;
+            y += row_height + padding;
+            row_height = 0;
+        }
+        if y + height > max_size {
+            sheets.push(Vec::new());
+            (x, y, row_height) = (0, 0, 0);
+        }
+
+        sheets.last_mut().unwrap().push((name, sprite, x, y));
+        x += width + padding;
+        row_height = row_height.max(height);
+    }
+
+    sheets
+        .into_iter()
+        .filter(|placed| !placed.is_empty())
+        .map(|placed| {
+            let used_width = placed
+                .iter()
+                .map(|(_, s, x, _)| x + s.width())
+                .max()
+                .unwrap_or(1);
+            let used_height = placed
+                .iter()
+                .map(|(_, s, _, y)| y + s.height())
+                .max()
+                .unwrap_or(1);
+            let mut image = RgbaImage::new(
+                used_width.next_power_of_two().min(max_size),
+                used_height.next_power_of_two().min(max_size),
+            );
+
+            let sprites = placed
+                .into_iter()
+                .map(|(name, sprite, x, y)| {
+                    imageops::replace(&mut image, &sprite, x as i64, y as i64);
+                    Placement {
+                        name,
+                        x,
+                        y,
+                        width: sprite.width(),
+                        height: sprite.height(),
+                    }
+                })
+                .collect();
+
+            Sheet { image, sprites }
+        })
+        .collect()
+}
+
+/// JSON listing the sheets and where every sprite sits on them.
+pub fn index(sheets: &[Sheet]) -> String {
+    let entries: Vec<String> = sheets
+        .iter()
+        .enumerate()
+        .map(|(i, sheet)| {
+            let sprites: Vec<String> = sheet
+                .sprites
+                .iter()
+                .map(|sprite| {
+                    format!(
+                        "        {{ \"name\": {}, \"x\": {}, \"y\": {}, \"width\": {}, \"height\": {} }}",
+                        json_string(&sprite.name),
+                        sprite.x,
+                        sprite.y,
+                        sprite.width,
+                        sprite.height
+                    )
+                })
+                .collect();
+
+            format!(
+                "    {{\n      \"file\": {},\n      \"width\": {},\n      \"height\": {},\n      \"sprites\": [\n{}\n      ]\n    }}",
+                json_string(&sheet_file(i)),
+                sheet.image.width(),
+                sheet.image.height(),
+                sprites.join(",\n")
+            )
+        })
+        .collect();
+
+    format!("{{\n  \"sheets\": [\n{}\n  ]\n}}\n", entries.join(",\n"))
+}
@@ -0,0 +1,126 @@
+use std::error::Error;
+
+use image::DynamicImage;
+use rav1e::prelude::PixelRange;
+use ravif::{AlphaColorMode, ColorSpace, Encoder, Img, MatrixCoefficients, RGB8, RGBA8};
+
+use crate::util::color;
+
+/// Encodes `img` as mathematically lossless AVIF with `depth` bits per channel, 8 or 10.
+///
+/// The image crate's encoder always converts to YCbCr, which rounds, so this goes to ravif directly
+/// with RGB kept as is and the lowest quantizer. 8 bit pixels widened to 10 bits still read
+/// back the same.
+pub fn encode_lossless(
+    img: &DynamicImage,
+    speed: u8,
+    depth: u8,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let encoder = Encoder::new()
+        .with_quality(100.0)
+        .with_alpha_quality(100.0)
+        .with_speed(speed)
+        .with_depth(Some(depth))
+        .with_internal_color_space(ColorSpace::RGB)
+        // Keep the color of fully transparent pixels too
+        .with_alpha_color_mode(AlphaColorMode::UnassociatedDirty);
+
+    encode(encoder, ColorSpace::RGB, depth, img)
+}
+
+/// Encodes `img` lossy at 10 bits per channel, which `image`'s encoder can't. Sources with
+/// 8 bits are still stored with finer steps, smoothing out banding in skies and gradients.
+pub fn encode_ten_bit(
+    img: &DynamicImage,
+    quality: u8,
+    speed: u8,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let encoder = Encoder::new()
+        .with_quality(quality as f32)
+        .with_alpha_quality(quality as f32)
+        .with_speed(speed)
+        .with_depth(Some(10));
+
+    encode(encoder, ColorSpace::YCbCr, 10, img)
+}
+
+fn encode(
+    encoder: Encoder,
+    color_space: ColorSpace,
+    depth: u8,
+    img: &DynamicImage,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    // Only 8 bit sources are widened by ravif, deeper ones are scaled down to 10 bits here
+    if depth == 10 && color::bit_depth(img) > 8 {
+        return encode_deep(encoder, color_space, img);
+    }
+
+    let (width, height) = (img.width() as usize, img.height() as usize);
+
+    let encoded = match img.color().has_alpha() {
+        true => {
+            let pixels: Vec<RGBA8> = img
+                .to_rgba8()
+                .pixels()
+                .map(|pixel| RGBA8::new(pixel[0], pixel[1], pixel[2], pixel[3]))
+                .collect();
+            encoder.encode_rgba(Img::new(&pixels[..], width, height))?
+        }
+        false => {
+            let pixels: Vec<RGB8> = img
+                .to_rgb8()
+                .pixels()
+                .map(|pixel| RGB8::new(pixel[0], pixel[1], pixel[2]))
+                .collect();
+            encoder.encode_rgb(Img::new(&pixels[..], width, height))?
+        }
+    };
+
+    Ok(encoded.avif_file)
+}
+
+/// Encodes a 16 bit or float image at 10 bits, converting to the planes ravif expects the way
+/// its own 8 bit path does: G, B, R for [`ColorSpace::RGB`] and full range BT.601 otherwise.
+fn encode_deep(
+    encoder: Encoder,
+    color_space: ColorSpace,
+    img: &DynamicImage,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (width, height) = (img.width() as usize, img.height() as usize);
+    let buf = img.to_rgba16();
+
+    let planes = buf.pixels().map(|pixel| {
+        let [r, g, b, _] = pixel.0.map(|value| value as f32 / 65535.0);
+        match color_space {
+            ColorSpace::RGB => [to_ten(g), to_ten(b), to_ten(r)],
+            ColorSpace::YCbCr => {
+                let y = BT601[0] * r + BT601[1] * g + BT601[2] * b;
+                let cb = (b - y) * 0.5 / (1.0 - BT601[2]) + 0.5;
+                let cr = (r - y) * 0.5 / (1.0 - BT601[0]) + 0.5;
+                [to_ten(y), to_ten(cb), to_ten(cr)]
+            }
+        }
+    });
+
+    // Like ravif, leave the alpha plane out when every pixel is opaque
+    let alpha = match img.color().has_alpha() && buf.pixels().any(|pixel| pixel[3] != u16::MAX) {
+        true => Some(buf.pixels().map(|pixel| to_ten(pixel[3] as f32 / 65535.0))),
+        false => None,
+    };
+
+    let matrix = match color_space {
+        ColorSpace::RGB => MatrixCoefficients::Identity,
+        ColorSpace::YCbCr => MatrixCoefficients::BT601,
+    };
+
+    let encoded =
+        encoder.encode_raw_planes_10_bit(width, height, planes, alpha, PixelRange::Full, matrix)?;
+
+    Ok(encoded.avif_file)
+}
+
+const BT601: [f32; 3] = [0.2990, 0.5870, 0.1140];
+
+fn to_ten(value: f32) -> u16 {
+    (value * 1023.0).round().clamp(0.0, 1023.0) as u16
+}
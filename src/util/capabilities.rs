@@ -0,0 +1,99 @@
+#[cfg(feature = "pdf")]
+use std::sync::OnceLock;
+
+/// Codecs and tools that depend on how the app was built or on libraries found at runtime.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Capability {
+    HeifInput,
+    RawInput,
+    PdfInput,
+    JxlOutput,
+    Mozjpeg,
+    SeamCarving,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 6] = [
+        Capability::HeifInput,
+        Capability::RawInput,
+        Capability::PdfInput,
+        Capability::JxlOutput,
+        Capability::Mozjpeg,
+        Capability::SeamCarving,
+    ];
+
+    /// Cargo feature that compiles it in.
+    pub fn feature(self) -> &'static str {
+        match self {
+            Capability::HeifInput => "heif",
+            Capability::RawInput => "raw",
+            Capability::PdfInput => "pdf",
+            Capability::JxlOutput => "jxl-output",
+            Capability::Mozjpeg => "mozjpeg",
+            Capability::SeamCarving => "seam-carving",
+        }
+    }
+
+    pub fn is_compiled(self) -> bool {
+        match self {
+            Capability::HeifInput => cfg!(feature = "heif"),
+            Capability::RawInput => cfg!(feature = "raw"),
+            Capability::PdfInput => cfg!(feature = "pdf"),
+            Capability::JxlOutput => cfg!(feature = "jxl-output"),
+            Capability::Mozjpeg => cfg!(feature = "mozjpeg"),
+            Capability::SeamCarving => cfg!(feature = "seam-carving"),
+        }
+    }
+
+    /// Whether it can be used right now, compiled in and with its library present.
+    pub fn is_available(self) -> bool {
+        match self {
+            Capability::PdfInput => self.is_compiled() && pdfium_installed(),
+            _ => self.is_compiled(),
+        }
+    }
+
+    /// Why it can't be used, for tooltips. `None` when it can.
+    pub fn unavailable_reason(self) -> Option<String> {
+        if !self.is_compiled() {
+            return Some(format!(
+                "{} isn't part of this build, it needs the '{}' feature",
+                self,
+                self.feature()
+            ));
+        }
+
+        match self.is_available() {
+            true => None,
+            false => Some(format!(
+                "{} needs the pdfium library next to the executable or installed",
+                self
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Capability::HeifInput => write!(f, "HEIC/HEIF input"),
+            Capability::RawInput => write!(f, "Camera RAW input"),
+            Capability::PdfInput => write!(f, "PDF input"),
+            Capability::JxlOutput => write!(f, "JPEG XL output"),
+            Capability::Mozjpeg => write!(f, "mozjpeg encoder"),
+            Capability::SeamCarving => write!(f, "Content-aware resizing"),
+        }
+    }
+}
+
+// Binding the library is slow, so it's only tried once
+#[cfg(feature = "pdf")]
+fn pdfium_installed() -> bool {
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+    *INSTALLED.get_or_init(crate::util::pdf::is_installed)
+}
+
+#[cfg(not(feature = "pdf"))]
+fn pdfium_installed() -> bool {
+    false
+}
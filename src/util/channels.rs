@@ -0,0 +1,109 @@
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use image::{DynamicImage, GrayImage, ImageBuffer};
+
+// File name suffixes of the red, green, blue and alpha channel
+pub const SUFFIXES: [&str; 4] = ["_r", "_g", "_b", "_a"];
+
+/// Every channel of `img` as a grayscale image, with the suffix for its file name.
+pub fn split(img: &DynamicImage) -> Vec<(&'static str, DynamicImage)> {
+    let channels = match img.color().has_alpha() {
+        true => 4,
+        false => 3,
+    };
+    let rgba = img.to_rgba8();
+
+    SUFFIXES[..channels]
+        .iter()
+        .enumerate()
+        .map(|(i, suffix)| {
+            let channel = GrayImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+                image::Luma([rgba.get_pixel(x, y).0[i]])
+            });
+            (*suffix, DynamicImage::ImageLuma8(channel))
+        })
+        .collect()
+}
+
+/// Stem of the file holding the channel with `suffix`, if `stem` belongs to a channel set.
+fn channel_stem(stem: &str, suffix: &str) -> Option<String> {
+    SUFFIXES.iter().find_map(|own| {
+        let base = stem.len().checked_sub(own.len())?;
+        stem.get(base..)?
+            .eq_ignore_ascii_case(own)
+            .then(|| format!("{}{}", &stem[..base], suffix))
+    })
+}
+
+/// Sibling of `path` holding the channel with `suffix`, when it exists.
+fn sibling(path: &Path, suffix: &str) -> Option<PathBuf> {
+    let stem = channel_stem(path.file_stem()?.to_str()?, suffix)?;
+    let mut sibling = path.with_file_name(stem);
+    if let Some(extension) = path.extension() {
+        sibling.set_extension(extension);
+    }
+
+    sibling.is_file().then_some(sibling)
+}
+
+/// Whether `path` is the red channel of a set with at least green and blue, which the merged image is made from.
+pub fn is_merge_source(path: &Path) -> bool {
+    let is_red = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| stem.to_ascii_lowercase().ends_with(SUFFIXES[0]));
+
+    is_red && sibling(path, SUFFIXES[1]).is_some() && sibling(path, SUFFIXES[2]).is_some()
+}
+
+/// Whether `path` is a green, blue or alpha channel that is merged into its red channel's output.
+pub fn is_merged_channel(path: &Path) -> bool {
+    sibling(path, SUFFIXES[0]).is_some_and(|red| red != path && is_merge_source(&red))
+}
+
+/// Stem of a merged output, without the channel suffix.
+pub fn merged_stem(stem: &str) -> &str {
+    let base = stem.len().saturating_sub(SUFFIXES[0].len());
+    match stem.to_ascii_lowercase().ends_with(SUFFIXES[0]) {
+        true => &stem[..base],
+        false => stem,
+    }
+}
+
+/// Combines the channel set `red` belongs to into one RGB(A) image.
+pub fn merge(red: &Path) -> Result<DynamicImage, Box<dyn Error>> {
+    let mut channels = Vec::new();
+
+    for suffix in SUFFIXES {
+        let path = match suffix == SUFFIXES[0] {
+            true => Some(red.to_path_buf()),
+            false => sibling(red, suffix),
+        };
+
+        if let Some(path) = path {
+            channels.push(image::open(path)?.to_luma8());
+        }
+    }
+
+    let (width, height) = channels[0].dimensions();
+    if channels
+        .iter()
+        .any(|channel| channel.dimensions() != (width, height))
+    {
+        return Err("Channels have different sizes".into());
+    }
+
+    let img = match channels.len() {
+        4 => DynamicImage::ImageRgba8(ImageBuffer::from_fn(width, height, |x, y| {
+            image::Rgba(std::array::from_fn(|i| channels[i].get_pixel(x, y).0[0]))
+        })),
+        _ => DynamicImage::ImageRgb8(ImageBuffer::from_fn(width, height, |x, y| {
+            image::Rgb(std::array::from_fn(|i| channels[i].get_pixel(x, y).0[0]))
+        })),
+    };
+
+    Ok(img)
+}
@@ -0,0 +1,26 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Saves the image currently on the clipboard as a PNG in `scratch` so it can be queued like any other file.
+pub fn paste_image(scratch: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let mut clipboard = arboard::Clipboard::new()?;
+    let data = clipboard.get_image()?;
+
+    let img = image::RgbaImage::from_raw(
+        data.width as u32,
+        data.height as u32,
+        data.bytes.into_owned(),
+    )
+    .ok_or("Clipboard image has an invalid size")?;
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    fs::create_dir_all(scratch)?;
+    let path = scratch.join(format!("clipboard-{}.png", timestamp));
+    img.save(&path)?;
+
+    Ok(path)
+}
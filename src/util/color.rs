@@ -0,0 +1,200 @@
+use image::{ColorType, DynamicImage, ImageBuffer};
+
+use crate::structs::settings::ToneMapping;
+
+// 8x8 Bayer matrix for ordered dithering
+const BAYER: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// Converts `img` to 32 bit float RGBA with linear light color channels.
+pub fn to_linear(img: &DynamicImage) -> DynamicImage {
+    let mut buf = img.to_rgba32f();
+
+    for pixel in buf.pixels_mut() {
+        for channel in &mut pixel.0[..3] {
+            *channel = srgb_to_linear(*channel);
+        }
+    }
+
+    DynamicImage::ImageRgba32F(buf)
+}
+
+/// Converts a linear light image made by [`to_linear`] back to sRGB in the given color type.
+pub fn from_linear(img: DynamicImage, color: ColorType) -> DynamicImage {
+    let mut buf = img.into_rgba32f();
+
+    for pixel in buf.pixels_mut() {
+        for channel in &mut pixel.0[..3] {
+            *channel = linear_to_srgb(*channel);
+        }
+    }
+
+    convert(DynamicImage::ImageRgba32F(buf), color)
+}
+
+/// Multiplies the color channels of a 32 bit float RGBA image by its alpha.
+pub fn premultiply(img: &mut DynamicImage) {
+    if let Some(buf) = img.as_mut_rgba32f() {
+        for pixel in buf.pixels_mut() {
+            let alpha = pixel.0[3];
+            for channel in &mut pixel.0[..3] {
+                *channel *= alpha;
+            }
+        }
+    }
+}
+
+/// Undoes [`premultiply`], fully transparent pixels stay black.
+pub fn unpremultiply(img: &mut DynamicImage) {
+    if let Some(buf) = img.as_mut_rgba32f() {
+        for pixel in buf.pixels_mut() {
+            let alpha = pixel.0[3];
+            for channel in &mut pixel.0[..3] {
+                *channel = match alpha > 0.0 {
+                    true => *channel / alpha,
+                    false => 0.0,
+                };
+            }
+        }
+    }
+}
+
+/// `img` in the given color type, keeping it as is for types that can't be represented.
+pub fn convert(img: DynamicImage, color: ColorType) -> DynamicImage {
+    match color {
+        ColorType::L8 => DynamicImage::ImageLuma8(img.to_luma8()),
+        ColorType::La8 => DynamicImage::ImageLumaA8(img.to_luma_alpha8()),
+        ColorType::Rgb8 => DynamicImage::ImageRgb8(img.to_rgb8()),
+        ColorType::Rgba8 => DynamicImage::ImageRgba8(img.to_rgba8()),
+        ColorType::L16 => DynamicImage::ImageLuma16(img.to_luma16()),
+        ColorType::La16 => DynamicImage::ImageLumaA16(img.to_luma_alpha16()),
+        ColorType::Rgb16 => DynamicImage::ImageRgb16(img.to_rgb16()),
+        ColorType::Rgba16 => DynamicImage::ImageRgba16(img.to_rgba16()),
+        ColorType::Rgb32F => DynamicImage::ImageRgb32F(img.to_rgb32f()),
+        _ => img,
+    }
+}
+
+/// Bits per channel of `img`, 32 for float images.
+pub fn bit_depth(img: &DynamicImage) -> u8 {
+    let color = img.color();
+    color.bytes_per_pixel() / color.channel_count() * 8
+}
+
+/// Reduces `img` to at most `max_depth` bits per channel, the most the output format stores.
+/// Float images become 16 bit when the format keeps more than 8 bits. 10 bit formats get 16 bit
+/// images and scale them down in their encoder.
+pub fn reduce_depth(img: DynamicImage, max_depth: u8, dither: bool) -> DynamicImage {
+    if bit_depth(&img) <= max_depth {
+        return img;
+    }
+
+    match max_depth > 8 {
+        true => match img.color().has_alpha() {
+            true => DynamicImage::ImageRgba16(img.to_rgba16()),
+            false => DynamicImage::ImageRgb16(img.to_rgb16()),
+        },
+        false => to_8bit(img, dither),
+    }
+}
+
+/// Reduces 16 bit and float images to 8 bits per channel, other images are returned as is.
+///
+/// With `dither` an ordered dither pattern is added before rounding, which breaks up banding in gradients.
+pub fn to_8bit(img: DynamicImage, dither: bool) -> DynamicImage {
+    let color = img.color();
+    if color.bytes_per_pixel() / color.channel_count() <= 1 {
+        return img;
+    }
+
+    if !dither {
+        return match color.has_alpha() {
+            true => DynamicImage::ImageRgba8(img.to_rgba8()),
+            false => DynamicImage::ImageRgb8(img.to_rgb8()),
+        };
+    }
+
+    let has_alpha = color.has_alpha();
+    let buf = img.into_rgba32f();
+    let channels = if has_alpha { 4 } else { 3 };
+
+    let mut data = Vec::with_capacity(buf.width() as usize * buf.height() as usize * channels);
+    for (x, y, pixel) in buf.enumerate_pixels() {
+        // Offset in -0.5..0.5 of an 8 bit step
+        let offset = (BAYER[y as usize % 8][x as usize % 8] as f32 + 0.5) / 64.0 - 0.5;
+
+        data.extend(
+            pixel.0[..channels]
+                .iter()
+                .map(|value| (value * 255.0 + offset).round().clamp(0.0, 255.0) as u8),
+        );
+    }
+
+    let (width, height) = buf.dimensions();
+    match has_alpha {
+        true => ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageRgba8),
+        false => ImageBuffer::from_raw(width, height, data).map(DynamicImage::ImageRgb8),
+    }
+    .expect("buffer matches the image size")
+}
+
+/// Maps a linear HDR image (EXR, Radiance) to sRGB in the 0 to 1 range, other images are returned
+/// as is.
+///
+/// `exposure` is in stops and applied before the tone mapping operator. The result stays in
+/// 32 bit float so resizing, dithering and [`reduce_depth`] work on the full precision.
+pub fn tone_map(img: DynamicImage, operator: ToneMapping, exposure: f32) -> DynamicImage {
+    if !matches!(img.color(), ColorType::Rgb32F | ColorType::Rgba32F) {
+        return img;
+    }
+
+    let has_alpha = img.color().has_alpha();
+    let mut buf = img.into_rgba32f();
+    let scale = 2f32.powf(exposure);
+
+    for pixel in buf.pixels_mut() {
+        for channel in &mut pixel.0[..3] {
+            let value = channel.max(0.0) * scale;
+            let mapped = match operator {
+                ToneMapping::Clamp => value,
+                ToneMapping::Reinhard => value / (1.0 + value),
+                // Narkowicz's fit of the ACES filmic curve
+                ToneMapping::Aces => {
+                    (value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14)
+                }
+            };
+            *channel = linear_to_srgb(mapped);
+        }
+    }
+
+    let img = DynamicImage::ImageRgba32F(buf);
+    match has_alpha {
+        true => img,
+        false => DynamicImage::ImageRgb32F(img.to_rgb32f()),
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    match value <= 0.04045 {
+        true => value / 12.92,
+        false => ((value + 0.055) / 1.055).powf(2.4),
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    // Lanczos overshoots, clamp before the power function
+    let value = value.clamp(0.0, 1.0);
+
+    match value <= 0.0031308 {
+        true => value * 12.92,
+        false => 1.055 * value.powf(1.0 / 2.4) - 0.055,
+    }
+}
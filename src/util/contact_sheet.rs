@@ -0,0 +1,98 @@
+use std::error::Error;
+
+use image::{imageops, Rgba, RgbaImage};
+use resvg::{tiny_skia, usvg};
+
+pub static CONTACT_SHEET_FILE: &str = "contact-sheet.png";
+
+// Space around and between cells
+const PADDING: u32 = 8;
+// Height of the filename strip below each cell
+const LABEL_HEIGHT: u32 = 20;
+const FONT_SIZE: u32 = 12;
+const BACKGROUND: Rgba<u8> = Rgba([255, 255, 255, 255]);
+// Most thumbnails one sheet holds, it's a single image kept in memory until it's written
+pub const MAX_CELLS: usize = 2000;
+
+/// Lays `cells` out in rows of `columns`, each thumbnail centered in its cell with its name below it.
+pub fn build(
+    cells: &[(String, RgbaImage)],
+    columns: u32,
+    cell_size: u32,
+    labels: bool,
+) -> Result<RgbaImage, Box<dyn Error>> {
+    if cells.is_empty() {
+        return Err("nothing to put on the sheet".into());
+    }
+
+    let columns = columns.clamp(1, cells.len() as u32);
+    let rows = (cells.len() as u32).div_ceil(columns);
+    let label_height = if labels { LABEL_HEIGHT } else { 0 };
+    let (cell_width, cell_height) = (cell_size + PADDING, cell_size + label_height + PADDING);
+
+    let mut sheet = RgbaImage::from_pixel(
+        columns * cell_width + PADDING,
+        rows * cell_height + PADDING,
+        BACKGROUND,
+    );
+
+    // Loading system fonts is slow, done once for every label
+    let options = labels.then(|| {
+        let mut options = usvg::Options::default();
+        options.fontdb_mut().load_system_fonts();
+        options
+    });
+
+    for (i, (name, thumb)) in cells.iter().enumerate() {
+        let (column, row) = (i as u32 % columns, i as u32 / columns);
+        let (x, y) = (PADDING + column * cell_width, PADDING + row * cell_height);
+
+        imageops::overlay(
+            &mut sheet,
+            thumb,
+            (x + cell_size.saturating_sub(thumb.width()) / 2) as i64,
+            (y + cell_size.saturating_sub(thumb.height()) / 2) as i64,
+        );
+
+        if let Some(options) = &options {
+            let label = label(name, cell_size, options)?;
+            imageops::overlay(&mut sheet, &label, x as i64, (y + cell_size) as i64);
+        }
+    }
+
+    Ok(sheet)
+}
+
+/// `text` centered in a `width` pixel strip, long names are cut off at the edges.
+fn label(text: &str, width: u32, options: &usvg::Options) -> Result<RgbaImage, Box<dyn Error>> {
+    let svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{LABEL_HEIGHT}"><text x="{}" y="{}" font-family="sans-serif" font-size="{FONT_SIZE}" fill="#333333" text-anchor="middle">{}</text></svg>"##,
+        width / 2,
+        LABEL_HEIGHT - (LABEL_HEIGHT - FONT_SIZE) / 2 - 2,
+        escape(text)
+    );
+    let tree = usvg::Tree::from_str(&svg, options)?;
+
+    let mut pixmap =
+        tiny_skia::Pixmap::new(width, LABEL_HEIGHT).ok_or("label is too large to render")?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+
+    // Pixmaps are premultiplied
+    let data = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+
+    Ok(RgbaImage::from_raw(width, LABEL_HEIGHT, data).ok_or("label has an invalid size")?)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
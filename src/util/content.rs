@@ -0,0 +1,41 @@
+use image::DynamicImage;
+
+// Average neighbouring luma difference that keeps the configured quality as is
+const NEUTRAL_DETAIL: f32 = 10.0;
+const MAX_ADJUSTMENT: f32 = 10.0;
+
+/// Raises `quality` for detailed images and lowers it for flat ones.
+pub fn adaptive_quality(quality: u8, img: &DynamicImage) -> u8 {
+    let offset = (detail(img) - NEUTRAL_DETAIL).clamp(-MAX_ADJUSTMENT, MAX_ADJUSTMENT);
+    (quality as f32 + offset).round().clamp(5.0, 100.0) as u8
+}
+
+/// Mean absolute luma difference between adjacent pixels, sampled on a thumbnail to keep it cheap.
+fn detail(img: &DynamicImage) -> f32 {
+    let luma = img.thumbnail(256, 256).to_luma8();
+    let (width, height) = luma.dimensions();
+
+    let mut total = 0u64;
+    let mut count = 0u64;
+
+    for y in 0..height {
+        for x in 0..width {
+            let value = luma.get_pixel(x, y)[0] as i32;
+
+            if x + 1 < width {
+                total += value.abs_diff(luma.get_pixel(x + 1, y)[0] as i32) as u64;
+                count += 1;
+            }
+
+            if y + 1 < height {
+                total += value.abs_diff(luma.get_pixel(x, y + 1)[0] as i32) as u64;
+                count += 1;
+            }
+        }
+    }
+
+    match count {
+        0 => NEUTRAL_DETAIL,
+        _ => total as f32 / count as f32,
+    }
+}
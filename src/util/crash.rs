@@ -0,0 +1,149 @@
+use std::{
+    backtrace::Backtrace,
+    cell::Cell,
+    collections::VecDeque,
+    fs,
+    panic::{self, PanicHookInfo},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::structs::settings::Settings;
+
+pub static CRASH_FOLDER: &str = "crash-reports";
+// Path of the newest report nobody has been shown yet
+static UNSEEN_FILE: &str = "unseen";
+// Log lines kept for reports
+const LOG_LINES: usize = 50;
+
+static LOG: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static SETTINGS: Mutex<String> = Mutex::new(String::new());
+static CRASHED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    // Set while a panic is expected and caught, it isn't a crash then
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Writes a crash report to the crash folder whenever a thread panics, next to the usual message.
+pub fn install() {
+    let default_hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        if CATCHING.get() {
+            return;
+        }
+
+        default_hook(info);
+
+        match write_report(info) {
+            Ok(path) => eprintln!("Crash report written to '{}'", path.display()),
+            Err(e) => eprintln!("Failed to write crash report: {}", e),
+        }
+    }));
+}
+
+/// Runs `f` and catches its panics without reporting them, for libraries that report errors
+/// by unwinding.
+#[cfg(feature = "mozjpeg")]
+pub fn catch_unwind<R>(f: impl FnOnce() -> R + panic::UnwindSafe) -> thread::Result<R> {
+    let catching = CATCHING.replace(true);
+    let result = panic::catch_unwind(f);
+    CATCHING.set(catching);
+
+    result
+}
+
+/// Keeps `line` for crash reports.
+pub fn log(line: &str) {
+    let mut log = LOG.lock().unwrap_or_else(|e| e.into_inner());
+    log.push_back(line.to_string());
+
+    if log.len() > LOG_LINES {
+        log.pop_front();
+    }
+}
+
+/// Keeps the settings of the current run for crash reports, without anything that names files or servers.
+pub fn set_settings(settings: &Settings) {
+    *SETTINGS.lock().unwrap_or_else(|e| e.into_inner()) = format!("{:#?}", settings.redacted());
+}
+
+/// The report of a crash that hasn't been shown yet, from this or an earlier session.
+pub fn take_unseen() -> Option<PathBuf> {
+    let unseen = Path::new(CRASH_FOLDER).join(UNSEEN_FILE);
+    let path = fs::read_to_string(&unseen).ok()?;
+    fs::remove_file(&unseen).ok()?;
+
+    Some(PathBuf::from(path))
+}
+
+/// Whether a thread panicked since the last call, the app itself keeps running after worker panics.
+pub fn crashed() -> bool {
+    CRASHED.swap(false, Ordering::Relaxed)
+}
+
+/// Opens the crash folder in the file manager.
+pub fn open_folder() -> std::io::Result<()> {
+    let program = match std::env::consts::OS {
+        "windows" => "explorer",
+        "macos" => "open",
+        _ => "xdg-open",
+    };
+
+    Command::new(program).arg(CRASH_FOLDER).spawn()?;
+    Ok(())
+}
+
+fn write_report(info: &PanicHookInfo) -> std::io::Result<PathBuf> {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    // The panicking thread may hold either lock, an empty section beats a deadlock
+    let log = match LOG.try_lock() {
+        Ok(log) => log.iter().cloned().collect::<Vec<_>>().join("\n"),
+        Err(_) => String::new(),
+    };
+    let settings = match SETTINGS.try_lock() {
+        Ok(settings) => settings.clone(),
+        Err(_) => String::new(),
+    };
+
+    let report = format!(
+        "Image converter {} ({}, {} {}) crashed at {}\n\
+         Thread: {}\n\
+         {}\n\n\
+         Backtrace:\n{}\n\n\
+         Settings:\n{}\n\n\
+         Log:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        env!("BUILD_COMMIT"),
+        env!("BUILD_TARGET"),
+        env!("BUILD_PROFILE"),
+        time,
+        thread::current().name().unwrap_or("unnamed"),
+        info,
+        Backtrace::force_capture(),
+        settings,
+        log,
+    );
+
+    fs::create_dir_all(CRASH_FOLDER)?;
+    let path = Path::new(CRASH_FOLDER).join(format!("crash-{}.txt", time));
+    fs::write(&path, report)?;
+    fs::write(
+        Path::new(CRASH_FOLDER).join(UNSEEN_FILE),
+        path.to_string_lossy().as_bytes(),
+    )?;
+    CRASHED.store(true, Ordering::Relaxed);
+
+    Ok(path)
+}
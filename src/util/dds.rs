@@ -0,0 +1,66 @@
+use std::{error::Error, fs::File, io::BufReader, path::Path};
+
+use ddsfile::{D3DFormat, Dds, DxgiFormat};
+use image::{DynamicImage, RgbaImage};
+use texpresso::Format;
+
+pub fn is_dds(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("dds"))
+}
+
+enum Layout {
+    Compressed(Format),
+    Rgba,
+    Bgra,
+}
+
+/// The top mip level of a DDS texture.
+pub fn decode(path: &Path) -> Result<DynamicImage, Box<dyn Error>> {
+    let dds = Dds::read(&mut BufReader::new(File::open(path)?))?;
+    let (width, height) = (dds.get_width(), dds.get_height());
+
+    let layout = match (dds.get_dxgi_format(), dds.get_d3d_format()) {
+        (Some(DxgiFormat::BC1_UNorm | DxgiFormat::BC1_UNorm_sRGB), _)
+        | (_, Some(D3DFormat::DXT1)) => Layout::Compressed(Format::Bc1),
+        (Some(DxgiFormat::BC2_UNorm | DxgiFormat::BC2_UNorm_sRGB), _)
+        | (_, Some(D3DFormat::DXT2 | D3DFormat::DXT3)) => Layout::Compressed(Format::Bc2),
+        (Some(DxgiFormat::BC3_UNorm | DxgiFormat::BC3_UNorm_sRGB), _)
+        | (_, Some(D3DFormat::DXT4 | D3DFormat::DXT5)) => Layout::Compressed(Format::Bc3),
+        (Some(DxgiFormat::BC4_UNorm), _) => Layout::Compressed(Format::Bc4),
+        (Some(DxgiFormat::BC5_UNorm), _) => Layout::Compressed(Format::Bc5),
+        (Some(DxgiFormat::R8G8B8A8_UNorm | DxgiFormat::R8G8B8A8_UNorm_sRGB), _)
+        | (_, Some(D3DFormat::A8B8G8R8)) => Layout::Rgba,
+        (Some(DxgiFormat::B8G8R8A8_UNorm | DxgiFormat::B8G8R8A8_UNorm_sRGB), _)
+        | (_, Some(D3DFormat::A8R8G8B8)) => Layout::Bgra,
+        (Some(format), _) => return Err(format!("Unsupported DDS format {:?}", format).into()),
+        (None, format) => return Err(format!("Unsupported DDS format {:?}", format).into()),
+    };
+
+    let data = dds.get_data(0)?;
+    let mut pixels = vec![0; width as usize * height as usize * 4];
+
+    match layout {
+        Layout::Compressed(format) => {
+            if data.len() < format.compressed_size(width as usize, height as usize) {
+                return Err("DDS texture is truncated".into());
+            }
+            format.decompress(data, width as usize, height as usize, &mut pixels);
+        }
+        Layout::Rgba | Layout::Bgra => {
+            let data = data.get(..pixels.len()).ok_or("DDS texture is truncated")?;
+            pixels.copy_from_slice(data);
+
+            if matches!(layout, Layout::Bgra) {
+                pixels
+                    .chunks_exact_mut(4)
+                    .for_each(|pixel| pixel.swap(0, 2));
+            }
+        }
+    }
+
+    let buf = RgbaImage::from_raw(width, height, pixels).ok_or("DDS has an invalid size")?;
+
+    Ok(DynamicImage::ImageRgba8(buf))
+}
@@ -0,0 +1,232 @@
+use std::{
+    error::Error,
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use image::{
+    codecs::jpeg::JpegEncoder, ColorType, DynamicImage, ImageDecoder, ImageFormat, ImageReader,
+};
+
+use crate::{structs::settings::PageSize, util::files::sniff_format};
+
+pub static COMBINED_PDF_FILE: &str = "combined.pdf";
+
+// PDF user space units per millimetre
+const POINTS_PER_MM: f32 = 72.0 / 25.4;
+// Images placed on fitted pages are shown at 96 dpi
+const POINTS_PER_PIXEL: f32 = 0.75;
+// Quality non-JPEG outputs are embedded with
+const JPEG_QUALITY: u8 = 90;
+
+/// A JPEG stream in a scratch file, ready to be placed on a page.
+pub struct Page {
+    pub file: PathBuf,
+    width: u32,
+    height: u32,
+    color_space: &'static str,
+}
+
+/// Writes a PDF with one page per entry in `pages` to `out`, each image centered and scaled
+/// down to fit inside `margin` millimetres from the page edges. Page images are read from their
+/// files one at a time.
+pub fn combine<W: Write>(
+    pages: &[Page],
+    page: PageSize,
+    margin: u32,
+    out: W,
+) -> Result<(), Box<dyn Error>> {
+    let mut pdf = Writer::new(out);
+    pdf.write(b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n")?;
+
+    // Catalog and page tree are objects 1 and 2, pages take 3 objects each from there
+    let kids: Vec<String> = (0..pages.len())
+        .map(|i| format!("{} 0 R", 3 + i * 3))
+        .collect();
+    pdf.object(b"<< /Type /Catalog /Pages 2 0 R >>")?;
+    pdf.object(
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        )
+        .as_bytes(),
+    )?;
+
+    let margin = margin as f32 * POINTS_PER_MM;
+
+    for (i, image) in pages.iter().enumerate() {
+        let (page_width, page_height) = match page {
+            PageSize::A4 => (210.0 * POINTS_PER_MM, 297.0 * POINTS_PER_MM),
+            PageSize::Letter => (8.5 * 72.0, 11.0 * 72.0),
+            PageSize::Fit => (
+                image.width as f32 * POINTS_PER_PIXEL + margin * 2.0,
+                image.height as f32 * POINTS_PER_PIXEL + margin * 2.0,
+            ),
+        };
+
+        // Never enlarged past 96 dpi, small receipts stay small on the page
+        let available_width = (page_width - margin * 2.0).max(1.0);
+        let available_height = (page_height - margin * 2.0).max(1.0);
+        let scale = (available_width / image.width as f32)
+            .min(available_height / image.height as f32)
+            .min(POINTS_PER_PIXEL);
+        let (width, height) = (image.width as f32 * scale, image.height as f32 * scale);
+        let (x, y) = ((page_width - width) / 2.0, (page_height - height) / 2.0);
+
+        let page_id = 3 + i * 3;
+        pdf.object(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources << /XObject << /Im0 {} 0 R >> >> /Contents {} 0 R >>",
+                page_width,
+                page_height,
+                page_id + 2,
+                page_id + 1
+            )
+            .as_bytes(),
+        )?;
+
+        let content = format!(
+            "q {:.2} 0 0 {:.2} {:.2} {:.2} cm /Im0 Do Q",
+            width, height, x, y
+        );
+        pdf.stream(
+            &format!("<< /Length {} >>", content.len()),
+            content.len() as u64,
+            content.as_bytes(),
+        )?;
+
+        let file = fs::File::open(&image.file)?;
+        let length = file.metadata()?.len();
+        pdf.stream(
+            &format!(
+                "<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /{} /BitsPerComponent 8 /Filter /DCTDecode /Length {} >>",
+                image.width,
+                image.height,
+                image.color_space,
+                length
+            ),
+            length,
+            file,
+        )?;
+    }
+
+    pdf.finish()?;
+    Ok(())
+}
+
+/// `img` as a page, encoded as a JPEG into `file`.
+pub fn image_page(img: &DynamicImage, file: &Path) -> Result<Page, Box<dyn Error>> {
+    // Transparency is flattened onto white paper
+    let mut rgba = img.to_rgba8();
+    for pixel in rgba.pixels_mut() {
+        let alpha = pixel[3] as u32;
+        for channel in pixel.0.iter_mut().take(3) {
+            *channel = ((*channel as u32 * alpha + 255 * (255 - alpha)) / 255) as u8;
+        }
+    }
+    let rgb = DynamicImage::ImageRgba8(rgba).to_rgb8();
+
+    let mut data = Vec::new();
+    rgb.write_with_encoder(JpegEncoder::new_with_quality(&mut data, JPEG_QUALITY))?;
+    fs::write(file, data)?;
+
+    Ok(Page {
+        file: file.to_path_buf(),
+        width: rgb.width(),
+        height: rgb.height(),
+        color_space: "DeviceRGB",
+    })
+}
+
+/// The image at `path` as a page in `file`, JPEGs are copied without re-encoding.
+pub fn file_page(path: &Path, file: &Path) -> Result<Page, Box<dyn Error>> {
+    let decoder = ImageReader::open(path)?
+        .with_guessed_format()?
+        .into_decoder()?;
+    let color = decoder.color_type();
+
+    if sniff_format(path) == Some(ImageFormat::Jpeg)
+        && matches!(color, ColorType::L8 | ColorType::Rgb8)
+    {
+        let (width, height) = decoder.dimensions();
+        fs::copy(path, file)?;
+        return Ok(Page {
+            file: file.to_path_buf(),
+            width,
+            height,
+            color_space: match color {
+                ColorType::L8 => "DeviceGray",
+                _ => "DeviceRGB",
+            },
+        });
+    }
+
+    image_page(&DynamicImage::from_decoder(decoder)?, file)
+}
+
+/// Numbers objects in the order they're written and keeps their offsets for the xref table.
+struct Writer<W: Write> {
+    out: W,
+    position: u64,
+    offsets: Vec<u64>,
+}
+
+impl<W: Write> Writer<W> {
+    fn new(out: W) -> Self {
+        Self {
+            out,
+            position: 0,
+            offsets: Vec::new(),
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.out.write_all(data)?;
+        self.position += data.len() as u64;
+        Ok(())
+    }
+
+    fn object(&mut self, body: &[u8]) -> io::Result<()> {
+        self.offsets.push(self.position);
+        self.write(format!("{} 0 obj\n", self.offsets.len()).as_bytes())?;
+        self.write(body)?;
+        self.write(b"\nendobj\n")
+    }
+
+    /// Writes a stream object whose `length` bytes of data are copied from `data`.
+    fn stream(&mut self, dictionary: &str, length: u64, data: impl Read) -> io::Result<()> {
+        self.offsets.push(self.position);
+        self.write(format!("{} 0 obj\n", self.offsets.len()).as_bytes())?;
+        self.write(dictionary.as_bytes())?;
+        self.write(b"\nstream\n")?;
+
+        let copied = io::copy(&mut data.take(length), &mut self.out)?;
+        if copied != length {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "page image is shorter than expected",
+            ));
+        }
+        self.position += copied;
+
+        self.write(b"\nendstream\nendobj\n")
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        let xref = self.position;
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1);
+        for offset in &self.offsets {
+            table.push_str(&format!("{:010} 00000 n \n", offset));
+        }
+        table.push_str(&format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.offsets.len() + 1,
+            xref
+        ));
+
+        self.write(table.as_bytes())?;
+        self.out.flush()
+    }
+}
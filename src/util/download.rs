@@ -0,0 +1,89 @@
+use std::{
+    error::Error,
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// Refuse anything larger than this to avoid filling the disk with a bad link
+const MAX_DOWNLOAD_SIZE: u64 = 256 * 1024 * 1024;
+// A server that stops responding fails the download instead of holding up its worker
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+static DOWNLOAD_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+pub fn is_url(path: &Path) -> bool {
+    path.to_str()
+        .is_some_and(|path| path.starts_with("http://") || path.starts_with("https://"))
+}
+
+/// Downloads `url` into a folder in `scratch`, keeping the remote file name so outputs are named after it.
+pub fn download_image(url: &str, scratch: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let response = ureq::builder()
+        .timeout_connect(TIMEOUT)
+        .timeout_read(TIMEOUT)
+        .build()
+        .get(url)
+        .call()?;
+
+    let mut file_name = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|url| url.trim_end_matches('/').rsplit('/').next())
+        .filter(|name| !name.is_empty() && !name.contains(':'))
+        .unwrap_or("download")
+        .to_string();
+
+    // Name the file after its content type when the url has no usable extension
+    if Path::new(&file_name).extension().is_none() {
+        if let Some(format) = image::ImageFormat::from_mime_type(response.content_type()) {
+            file_name.push('.');
+            file_name.push_str(format.extensions_str()[0]);
+        }
+    }
+
+    // One byte past the limit tells a file of exactly the limit from a larger one
+    let mut data = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_DOWNLOAD_SIZE + 1)
+        .read_to_end(&mut data)?;
+
+    if data.len() as u64 > MAX_DOWNLOAD_SIZE {
+        return Err(format!(
+            "download is larger than {} MB",
+            MAX_DOWNLOAD_SIZE / 1024 / 1024
+        )
+        .into());
+    }
+
+    // Unique folder per download so equally named remote files don't collide
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    let count = DOWNLOAD_COUNT.fetch_add(1, Ordering::Relaxed);
+    let folder = scratch
+        .join("downloads")
+        .join(format!("{}-{}", timestamp, count));
+    fs::create_dir_all(&folder)?;
+
+    let path = folder.join(file_name);
+    fs::write(&path, data)?;
+
+    Ok(path)
+}
+
+/// Whether `error` or one of its causes is a connection or read that timed out.
+pub fn is_timeout(error: &(dyn Error + 'static)) -> bool {
+    let mut error = Some(error);
+    while let Some(e) = error {
+        if e.downcast_ref::<io::Error>()
+            .is_some_and(|e| e.kind() == io::ErrorKind::TimedOut)
+        {
+            return true;
+        }
+        error = e.source();
+    }
+
+    false
+}
@@ -0,0 +1,569 @@
+use std::{fs, path::Path};
+
+use image::{codecs::jpeg::JpegEncoder, DynamicImage};
+
+use crate::{
+    structs::{error::ConvertError, file_type::EncodingOptions, settings::Settings},
+    util::xmp,
+};
+
+// Thumbnail sizes and qualities tried in turn until the metadata fits in its segment
+const THUMBNAIL_STEPS: [(u32, u8); 3] = [(160, 75), (120, 60), (80, 50)];
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+// Tiff tags
+const COMPRESSION: u16 = 0x0103;
+const ORIENTATION: u16 = 0x0112;
+const X_RESOLUTION: u16 = 0x011A;
+const Y_RESOLUTION: u16 = 0x011B;
+const RESOLUTION_UNIT: u16 = 0x0128;
+const DATE_TIME: u16 = 0x0132;
+const JPEG_INTERCHANGE_FORMAT: u16 = 0x0201;
+const JPEG_INTERCHANGE_FORMAT_LENGTH: u16 = 0x0202;
+const EXIF_IFD: u16 = 0x8769;
+const GPS_IFD: u16 = 0x8825;
+const DATE_TIME_ORIGINAL: u16 = 0x9003;
+const DATE_TIME_DIGITIZED: u16 = 0x9004;
+const INTEROP_IFD: u16 = 0xA005;
+
+// Tiff field types
+const ASCII: u16 = 2;
+const SHORT: u16 = 3;
+const LONG: u16 = 4;
+const RATIONAL: u16 = 5;
+
+/// Adds EXIF metadata to a JPEG output: the source's own when kept, with shifted timestamps,
+/// and a thumbnail generated from the output pixels when enabled.
+pub fn apply(
+    jpeg: Vec<u8>,
+    source: &Path,
+    img: &DynamicImage,
+    settings: &Settings,
+) -> Result<Vec<u8>, ConvertError> {
+    let EncodingOptions::Jpeg(options) = &settings.encoding_options else {
+        return Ok(jpeg);
+    };
+
+    let mut tiff = match settings.keep_exif {
+        true => fs::read(source).ok().and_then(|data| read_exif(&data)),
+        false => None,
+    };
+
+    if let Some(tiff) = &mut tiff {
+        if settings.time_shift != 0 {
+            shift_timestamps(tiff, settings.time_shift);
+        }
+
+        // The pixels were already rotated to match the sidecar
+        let rotated = settings.use_sidecars
+            && xmp::read_sidecar(source).is_some_and(|sidecar| sidecar.orientation.is_some());
+        if rotated {
+            reset_orientation(tiff);
+        }
+
+        // It shows the source before resizing and cropping
+        strip_thumbnail(tiff);
+    }
+
+    if options.thumbnail {
+        tiff = add_thumbnail(tiff, img)?;
+    }
+
+    let Some(tiff) = tiff else {
+        return Ok(jpeg);
+    };
+
+    let segment = app1_segment(&tiff)
+        .ok_or_else(|| ConvertError::Encode("EXIF data is too large".to_string()))?;
+
+    Ok(insert_segment(&jpeg, &segment))
+}
+
+/// When a JPEG or PNG was taken as "YYYY:MM:DD HH:MM:SS", DateTimeOriginal with DateTime as
+/// a fallback.
+pub fn date_taken(path: &Path) -> Option<String> {
+    let tiff = read_exif(&fs::read(path).ok()?)?;
+    let big_endian = endianness(&tiff)?;
+    let ifd0 = read_u32(&tiff, 4, big_endian)? as usize;
+    let (entries, _) = ifd_entries(&tiff, ifd0, big_endian)?;
+
+    let mut date_time = None;
+
+    for (entry, tag) in entries {
+        match tag {
+            DATE_TIME => date_time = read_date(&tiff, entry, big_endian),
+            EXIF_IFD => {
+                let exif_ifd = read_u32(&tiff, entry + 8, big_endian)? as usize;
+                let original = ifd_entries(&tiff, exif_ifd, big_endian)
+                    .and_then(|(entries, _)| {
+                        entries
+                            .into_iter()
+                            .find(|(_, tag)| *tag == DATE_TIME_ORIGINAL)
+                    })
+                    .and_then(|(entry, _)| read_date(&tiff, entry, big_endian));
+
+                if original.is_some() {
+                    return original;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    date_time
+}
+
+fn read_date(tiff: &[u8], entry: usize, big_endian: bool) -> Option<String> {
+    if read_u16(tiff, entry + 2, big_endian)? != ASCII
+        || read_u32(tiff, entry + 4, big_endian)? < 19
+    {
+        return None;
+    }
+
+    let value = read_u32(tiff, entry + 8, big_endian)? as usize;
+    std::str::from_utf8(tiff.get(value..value + 19)?)
+        .ok()
+        .map(str::to_string)
+}
+
+/// `tiff` with a thumbnail of `img`, made smaller until the metadata fits in an APP1 segment.
+/// Left without one when even the smallest doesn't fit.
+fn add_thumbnail(
+    tiff: Option<Vec<u8>>,
+    img: &DynamicImage,
+) -> Result<Option<Vec<u8>>, ConvertError> {
+    for (size, quality) in THUMBNAIL_STEPS {
+        let thumbnail = encode_thumbnail(img, size, quality)?;
+
+        let with_thumbnail = tiff
+            .clone()
+            .and_then(|mut tiff| attach_thumbnail(&mut tiff, &thumbnail).map(|_| tiff))
+            .unwrap_or_else(|| thumbnail_tiff(&thumbnail));
+
+        if app1_segment(&with_thumbnail).is_some() {
+            return Ok(Some(with_thumbnail));
+        }
+    }
+
+    Ok(tiff)
+}
+
+fn encode_thumbnail(img: &DynamicImage, size: u32, quality: u8) -> Result<Vec<u8>, ConvertError> {
+    let thumbnail = img.thumbnail(size, size).to_rgb8();
+
+    let mut data = Vec::new();
+    thumbnail
+        .write_with_encoder(JpegEncoder::new_with_quality(&mut data, quality))
+        .map_err(ConvertError::from_encoding)?;
+
+    Ok(data)
+}
+
+/// Raw tiff structure of the EXIF data in a JPEG (APP1) or PNG (eXIf chunk).
+fn read_exif(data: &[u8]) -> Option<Vec<u8>> {
+    if data.starts_with(&[0xFF, 0xD8]) {
+        let mut position = 2;
+
+        loop {
+            let marker = data.get(position..position + 4)?;
+            if marker[0] != 0xFF || marker[1] == 0xDA {
+                return None;
+            }
+
+            let length = u16::from_be_bytes([marker[2], marker[3]]) as usize;
+
+            if marker[1] == 0xE1 && data.get(position + 4..position + 10)? == b"Exif\0\0" {
+                return data
+                    .get(position + 10..position + 2 + length)
+                    .map(<[u8]>::to_vec);
+            }
+
+            position += 2 + length;
+        }
+    }
+
+    if data.starts_with(&PNG_SIGNATURE) {
+        let mut position = 8;
+
+        while let Some(header) = data.get(position..position + 8) {
+            let length = u32::from_be_bytes(header[..4].try_into().ok()?) as usize;
+
+            if &header[4..] == b"eXIf" {
+                return data
+                    .get(position + 8..position + 8 + length)
+                    .map(<[u8]>::to_vec);
+            }
+
+            // Length, type, data and crc
+            position += 12 + length;
+        }
+    }
+
+    None
+}
+
+/// Moves DateTime, DateTimeOriginal and DateTimeDigitized by `seconds`, in place.
+fn shift_timestamps(tiff: &mut [u8], seconds: i64) -> Option<()> {
+    let big_endian = endianness(tiff)?;
+    let ifd0 = read_u32(tiff, 4, big_endian)? as usize;
+    let (entries, _) = ifd_entries(tiff, ifd0, big_endian)?;
+
+    let mut targets = Vec::new();
+
+    for (entry, tag) in entries {
+        match tag {
+            DATE_TIME => targets.push(entry),
+            EXIF_IFD => {
+                let exif_ifd = read_u32(tiff, entry + 8, big_endian)? as usize;
+                if let Some((exif_entries, _)) = ifd_entries(tiff, exif_ifd, big_endian) {
+                    targets.extend(
+                        exif_entries
+                            .into_iter()
+                            .filter(|(_, tag)| {
+                                [DATE_TIME_ORIGINAL, DATE_TIME_DIGITIZED].contains(tag)
+                            })
+                            .map(|(entry, _)| entry),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for entry in targets {
+        // "YYYY:MM:DD HH:MM:SS" plus a terminator doesn't fit in the entry, so it is stored at an offset
+        if read_u16(tiff, entry + 2, big_endian)? != ASCII
+            || read_u32(tiff, entry + 4, big_endian)? < 19
+        {
+            continue;
+        }
+
+        let value = read_u32(tiff, entry + 8, big_endian)? as usize;
+        let shifted = tiff
+            .get(value..value + 19)
+            .and_then(|text| std::str::from_utf8(text).ok())
+            .and_then(|text| shift_date(text, seconds));
+
+        if let Some(shifted) = shifted {
+            tiff[value..value + 19].copy_from_slice(shifted.as_bytes());
+        }
+    }
+
+    Some(())
+}
+
+/// Sets the orientation in IFD0 to upright, in place.
+fn reset_orientation(tiff: &mut [u8]) -> Option<()> {
+    let big_endian = endianness(tiff)?;
+    let ifd0 = read_u32(tiff, 4, big_endian)? as usize;
+    let (entries, _) = ifd_entries(tiff, ifd0, big_endian)?;
+
+    let (entry, _) = entries.into_iter().find(|(_, tag)| *tag == ORIENTATION)?;
+    if read_u16(tiff, entry + 2, big_endian)? != SHORT {
+        return None;
+    }
+
+    tiff.get_mut(entry + 8..entry + 10)?
+        .copy_from_slice(&to_bytes_u16(1, big_endian));
+
+    Some(())
+}
+
+fn shift_date(text: &str, seconds: i64) -> Option<String> {
+    let (date, time) = text.split_once(' ')?;
+    let date: Vec<i64> = date
+        .split(':')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let time: Vec<i64> = time
+        .split(':')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+
+    let [year, month, day]: [i64; 3] = date.try_into().ok()?;
+    let [hour, minute, second]: [i64; 3] = time.try_into().ok()?;
+
+    let timestamp =
+        days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second + seconds;
+    let (year, month, day) = civil_from_days(timestamp.div_euclid(86400));
+    let time_of_day = timestamp.rem_euclid(86400);
+
+    if !(0..=9999).contains(&year) {
+        return None;
+    }
+
+    Some(format!(
+        "{:04}:{:02}:{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time_of_day / 3600,
+        time_of_day % 3600 / 60,
+        time_of_day % 60
+    ))
+}
+
+// Proleptic gregorian calendar conversions, days relative to 1970-01-01
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+/// Points IFD0 at a new thumbnail IFD appended to existing metadata.
+fn attach_thumbnail(tiff: &mut Vec<u8>, thumbnail: &[u8]) -> Option<()> {
+    let big_endian = endianness(tiff)?;
+    let ifd0 = read_u32(tiff, 4, big_endian)? as usize;
+    let (_, next_ifd) = ifd_entries(tiff, ifd0, big_endian)?;
+    read_u32(tiff, next_ifd, big_endian)?;
+
+    // IFDs start on a word boundary
+    if !tiff.len().is_multiple_of(2) {
+        tiff.push(0);
+    }
+
+    let ifd1 = tiff.len() as u32;
+    tiff[next_ifd..next_ifd + 4].copy_from_slice(&to_bytes_u32(ifd1, big_endian));
+    write_thumbnail_ifd(tiff, thumbnail, big_endian);
+
+    Some(())
+}
+
+/// Unlinks the thumbnail IFD of kept metadata, in place. It and its image are cut off as well
+/// when nothing else is stored after them, which is where cameras put them.
+fn strip_thumbnail(tiff: &mut Vec<u8>) -> Option<()> {
+    let big_endian = endianness(tiff)?;
+    let ifd0 = read_u32(tiff, 4, big_endian)? as usize;
+    let (_, next_ifd) = ifd_entries(tiff, ifd0, big_endian)?;
+    let ifd1 = read_u32(tiff, next_ifd, big_endian)? as usize;
+    if ifd1 == 0 {
+        return Some(());
+    }
+
+    let (entries, _) = ifd_entries(tiff, ifd1, big_endian)?;
+    let thumbnail = entries
+        .into_iter()
+        .find(|(_, tag)| *tag == JPEG_INTERCHANGE_FORMAT)
+        .and_then(|(entry, _)| read_u32(tiff, entry + 8, big_endian))
+        .map_or(ifd1, |offset| offset as usize);
+
+    tiff.get_mut(next_ifd..next_ifd + 4)?
+        .copy_from_slice(&[0; 4]);
+
+    let start = ifd1.min(thumbnail);
+    if start >= data_end(tiff, ifd0, big_endian, 0)? {
+        tiff.truncate(start);
+    }
+
+    Some(())
+}
+
+/// End of the IFD at `offset`, the values it stores out of line and the EXIF, GPS and
+/// interoperability IFDs below it.
+fn data_end(tiff: &[u8], offset: usize, big_endian: bool, depth: u32) -> Option<usize> {
+    // Sub IFDs only go two levels deep, anything further is a loop
+    if depth > 2 {
+        return None;
+    }
+
+    let (entries, next_ifd) = ifd_entries(tiff, offset, big_endian)?;
+    let mut end = next_ifd + 4;
+
+    for (entry, tag) in entries {
+        let size = match read_u16(tiff, entry + 2, big_endian)? {
+            SHORT | 8 => 2,
+            LONG | 9 | 11 => 4,
+            RATIONAL | 10 | 12 => 8,
+            _ => 1,
+        } * read_u32(tiff, entry + 4, big_endian)? as usize;
+        let value = read_u32(tiff, entry + 8, big_endian)? as usize;
+
+        // Values of up to 4 bytes are stored in the entry itself
+        if size > 4 {
+            end = end.max(value + size);
+        }
+        if [EXIF_IFD, GPS_IFD, INTEROP_IFD].contains(&tag) {
+            end = end.max(data_end(tiff, value, big_endian, depth + 1)?);
+        }
+    }
+
+    Some(end)
+}
+
+/// Minimal little endian tiff structure: an IFD0 followed by the thumbnail IFD.
+fn thumbnail_tiff(thumbnail: &[u8]) -> Vec<u8> {
+    const IFD0_OFFSET: u32 = 8;
+    const IFD1_OFFSET: u32 = IFD0_OFFSET + 2 + 12 + 4;
+
+    let mut tiff = Vec::new();
+    tiff.extend_from_slice(b"II*\0");
+    tiff.extend_from_slice(&IFD0_OFFSET.to_le_bytes());
+
+    // IFD0
+    tiff.extend_from_slice(&1u16.to_le_bytes());
+    write_entry(&mut tiff, ORIENTATION, SHORT, 1, false);
+    tiff.extend_from_slice(&IFD1_OFFSET.to_le_bytes());
+
+    write_thumbnail_ifd(&mut tiff, thumbnail, false);
+    tiff
+}
+
+/// Appends IFD1 describing `thumbnail`, followed by its resolution values and the thumbnail itself.
+fn write_thumbnail_ifd(tiff: &mut Vec<u8>, thumbnail: &[u8], big_endian: bool) {
+    let ifd_offset = tiff.len() as u32;
+    let rational_offset = ifd_offset + 2 + 6 * 12 + 4;
+    let thumbnail_offset = rational_offset + 16;
+
+    // Entries sorted by tag
+    tiff.extend_from_slice(&to_bytes_u16(6, big_endian));
+    write_entry(tiff, COMPRESSION, SHORT, 6, big_endian);
+    write_entry(tiff, X_RESOLUTION, RATIONAL, rational_offset, big_endian);
+    write_entry(
+        tiff,
+        Y_RESOLUTION,
+        RATIONAL,
+        rational_offset + 8,
+        big_endian,
+    );
+    write_entry(tiff, RESOLUTION_UNIT, SHORT, 2, big_endian);
+    write_entry(
+        tiff,
+        JPEG_INTERCHANGE_FORMAT,
+        LONG,
+        thumbnail_offset,
+        big_endian,
+    );
+    write_entry(
+        tiff,
+        JPEG_INTERCHANGE_FORMAT_LENGTH,
+        LONG,
+        thumbnail.len() as u32,
+        big_endian,
+    );
+    tiff.extend_from_slice(&0u32.to_le_bytes());
+
+    // 72 dpi
+    for _ in 0..2 {
+        tiff.extend_from_slice(&to_bytes_u32(72, big_endian));
+        tiff.extend_from_slice(&to_bytes_u32(1, big_endian));
+    }
+
+    tiff.extend_from_slice(thumbnail);
+}
+
+fn write_entry(tiff: &mut Vec<u8>, tag: u16, kind: u16, value: u32, big_endian: bool) {
+    tiff.extend_from_slice(&to_bytes_u16(tag, big_endian));
+    tiff.extend_from_slice(&to_bytes_u16(kind, big_endian));
+    tiff.extend_from_slice(&to_bytes_u32(1, big_endian));
+
+    // Shorts are left aligned in the value field
+    match kind {
+        SHORT => {
+            tiff.extend_from_slice(&to_bytes_u16(value as u16, big_endian));
+            tiff.extend_from_slice(&[0, 0]);
+        }
+        _ => tiff.extend_from_slice(&to_bytes_u32(value, big_endian)),
+    }
+}
+
+fn endianness(tiff: &[u8]) -> Option<bool> {
+    match tiff.get(..4)? {
+        b"MM\0*" => Some(true),
+        b"II*\0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Entry offsets and tags of the IFD at `offset`, plus the position of its next IFD pointer.
+fn ifd_entries(tiff: &[u8], offset: usize, big_endian: bool) -> Option<(Vec<(usize, u16)>, usize)> {
+    let count = read_u16(tiff, offset, big_endian)? as usize;
+
+    let entries = (0..count)
+        .map(|i| offset + 2 + i * 12)
+        .map(|entry| Some((entry, read_u16(tiff, entry, big_endian)?)))
+        .collect::<Option<Vec<_>>>()?;
+
+    Some((entries, offset + 2 + count * 12))
+}
+
+fn read_u16(tiff: &[u8], offset: usize, big_endian: bool) -> Option<u16> {
+    let bytes = tiff.get(offset..offset + 2)?.try_into().ok()?;
+    Some(match big_endian {
+        true => u16::from_be_bytes(bytes),
+        false => u16::from_le_bytes(bytes),
+    })
+}
+
+fn read_u32(tiff: &[u8], offset: usize, big_endian: bool) -> Option<u32> {
+    let bytes = tiff.get(offset..offset + 4)?.try_into().ok()?;
+    Some(match big_endian {
+        true => u32::from_be_bytes(bytes),
+        false => u32::from_le_bytes(bytes),
+    })
+}
+
+fn to_bytes_u16(value: u16, big_endian: bool) -> [u8; 2] {
+    match big_endian {
+        true => value.to_be_bytes(),
+        false => value.to_le_bytes(),
+    }
+}
+
+fn to_bytes_u32(value: u32, big_endian: bool) -> [u8; 4] {
+    match big_endian {
+        true => value.to_be_bytes(),
+        false => value.to_le_bytes(),
+    }
+}
+
+/// Wraps a tiff structure in an APP1 marker, `None` if it exceeds the segment size limit.
+fn app1_segment(tiff: &[u8]) -> Option<Vec<u8>> {
+    let length = u16::try_from(2 + 6 + tiff.len()).ok()?;
+
+    let mut segment = vec![0xFF, 0xE1];
+    segment.extend_from_slice(&length.to_be_bytes());
+    segment.extend_from_slice(b"Exif\0\0");
+    segment.extend_from_slice(tiff);
+
+    Some(segment)
+}
+
+/// Inserts a marker segment right after SOI, or after the JFIF header when there is one.
+fn insert_segment(jpeg: &[u8], segment: &[u8]) -> Vec<u8> {
+    let mut position = 2;
+
+    if jpeg.len() > 5 && jpeg[2..4] == [0xFF, 0xE0] {
+        let length = u16::from_be_bytes([jpeg[4], jpeg[5]]) as usize;
+        position = (4 + length).min(jpeg.len());
+    }
+
+    let mut output = Vec::with_capacity(jpeg.len() + segment.len());
+    output.extend_from_slice(&jpeg[..position]);
+    output.extend_from_slice(segment);
+    output.extend_from_slice(&jpeg[position..]);
+    output
+}
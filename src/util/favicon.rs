@@ -0,0 +1,72 @@
+use std::{error::Error, fs, path::Path};
+
+use image::{codecs::png::PngEncoder, DynamicImage};
+
+use crate::{
+    structs::{settings::Settings, update::Asset},
+    util::{icons, scratch::write_staged},
+};
+
+pub static ICO_FILE: &str = "favicon.ico";
+
+/// PNG icons of a favicon package, by file name and size.
+const PNG_ICONS: [(&str, u32); 5] = [
+    ("favicon-16x16.png", 16),
+    ("favicon-32x32.png", 32),
+    ("apple-touch-icon.png", 180),
+    ("android-chrome-192x192.png", 192),
+    ("android-chrome-512x512.png", 512),
+];
+// Sizes packed into favicon.ico
+const ICO_SIZES: [u32; 3] = [16, 32, 48];
+
+const WEB_MANIFEST: &str = r##"{
+  "name": "",
+  "short_name": "",
+  "icons": [
+    { "src": "/android-chrome-192x192.png", "sizes": "192x192", "type": "image/png" },
+    { "src": "/android-chrome-512x512.png", "sizes": "512x512", "type": "image/png" }
+  ],
+  "theme_color": "#ffffff",
+  "background_color": "#ffffff",
+  "display": "standalone"
+}
+"##;
+
+/// Writes the usual set of site icons made from `img` to `dir`, with their standard names and
+/// a site.webmanifest pointing at the Android ones.
+pub fn write_package(
+    img: &DynamicImage,
+    dir: &Path,
+    settings: &Settings,
+) -> Result<Vec<Asset>, Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
+
+    let mut assets = Vec::with_capacity(PNG_ICONS.len() + 2);
+    let mut write = |name: &str, size: u32, data: &[u8]| -> std::io::Result<()> {
+        let path = dir.join(name);
+        let replaced = path.exists();
+        write_staged(data, &path, settings)?;
+        assets.push(Asset {
+            path,
+            width: size,
+            height: size,
+            size: data.len() as u64,
+            blurhash: None,
+            replaced,
+        });
+        Ok(())
+    };
+
+    write(ICO_FILE, 48, &icons::encode_ico(img, &ICO_SIZES)?)?;
+
+    for (name, size) in PNG_ICONS {
+        let mut data = Vec::new();
+        icons::square(img, size).write_with_encoder(PngEncoder::new(&mut data))?;
+        write(name, size, &data)?;
+    }
+
+    write("site.webmanifest", 0, WEB_MANIFEST.as_bytes())?;
+
+    Ok(assets)
+}
@@ -1,12 +1,47 @@
 use std::{
+    collections::{HashMap, HashSet},
     error::Error,
     fs,
+    io::{self, Read},
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
 };
 
-use crate::{INPUT_FOLDER, OUTPUT_FOLDER};
+use image::ImageFormat;
+use rayon::prelude::*;
 
-pub fn get_files() -> Result<Vec<PathBuf>, Box<dyn Error>> {
+use crate::{
+    structs::settings::{OriginalsOption, Settings, SortOrder, SymlinkPolicy},
+    util::{dds, icons, jxl, pattern::matches, psd, svg},
+    INPUT_FOLDER, ORIGINALS_FOLDER, OUTPUT_FOLDER,
+};
+
+const ALLOWED_EXTENSIONS: [&str; 16] = [
+    "jpg", "jpeg", "mpo", "png", "avif", "webp", "tif", "tiff", "bmp", "gif", "tga", "jxl", "exr",
+    "hdr", "qoi", "zip",
+];
+// Tga has no signature, so it is only recognized by extension
+const ALLOWED_FORMATS: [ImageFormat; 10] = [
+    ImageFormat::Jpeg,
+    ImageFormat::Png,
+    ImageFormat::Avif,
+    ImageFormat::WebP,
+    ImageFormat::Tiff,
+    ImageFormat::Bmp,
+    ImageFormat::Gif,
+    ImageFormat::OpenExr,
+    ImageFormat::Hdr,
+    ImageFormat::Qoi,
+];
+// Files found in a single folder before they are passed on
+const SCAN_BATCH_SIZE: usize = 500;
+
+/// Creates the input and output folders when they don't exist yet.
+pub fn prepare_folders() -> Result<(), Box<dyn Error>> {
     let input_path = Path::new(INPUT_FOLDER);
     let output_path = Path::new(OUTPUT_FOLDER);
 
@@ -26,26 +61,420 @@ pub fn get_files() -> Result<Vec<PathBuf>, Box<dyn Error>> {
         return Err(format!("{} is not a directory", OUTPUT_FOLDER).into());
     }
 
-    let allowed_extensions = ["jpg", "jpeg", "png", "avif"];
-
-    // Get all image files
-    let files: Vec<PathBuf> = fs::read_dir(input_path)?
-        .filter_map(|entry| {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if path.is_file() {
-                    if let Some(extension) = path.extension() {
-                        if let Some(ext) = extension.to_str().map(|ext| ext.to_ascii_lowercase()) {
-                            if allowed_extensions.contains(&ext.as_str()) {
-                                return Some(path);
-                            }
-                        }
-                    }
-                }
+    Ok(())
+}
+
+/// All image files in `dir` that pass the filters in `settings`.
+pub fn scan_folder(
+    dir: &Path,
+    recursive: bool,
+    settings: &Settings,
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let files = Mutex::new(Vec::new());
+    walk_folder(
+        dir,
+        recursive,
+        settings,
+        &AtomicBool::new(false),
+        &|found| files.lock().unwrap().extend(found),
+        &|folder, e| eprintln!("Skipping '{}': {}", folder.display(), e),
+    )?;
+
+    // Folders are walked in parallel, so the order isn't stable otherwise
+    let mut files = files.into_inner().unwrap();
+    files.sort();
+
+    Ok(files)
+}
+
+/// Walks `dir` on the rayon pool, passing the images that pass the filters in `settings`
+/// to `found` one folder at a time. Stops early once `stop_flag` is set.
+///
+/// Subfolders that can't be read are passed to `skipped` and the walk carries on, only failing
+/// to read `dir` itself is an error.
+pub fn walk_folder(
+    dir: &Path,
+    recursive: bool,
+    settings: &Settings,
+    stop_flag: &AtomicBool,
+    found: &(dyn Fn(Vec<PathBuf>) + Sync),
+    skipped: &(dyn Fn(&Path, io::Error) + Sync),
+) -> io::Result<()> {
+    let walk = Walk {
+        recursive,
+        settings,
+        visited: Mutex::new(HashSet::new()),
+        seen: Mutex::new(HashSet::new()),
+        stop_flag,
+        found,
+        skipped,
+    };
+    collect_files(dir, &walk)
+}
+
+/// Expands paths given on the command line, scanning folders like the input folder.
+pub fn expand_paths(paths: &[PathBuf], settings: &Settings) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = Vec::new();
+
+    for path in paths {
+        if path.is_dir() {
+            match scan_folder(path, false, settings) {
+                Ok(found) => files.extend(found),
+                Err(e) => eprintln!("Failed to scan '{}': {}", path.display(), e),
             }
-            None
+        } else if path.is_file() && is_allowed(path) {
+            files.push(path.clone());
+        } else {
+            eprintln!("Skipping '{}', not an image", path.display());
+        }
+    }
+
+    // Links and relative paths can lead to the same file
+    let mut seen = HashSet::new();
+    files.retain(|path| seen.insert(fs::canonicalize(path).unwrap_or_else(|_| path.clone())));
+
+    files
+}
+
+// State shared by every folder of a walk
+struct Walk<'a> {
+    recursive: bool,
+    settings: &'a Settings,
+    visited: Mutex<HashSet<PathBuf>>,
+    seen: Mutex<HashSet<PathBuf>>,
+    stop_flag: &'a AtomicBool,
+    found: &'a (dyn Fn(Vec<PathBuf>) + Sync),
+    skipped: &'a (dyn Fn(&Path, io::Error) + Sync),
+}
+
+fn collect_files(dir: &Path, walk: &Walk) -> io::Result<()> {
+    let settings = walk.settings;
+    if walk.stop_flag.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    // Links can point back up the tree, so every folder is only entered once
+    let canonical = fs::canonicalize(dir)?;
+    if !walk.visited.lock().unwrap().insert(canonical) {
+        return Ok(());
+    }
+
+    let mut files = Vec::new();
+    let mut folders = Vec::new();
+
+    for entry in fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        let is_link = entry
+            .file_type()
+            .is_ok_and(|file_type| file_type.is_symlink());
+
+        if path.is_dir() {
+            let skipped_link = is_link && settings.symlinks == SymlinkPolicy::Skip;
+            if walk.recursive && !skipped_link && !is_originals_folder(&path) {
+                folders.push(path);
+            }
+        } else if is_wanted(&path, is_link, settings) && is_first_path(&path, settings, &walk.seen)
+        {
+            files.push(path);
+        }
+
+        // Huge flat folders still show up bit by bit
+        if files.len() >= SCAN_BATCH_SIZE {
+            (walk.found)(std::mem::take(&mut files));
+
+            if walk.stop_flag.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+        }
+    }
+
+    if !files.is_empty() {
+        (walk.found)(files);
+    }
+
+    // One unreadable folder shouldn't hide the rest of the tree
+    folders.par_iter().for_each(|folder| {
+        if let Err(e) = collect_files(folder, walk) {
+            (walk.skipped)(folder, e);
+        }
+    });
+
+    Ok(())
+}
+
+/// Whether no other path to the same file was found yet. Only tracked when resolving links,
+/// the path found first is kept so outputs still mirror where it was found.
+fn is_first_path(path: &Path, settings: &Settings, seen: &Mutex<HashSet<PathBuf>>) -> bool {
+    if settings.symlinks != SymlinkPolicy::Resolve {
+        return true;
+    }
+
+    match fs::canonicalize(path) {
+        Ok(target) => seen.lock().unwrap().insert(target),
+        // Broken link
+        Err(_) => false,
+    }
+}
+
+fn is_originals_folder(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name == ORIGINALS_FOLDER)
+}
+
+/// Whether `path` is a source that was already moved away after converting it.
+pub fn is_moved_original(path: &Path) -> bool {
+    path.parent().is_some_and(is_originals_folder)
+}
+
+/// Moves or deletes a converted source file, as chosen in `settings`.
+pub fn handle_original(path: &Path, settings: &Settings) -> io::Result<()> {
+    if settings.originals != OriginalsOption::Keep && !resolves_inside_input(path, settings) {
+        return Err(io::Error::other(
+            "it links to a file outside of its input folder, so it was left in place",
+        ));
+    }
+
+    match settings.originals {
+        OriginalsOption::Keep => Ok(()),
+        OriginalsOption::Delete => fs::remove_file(path),
+        OriginalsOption::Move => {
+            let folder = path
+                .parent()
+                .unwrap_or(Path::new(""))
+                .join(ORIGINALS_FOLDER);
+            fs::create_dir_all(&folder)?;
+            fs::rename(path, folder.join(path.file_name().unwrap_or_default()))
+        }
+    }
+}
+
+/// Whether `path` still lies inside the input folder it was found in once links are resolved.
+/// Files that never were in an input folder, like ones passed on the command line, count as inside.
+fn resolves_inside_input(path: &Path, settings: &Settings) -> bool {
+    let mut folders = settings
+        .input_folders
+        .iter()
+        .filter(|folder| path.starts_with(&folder.path))
+        .peekable();
+    if folders.peek().is_none() {
+        return true;
+    }
+
+    let Ok(target) = fs::canonicalize(path) else {
+        return false;
+    };
+
+    folders.any(|folder| fs::canonicalize(&folder.path).is_ok_and(|root| target.starts_with(root)))
+}
+
+/// Whether `path` is an image that can be converted, by extension or else by content.
+pub fn is_allowed(path: &Path) -> bool {
+    let by_extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ALLOWED_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        .unwrap_or(false);
+    if by_extension {
+        return true;
+    }
+
+    if jxl::is_jxl(path)
+        || svg::is_svg(path)
+        || psd::is_psd(path)
+        || icons::is_icon(path)
+        || dds::is_dds(path)
+    {
+        return true;
+    }
+
+    #[cfg(feature = "raw")]
+    if crate::util::raw::is_raw(path) {
+        return true;
+    }
+
+    // Without pdfium they'd only fail to convert
+    #[cfg(feature = "pdf")]
+    if crate::util::pdf::is_pdf(path) {
+        return crate::util::capabilities::Capability::PdfInput.is_available();
+    }
+
+    #[cfg(feature = "heif")]
+    if crate::util::heif::is_heif(path) {
+        return true;
+    }
+
+    sniff_format(path).is_some_and(|format| ALLOWED_FORMATS.contains(&format))
+}
+
+/// Image format of `path` according to its first bytes, regardless of its extension.
+pub fn sniff_format(path: &Path) -> Option<ImageFormat> {
+    let mut header = Vec::with_capacity(32);
+    fs::File::open(path)
+        .ok()?
+        .take(32)
+        .read_to_end(&mut header)
+        .ok()?;
+
+    image::guess_format(&header).ok()
+}
+
+/// Whether the file at `path` should be converted: an image that passes the symlink policy, the
+/// name filter and the size and date limits in `settings`. Scans and the watcher both check this,
+/// `is_link` is whether `path` itself is a link.
+pub fn is_wanted(path: &Path, is_link: bool, settings: &Settings) -> bool {
+    !(is_link && settings.symlinks == SymlinkPolicy::Skip)
+        && path.is_file()
+        && is_allowed(path)
+        && matches_filter(path, settings)
+        && matches_limits(path, settings)
+}
+
+pub fn matches_filter(path: &Path, settings: &Settings) -> bool {
+    match &settings.file_filter {
+        Some(filter) => matches(
+            filter,
+            &path.file_name().unwrap_or_default().to_string_lossy(),
+        ),
+        None => true,
+    }
+}
+
+/// Whether the size and modification date of `path` fall within the limits in `settings`.
+fn matches_limits(path: &Path, settings: &Settings) -> bool {
+    if settings.min_size == 0 && settings.max_size == 0 && settings.modified_after.is_none() {
+        return true;
+    }
+
+    let Ok(metadata) = fs::metadata(path) else {
+        return false;
+    };
+
+    let size = metadata.len();
+    if size < settings.min_size || (settings.max_size > 0 && size > settings.max_size) {
+        return false;
+    }
+
+    match settings.modified_after {
+        Some(after) => metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .is_some_and(|modified| modified.as_secs() >= after),
+        None => true,
+    }
+}
+
+/// Removes files with the same content as an earlier file in `files`.
+///
+/// Returns each removed file with the file that was kept in its place.
+pub fn remove_duplicates<T: AsRef<Path> + Clone>(files: &mut Vec<T>) -> Vec<(T, T)> {
+    // Only files sharing a size can be identical, so most files are never hashed
+    let mut sizes: HashMap<u64, usize> = HashMap::new();
+    let lengths: Vec<Option<u64>> = files
+        .iter()
+        .map(|file| {
+            fs::metadata(file)
+                .ok()
+                .filter(|m| m.is_file())
+                .map(|m| m.len())
         })
         .collect();
+    for size in lengths.iter().flatten() {
+        *sizes.entry(*size).or_default() += 1;
+    }
 
-    Ok(files)
+    let mut kept: HashMap<(u64, blake3::Hash), T> = HashMap::new();
+    let mut duplicates = Vec::new();
+    let mut unique = Vec::with_capacity(files.len());
+
+    for (file, size) in files.drain(..).zip(lengths) {
+        let key = size
+            .filter(|size| sizes[size] > 1)
+            .and_then(|size| hash_file(file.as_ref()).ok().map(|hash| (size, hash)));
+
+        match key {
+            Some(key) => match kept.get(&key) {
+                Some(original) => duplicates.push((file, original.clone())),
+                None => {
+                    kept.insert(key, file.clone());
+                    unique.push(file);
+                }
+            },
+            None => unique.push(file),
+        }
+    }
+
+    *files = unique;
+    duplicates
+}
+
+pub fn hash_file(path: &Path) -> io::Result<blake3::Hash> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(fs::File::open(path)?)?;
+    Ok(hasher.finalize())
+}
+
+/// Sorts `files` into the order they should be converted in.
+pub fn sort_files<T: AsRef<Path>>(files: &mut [T], order: SortOrder) {
+    let modified = |file: &T| {
+        fs::metadata(file)
+            .and_then(|m| m.modified())
+            .unwrap_or(UNIX_EPOCH)
+    };
+    let size = |file: &T| fs::metadata(file).map(|m| m.len()).unwrap_or(0);
+
+    match order {
+        SortOrder::Name => files.sort_by(|a, b| a.as_ref().cmp(b.as_ref())),
+        SortOrder::SmallestFirst => files.sort_by_cached_key(size),
+        SortOrder::LargestFirst => files.sort_by_cached_key(|file| std::cmp::Reverse(size(file))),
+        SortOrder::NewestFirst => {
+            files.sort_by_cached_key(|file| std::cmp::Reverse(modified(file)))
+        }
+        SortOrder::OldestFirst => files.sort_by_cached_key(modified),
+        SortOrder::Shuffle => {
+            // Xorshift seeded from the clock, good enough to mix up a queue
+            let mut state = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_nanos() as u64)
+                .unwrap_or(0)
+                | 1;
+
+            for i in (1..files.len()).rev() {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                files.swap(i, (state % (i as u64 + 1)) as usize);
+            }
+        }
+    }
+}
+
+/// Combined size of `files` in bytes.
+pub fn total_size(files: &[PathBuf]) -> u64 {
+    files
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Folder the output for `path` should be written to, mirroring its location inside its input folder.
+pub fn output_dir(path: &Path, settings: &Settings) -> PathBuf {
+    // The innermost input folder when they are nested
+    let source = path.parent().and_then(|parent| {
+        settings
+            .input_folders
+            .iter()
+            .filter_map(|folder| Some((folder, parent.strip_prefix(&folder.path).ok()?)))
+            .min_by_key(|(_, relative)| relative.components().count())
+    });
+
+    match source {
+        Some((folder, relative)) => folder
+            .output
+            .as_deref()
+            .unwrap_or(Path::new(OUTPUT_FOLDER))
+            .join(relative),
+        None => PathBuf::from(OUTPUT_FOLDER),
+    }
 }
@@ -0,0 +1,92 @@
+use std::{borrow::Cow, error::Error};
+
+use gif::{DisposalMethod, Encoder, Frame, Repeat};
+use image::{DynamicImage, RgbaImage};
+
+use crate::{structs::file_type::GifSettings, util::quantize};
+
+/// Alpha below which a pixel is transparent, GIF has no partial transparency.
+const ALPHA_THRESHOLD: u8 = 128;
+
+/// Encodes `frames` with their delays in milliseconds as a GIF, looping when there's more than one.
+pub fn encode(
+    frames: &[(&DynamicImage, u32)],
+    options: &GifSettings,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (first, _) = frames.first().ok_or("Animation has no frames")?;
+    let (width, height) = (first.width(), first.height());
+    if width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(format!("GIFs are at most {} pixels wide and high", u16::MAX).into());
+    }
+
+    let buffers: Vec<RgbaImage> = frames.iter().map(|(img, _)| img.to_rgba8()).collect();
+    let indexed = quantize::remap(&buffers, options.colors, options.dither)?;
+
+    // Pixels that don't change between opaque frames are left transparent,
+    // which compresses to almost nothing
+    let opaque = buffers
+        .iter()
+        .all(|buffer| buffer.pixels().all(|pixel| pixel[3] == 255));
+
+    let mut encoder = Encoder::new(Vec::new(), width as u16, height as u16, &[])?;
+    if frames.len() > 1 {
+        encoder.set_repeat(Repeat::Infinite)?;
+    }
+
+    let mut previous: Option<&RgbaImage> = None;
+    for (((_, delay), buffer), indexed) in frames.iter().zip(&buffers).zip(indexed) {
+        let mut palette = indexed.palette;
+        let mut indexes = indexed.indexes;
+
+        // Every transparent entry is merged into the first one
+        let mut transparent = palette.iter().position(|color| color.a < ALPHA_THRESHOLD);
+        if let Some(transparent) = transparent {
+            for index in indexes.iter_mut() {
+                if palette[*index as usize].a < ALPHA_THRESHOLD {
+                    *index = transparent as u8;
+                }
+            }
+        }
+
+        if let Some(previous) = previous.filter(|_| opaque) {
+            if transparent.is_none() && palette.len() < 256 {
+                palette.push(imagequant::RGBA::new(0, 0, 0, 0));
+                transparent = Some(palette.len() - 1);
+            }
+
+            if let Some(transparent) = transparent {
+                for ((index, pixel), old) in indexes
+                    .iter_mut()
+                    .zip(buffer.pixels())
+                    .zip(previous.pixels())
+                {
+                    if pixel == old {
+                        *index = transparent as u8;
+                    }
+                }
+            }
+        }
+
+        let frame = Frame {
+            width: width as u16,
+            height: height as u16,
+            // In hundredths of a second
+            delay: (delay / 10).min(u16::MAX as u32) as u16,
+            dispose: DisposalMethod::Keep,
+            transparent: transparent.map(|index| index as u8),
+            palette: Some(
+                palette
+                    .iter()
+                    .flat_map(|color| [color.r, color.g, color.b])
+                    .collect(),
+            ),
+            buffer: Cow::Owned(indexes),
+            ..Frame::default()
+        };
+        encoder.write_frame(&frame)?;
+
+        previous = Some(buffer);
+    }
+
+    Ok(encoder.into_inner()?)
+}
@@ -0,0 +1,45 @@
+use std::{error::Error, fs, io::Read, path::Path};
+
+use image::{DynamicImage, RgbaImage};
+use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+// Major brands of HEIF files holding HEVC coded images, AVIF is handled by the image crate
+const BRANDS: [&[u8; 4]; 7] = [
+    b"heic", b"heix", b"heim", b"heis", b"hevc", b"hevx", b"mif1",
+];
+
+pub fn is_heif(path: &Path) -> bool {
+    let mut header = [0u8; 12];
+
+    let Ok(mut file) = fs::File::open(path) else {
+        return false;
+    };
+
+    file.read_exact(&mut header).is_ok()
+        && &header[4..8] == b"ftyp"
+        && BRANDS.iter().any(|brand| header[8..12] == brand[..])
+}
+
+/// Decodes the primary image of a HEIC/HEIF file, as taken by most phones.
+pub fn decode(path: &Path) -> Result<DynamicImage, Box<dyn Error>> {
+    let lib_heif = LibHeif::new();
+    let context = HeifContext::read_from_file(&path.to_string_lossy())?;
+    let handle = context.primary_image_handle()?;
+
+    let image = lib_heif.decode(&handle, ColorSpace::Rgb(RgbChroma::Rgba), None)?;
+    let plane = image
+        .planes()
+        .interleaved
+        .ok_or("HEIF image has no interleaved plane")?;
+
+    // Rows can be padded beyond width * 4 bytes
+    let (width, height) = (plane.width, plane.height);
+    let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+    for row in plane.data.chunks(plane.stride).take(height as usize) {
+        data.extend_from_slice(&row[..width as usize * 4]);
+    }
+
+    let buf = RgbaImage::from_raw(width, height, data).ok_or("HEIF image has an invalid size")?;
+
+    Ok(DynamicImage::ImageRgba8(buf))
+}
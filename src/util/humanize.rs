@@ -0,0 +1,81 @@
+use std::{env, sync::OnceLock, time::Duration};
+
+const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+/// Thousands and decimal separators of the locale in the usual environment variables,
+/// English when none is set like on most Windows installs.
+fn separators() -> (char, char) {
+    static SEPARATORS: OnceLock<(char, char)> = OnceLock::new();
+
+    *SEPARATORS.get_or_init(|| {
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .find_map(|var| env::var(var).ok().filter(|value| !value.is_empty()))
+            .unwrap_or_default();
+        let language = locale.split(['_', '-', '.']).next().unwrap_or_default();
+
+        match language.to_ascii_lowercase().as_str() {
+            "da" | "de" | "el" | "es" | "id" | "it" | "nl" | "pt" | "tr" => ('.', ','),
+            "cs" | "fi" | "fr" | "hu" | "nb" | "pl" | "ru" | "sk" | "sv" | "uk" => ('\u{a0}', ','),
+            _ => (',', '.'),
+        }
+    })
+}
+
+/// `value` with `places` decimals and grouped thousands.
+pub fn decimal(value: f64, places: usize) -> String {
+    let (thousands, point) = separators();
+    let text = format!("{:.*}", places, value.abs());
+    let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(thousands);
+        }
+        grouped.push(digit);
+    }
+
+    if value.is_sign_negative() && value != 0.0 {
+        grouped.insert(0, '-');
+    }
+    if !fraction.is_empty() {
+        grouped.push(point);
+        grouped.push_str(fraction);
+    }
+
+    grouped
+}
+
+/// A count like 1,234.
+pub fn count(n: usize) -> String {
+    decimal(n as f64, 0)
+}
+
+/// A file size like 45.6 MB.
+pub fn size(bytes: u64) -> String {
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{} {}", decimal(value, 1), UNITS[unit])
+}
+
+/// A duration like 1 m 12 s, precise to a tenth of a second under a minute.
+pub fn duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+
+    match seconds {
+        0 => format!("{} ms", duration.as_millis()),
+        1..60 => format!("{} s", decimal(duration.as_secs_f64(), 1)),
+        60..3600 => format!("{} m {} s", seconds / 60, seconds % 60),
+        _ => format!("{} h {} m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
@@ -0,0 +1,102 @@
+use std::{error::Error, fs, io::BufReader, path::Path};
+
+use image::{imageops::FilterType, DynamicImage, RgbaImage};
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+}
+
+pub fn is_icon(path: &Path) -> bool {
+    matches!(extension(path).as_deref(), Some("ico" | "icns"))
+}
+
+/// Images stored in an ICO or ICNS file, largest first. Only the largest one unless `all` is set.
+pub fn icon_images(path: &Path, all: bool) -> Result<Vec<DynamicImage>, Box<dyn Error>> {
+    let file = BufReader::new(fs::File::open(path)?);
+
+    let mut images = match extension(path).as_deref() {
+        Some("icns") => icns_images(file)?,
+        _ => ico_images(file)?,
+    };
+
+    if images.is_empty() {
+        return Err("Icon contains no images that can be decoded".into());
+    }
+
+    images.sort_by_key(|img| std::cmp::Reverse(img.width() * img.height()));
+    if !all {
+        images.truncate(1);
+    }
+
+    Ok(images)
+}
+
+/// `img` scaled to fit a `size` pixel square, centered on a transparent background.
+pub fn square(img: &DynamicImage, size: u32) -> RgbaImage {
+    let resized = img.resize(size, size, FilterType::Lanczos3).to_rgba8();
+    let mut square = RgbaImage::new(size, size);
+    image::imageops::overlay(
+        &mut square,
+        &resized,
+        ((size - resized.width()) / 2) as i64,
+        ((size - resized.height()) / 2) as i64,
+    );
+
+    square
+}
+
+/// Packs `img` into an ICO holding a square version for each of `sizes`.
+/// Images that aren't square are centered on a transparent background.
+pub fn encode_ico(img: &DynamicImage, sizes: &[u32]) -> Result<Vec<u8>, Box<dyn Error>> {
+    if sizes.is_empty() {
+        return Err("No icon sizes selected".into());
+    }
+
+    let mut dir = ico::IconDir::new(ico::ResourceType::Icon);
+
+    for &size in sizes {
+        let icon = ico::IconImage::from_rgba_data(size, size, square(img, size).into_raw());
+        dir.add_entry(ico::IconDirEntry::encode(&icon)?);
+    }
+
+    let mut data = Vec::new();
+    dir.write(&mut data)?;
+
+    Ok(data)
+}
+
+fn ico_images(file: BufReader<fs::File>) -> Result<Vec<DynamicImage>, Box<dyn Error>> {
+    let dir = ico::IconDir::read(file)?;
+
+    let images = dir
+        .entries()
+        .iter()
+        .filter_map(|entry| entry.decode().ok())
+        .filter_map(|icon| {
+            RgbaImage::from_raw(icon.width(), icon.height(), icon.rgba_data().to_vec())
+        })
+        .map(DynamicImage::ImageRgba8)
+        .collect();
+
+    Ok(images)
+}
+
+fn icns_images(file: BufReader<fs::File>) -> Result<Vec<DynamicImage>, Box<dyn Error>> {
+    let family = icns::IconFamily::read(file)?;
+
+    // Icon types can hold the same size twice (e.g. a retina variant), and JPEG 2000 entries can't be decoded
+    let images = family
+        .available_icons()
+        .into_iter()
+        .filter_map(|icon_type| family.get_icon_with_type(icon_type).ok())
+        .map(|icon| icon.convert_to(icns::PixelFormat::RGBA))
+        .filter_map(|icon| {
+            RgbaImage::from_raw(icon.width(), icon.height(), icon.into_data().into_vec())
+        })
+        .map(DynamicImage::ImageRgba8)
+        .collect();
+
+    Ok(images)
+}
@@ -0,0 +1,78 @@
+use std::{error::Error, fs, io::Cursor, io::Read, path::Path};
+
+use image::{DynamicImage, ImageBuffer};
+use png::{BitDepth, ColorType, Transformations};
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Whether `path` is a PNG using Adam7 interlacing, read from the IHDR chunk.
+pub fn is_interlaced(path: &Path) -> bool {
+    let mut header = [0u8; 29];
+
+    let Ok(mut file) = fs::File::open(path) else {
+        return false;
+    };
+
+    file.read_exact(&mut header).is_ok()
+        && header[..8] == PNG_SIGNATURE
+        && &header[12..16] == b"IHDR"
+        && header[28] == 1
+}
+
+/// Decodes a PNG from memory with checksum verification turned off, which saves a pass over large interlaced files.
+pub fn decode_fast(path: &Path) -> Result<DynamicImage, Box<dyn Error>> {
+    let data = fs::read(path)?;
+
+    let mut decoder = png::Decoder::new(Cursor::new(data));
+    decoder.set_transformations(Transformations::EXPAND);
+    decoder.ignore_checksums(true);
+
+    let mut reader = decoder.read_info()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf)?;
+    buf.truncate(info.buffer_size());
+
+    let (width, height) = (info.width, info.height);
+
+    let img = match info.bit_depth {
+        BitDepth::Sixteen => {
+            let samples: Vec<u16> = buf
+                .chunks_exact(2)
+                .map(|sample| u16::from_be_bytes([sample[0], sample[1]]))
+                .collect();
+
+            match info.color_type {
+                ColorType::Rgba => {
+                    ImageBuffer::from_raw(width, height, samples).map(DynamicImage::ImageRgba16)
+                }
+                ColorType::Rgb => {
+                    ImageBuffer::from_raw(width, height, samples).map(DynamicImage::ImageRgb16)
+                }
+                ColorType::GrayscaleAlpha => {
+                    ImageBuffer::from_raw(width, height, samples).map(DynamicImage::ImageLumaA16)
+                }
+                ColorType::Grayscale => {
+                    ImageBuffer::from_raw(width, height, samples).map(DynamicImage::ImageLuma16)
+                }
+                ColorType::Indexed => None,
+            }
+        }
+        _ => match info.color_type {
+            ColorType::Rgba => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgba8)
+            }
+            ColorType::Rgb => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgb8)
+            }
+            ColorType::GrayscaleAlpha => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLumaA8)
+            }
+            ColorType::Grayscale => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLuma8)
+            }
+            ColorType::Indexed => None,
+        },
+    };
+
+    img.ok_or_else(|| "Unsupported PNG color type".into())
+}
@@ -0,0 +1,91 @@
+use std::error::Error;
+
+use image::DynamicImage;
+use jpeg_encoder::{ColorType, Encoder, SamplingFactor};
+
+use crate::structs::file_type::{ChromaSubsampling, JpegSettings};
+#[cfg(feature = "mozjpeg")]
+use crate::util::crash;
+
+/// Encodes `img` with the chosen chroma subsampling, progressively if asked. Progressive
+/// JPEGs are stored in passes that each sharpen the whole picture, instead of top to bottom, and
+/// colour can be kept at full resolution for sharp coloured edges like text.
+pub fn encode(
+    img: &DynamicImage,
+    options: &JpegSettings,
+    quality: u8,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (width, height) = (
+        u16::try_from(img.width()).map_err(|_| "image is too wide for JPEG")?,
+        u16::try_from(img.height()).map_err(|_| "image is too high for JPEG")?,
+    );
+
+    let mut buf = Vec::new();
+    let mut encoder = Encoder::new(&mut buf, quality);
+    encoder.set_progressive(options.progressive);
+    encoder.set_sampling_factor(match options.subsampling {
+        ChromaSubsampling::Yuv444 => SamplingFactor::F_1_1,
+        ChromaSubsampling::Yuv422 => SamplingFactor::F_2_1,
+        ChromaSubsampling::Yuv420 => SamplingFactor::F_2_2,
+    });
+
+    match img {
+        DynamicImage::ImageLuma8(gray) => encoder.encode(gray, width, height, ColorType::Luma)?,
+        // Alpha is dropped, like other encoders do for formats without it
+        DynamicImage::ImageRgba8(rgba) => encoder.encode(rgba, width, height, ColorType::Rgba)?,
+        _ => encoder.encode(&img.to_rgb8(), width, height, ColorType::Rgb)?,
+    }
+
+    Ok(buf)
+}
+
+/// Encodes `img` with mozjpeg's trellis quantization and optimized Huffman tables, around a
+/// tenth smaller than other encoders at the same quality. Its own profile is always
+/// progressive, baseline JPEGs fall back to libjpeg's defaults with optimized tables.
+#[cfg(feature = "mozjpeg")]
+pub fn encode_mozjpeg(
+    img: &DynamicImage,
+    options: &JpegSettings,
+    quality: u8,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    use mozjpeg::{ColorSpace, Compress};
+
+    let (gray, pixels) = match img {
+        DynamicImage::ImageLuma8(gray) => (true, gray.as_raw().clone()),
+        _ => (false, img.to_rgb8().into_raw()),
+    };
+
+    // mozjpeg reports errors by unwinding
+    crash::catch_unwind(|| -> std::io::Result<Vec<u8>> {
+        let mut compress = match gray {
+            true => Compress::new(ColorSpace::JCS_GRAYSCALE),
+            false => Compress::new(ColorSpace::JCS_RGB),
+        };
+        // Resets everything else, so it goes first
+        match options.progressive {
+            true => compress.set_progressive_mode(),
+            false => {
+                compress.set_fastest_defaults();
+                compress.set_optimize_coding(true);
+            }
+        }
+        compress.set_size(img.width() as usize, img.height() as usize);
+        compress.set_quality(quality as f32);
+        if !gray {
+            compress.set_chroma_sampling_pixel_sizes(
+                (1, 1),
+                match options.subsampling {
+                    ChromaSubsampling::Yuv444 => (1, 1),
+                    ChromaSubsampling::Yuv422 => (2, 1),
+                    ChromaSubsampling::Yuv420 => (2, 2),
+                },
+            );
+        }
+
+        let mut compress = compress.start_compress(Vec::new())?;
+        compress.write_scanlines(&pixels)?;
+        compress.finish()
+    })
+    .map_err(|_| "mozjpeg failed to encode the image")?
+    .map_err(Into::into)
+}
@@ -0,0 +1,27 @@
+use std::{error::Error, fs, io::Read, path::Path};
+
+use image::DynamicImage;
+use jxl_oxide::integration::JxlDecoder;
+
+const CODESTREAM_SIGNATURE: [u8; 2] = [0xFF, 0x0A];
+const CONTAINER_SIGNATURE: [u8; 12] = [
+    0, 0, 0, 0x0C, b'J', b'X', b'L', b' ', 0x0D, 0x0A, 0x87, 0x0A,
+];
+
+/// Whether `path` is a JPEG XL file, either a bare codestream or the ISOBMFF container.
+pub fn is_jxl(path: &Path) -> bool {
+    let mut header = Vec::with_capacity(12);
+
+    let Ok(file) = fs::File::open(path) else {
+        return false;
+    };
+
+    file.take(12).read_to_end(&mut header).is_ok()
+        && (header.starts_with(&CODESTREAM_SIGNATURE) || header == CONTAINER_SIGNATURE)
+}
+
+pub fn decode(path: &Path) -> Result<DynamicImage, Box<dyn Error>> {
+    let decoder = JxlDecoder::new(fs::File::open(path)?)?;
+
+    Ok(DynamicImage::from_decoder(decoder)?)
+}
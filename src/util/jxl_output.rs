@@ -0,0 +1,71 @@
+use std::{error::Error, fs, path::Path};
+
+use image::DynamicImage;
+use jpegxl_rs::encode::{encoder_builder, EncoderResult, EncoderSpeed};
+
+use crate::{structs::file_type::JxlSettings, util::color};
+
+fn speed(effort: u8) -> EncoderSpeed {
+    match effort {
+        0 | 1 => EncoderSpeed::Lightning,
+        2 => EncoderSpeed::Thunder,
+        3 => EncoderSpeed::Falcon,
+        4 => EncoderSpeed::Cheetah,
+        5 => EncoderSpeed::Hare,
+        6 => EncoderSpeed::Wombat,
+        7 => EncoderSpeed::Squirrel,
+        8 => EncoderSpeed::Kitten,
+        _ => EncoderSpeed::Tortoise,
+    }
+}
+
+/// Encodes `img` as JPEG XL, keeping 16 bit images at 16 bits.
+pub fn encode(img: &DynamicImage, options: &JxlSettings) -> Result<Vec<u8>, Box<dyn Error>> {
+    let has_alpha = img.color().has_alpha();
+    let (width, height) = (img.width(), img.height());
+
+    let mut encoder = encoder_builder()
+        .lossless(options.lossless)
+        // Lossless needs the pixels in their own color space, not XYB
+        .uses_original_profile(options.lossless)
+        .quality(options.distance)
+        .speed(speed(options.effort))
+        .has_alpha(has_alpha)
+        .build()?;
+
+    let data = match (color::bit_depth(img) > 8, has_alpha) {
+        (true, true) => {
+            let result: EncoderResult<u16> =
+                encoder.encode(img.to_rgba16().as_raw(), width, height)?;
+            result.data
+        }
+        (true, false) => {
+            let result: EncoderResult<u16> =
+                encoder.encode(img.to_rgb16().as_raw(), width, height)?;
+            result.data
+        }
+        (false, true) => {
+            let result: EncoderResult<u8> =
+                encoder.encode(img.to_rgba8().as_raw(), width, height)?;
+            result.data
+        }
+        (false, false) => {
+            let result: EncoderResult<u8> =
+                encoder.encode(img.to_rgb8().as_raw(), width, height)?;
+            result.data
+        }
+    };
+
+    Ok(data)
+}
+
+/// Losslessly recompresses the JPEG at `path`, the original file can be restored bit for bit.
+pub fn recompress_jpeg(path: &Path, options: &JxlSettings) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut encoder = encoder_builder()
+        .speed(speed(options.effort))
+        // The reconstruction data is stored in a box next to the codestream
+        .use_container(true)
+        .build()?;
+
+    Ok(encoder.encode_jpeg(&fs::read(path)?)?.data)
+}
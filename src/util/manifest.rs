@@ -0,0 +1,183 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use image::DynamicImage;
+
+use crate::{structs::update::Converted, OUTPUT_FOLDER};
+
+pub static MANIFEST_FILE: &str = "manifest.json";
+
+// Blurhashes only hold a few colors, so a tiny copy of the image is plenty
+const BLURHASH_SIZE: u32 = 32;
+
+/// Blurhash placeholder for `img`.
+pub fn blurhash(img: &DynamicImage) -> Option<String> {
+    let small = img.thumbnail(BLURHASH_SIZE, BLURHASH_SIZE).to_rgba8();
+    let (x_components, y_components) = match small.width() >= small.height() {
+        true => (4, 3),
+        false => (3, 4),
+    };
+
+    blurhash::encode(
+        x_components,
+        y_components,
+        small.width(),
+        small.height(),
+        small.as_raw(),
+    )
+    .ok()
+}
+
+/// Writes a JSON description of every output in `success` to the output folder.
+///
+/// Paths are relative to the output folder and use forward slashes, so they can be
+/// fed straight into static site generators.
+pub fn write_manifest(success: &[(PathBuf, Converted)]) -> io::Result<PathBuf> {
+    let generated = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    let mut images = Vec::new();
+
+    for (source, converted) in success {
+        for asset in &converted.outputs {
+            let format = asset
+                .path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+
+            let aspect_ratio = match asset.height {
+                0 => 0.0,
+                height => asset.width as f64 / height as f64,
+            };
+
+            let blurhash = match &asset.blurhash {
+                Some(hash) => json_string(hash),
+                None => "null".to_string(),
+            };
+
+            images.push(format!(
+                "    {{\n      \"src\": {},\n      \"source\": {},\n      \"format\": {},\n      \"mimeType\": {},\n      \"width\": {},\n      \"height\": {},\n      \"aspectRatio\": {:.4},\n      \"size\": {},\n      \"blurhash\": {}\n    }}",
+                json_string(&relative_path(&asset.path)),
+                json_string(&source.to_string_lossy()),
+                json_string(&format),
+                json_string(mime_type(&format)),
+                asset.width,
+                asset.height,
+                aspect_ratio,
+                asset.size,
+                blurhash,
+            ));
+        }
+    }
+
+    let json = format!(
+        "{{\n  \"generated\": {},\n  \"images\": [\n{}\n  ]\n}}\n",
+        generated,
+        images.join(",\n")
+    );
+
+    let path = Path::new(OUTPUT_FOLDER).join(MANIFEST_FILE);
+    fs::write(&path, json)?;
+
+    Ok(path)
+}
+
+/// Outputs listed in the manifest of the output folder, none when there is no manifest.
+///
+/// Paths outside the output folder can't be told from ones inside it, so both are returned.
+pub fn listed_outputs() -> io::Result<Vec<PathBuf>> {
+    let json = match fs::read_to_string(Path::new(OUTPUT_FOLDER).join(MANIFEST_FILE)) {
+        Ok(json) => json,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    // `write_manifest` puts every field on its own line
+    Ok(json
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("\"src\": "))
+        .filter_map(|value| parse_json_string(value.trim_end_matches(',')))
+        .flat_map(|src| [Path::new(OUTPUT_FOLDER).join(&src), PathBuf::from(src)])
+        .collect())
+}
+
+fn relative_path(path: &Path) -> String {
+    let relative = path.strip_prefix(OUTPUT_FOLDER).unwrap_or(path);
+
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+pub fn mime_type(format: &str) -> &'static str {
+    match format {
+        "avif" => "image/avif",
+        "bmp" => "image/bmp",
+        "webp" => "image/webp",
+        "gif" => "image/gif",
+        "ico" => "image/vnd.microsoft.icon",
+        "jpg" | "jpeg" => "image/jpeg",
+        "json" => "application/json",
+        "jxl" => "image/jxl",
+        "png" => "image/png",
+        "qoi" => "image/qoi",
+        "tif" | "tiff" => "image/tiff",
+        "webmanifest" => "application/manifest+json",
+        _ => "application/octet-stream",
+    }
+}
+
+pub fn json_string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+/// Reverses `json_string`.
+fn parse_json_string(json: &str) -> Option<String> {
+    let mut chars = json.strip_prefix('"')?.strip_suffix('"')?.chars();
+    let mut text = String::with_capacity(json.len());
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+
+        match chars.next()? {
+            'n' => text.push('\n'),
+            'r' => text.push('\r'),
+            't' => text.push('\t'),
+            'u' => {
+                let code: String = chars.by_ref().take(4).collect();
+                text.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+            }
+            c => text.push(c),
+        }
+    }
+
+    Some(text)
+}
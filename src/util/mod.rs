@@ -1 +1,58 @@
-pub mod files;
\ No newline at end of file
+pub mod affinity;
+pub mod animation;
+pub mod archive;
+pub mod atlas;
+pub mod avif;
+pub mod capabilities;
+pub mod channels;
+pub mod clipboard;
+pub mod color;
+pub mod contact_sheet;
+pub mod content;
+pub mod crash;
+pub mod dds;
+pub mod document;
+pub mod download;
+pub mod exif;
+pub mod favicon;
+pub mod files;
+pub mod gif_output;
+#[cfg(feature = "heif")]
+pub mod heif;
+pub mod humanize;
+pub mod icons;
+pub mod interlace;
+pub mod jpeg;
+pub mod jxl;
+#[cfg(feature = "jxl-output")]
+pub mod jxl_output;
+pub mod manifest;
+pub mod mpo;
+pub mod multipage;
+pub mod naming;
+pub mod passthrough;
+pub mod pattern;
+#[cfg(feature = "pdf")]
+pub mod pdf;
+pub mod provenance;
+pub mod psd;
+pub mod quantize;
+pub mod quota;
+#[cfg(feature = "raw")]
+pub mod raw;
+pub mod report;
+pub mod scan;
+pub mod scratch;
+#[cfg(feature = "seam-carving")]
+pub mod seam;
+pub mod sequence;
+pub mod session;
+pub mod share;
+pub mod stats;
+pub mod svg;
+pub mod tiff_output;
+pub mod tiles;
+pub mod verify;
+pub mod watch;
+pub mod webhook;
+pub mod xmp;
@@ -0,0 +1,84 @@
+use std::ops::Range;
+
+const MP_ENTRY: u16 = 0xB002;
+
+/// Byte ranges of the images in a multi-picture (MPO) file, `None` for regular JPEGs.
+pub fn frames(data: &[u8]) -> Option<Vec<Range<usize>>> {
+    let header = mpf_header(data)?;
+    let tiff = data.get(header..)?;
+
+    let big_endian = match tiff.get(..4)? {
+        b"MM\0*" => true,
+        b"II*\0" => false,
+        _ => return None,
+    };
+
+    let read_u16 = |offset: usize| -> Option<u16> {
+        let bytes = tiff.get(offset..offset + 2)?.try_into().ok()?;
+        Some(match big_endian {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes),
+        })
+    };
+    let read_u32 = |offset: usize| -> Option<u32> {
+        let bytes = tiff.get(offset..offset + 4)?.try_into().ok()?;
+        Some(match big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        })
+    };
+
+    // MP index IFD
+    let ifd = read_u32(4)? as usize;
+    let entry_count = read_u16(ifd)? as usize;
+
+    let (count, offset) = (0..entry_count)
+        .map(|i| ifd + 2 + i * 12)
+        .find(|entry| read_u16(*entry) == Some(MP_ENTRY))
+        .and_then(|entry| Some((read_u32(entry + 4)? as usize, read_u32(entry + 8)? as usize)))?;
+
+    // 16 bytes per image: attributes, size, offset, two dependent image entries
+    let frames: Vec<Range<usize>> = (0..count / 16)
+        .filter_map(|i| {
+            let entry = offset + i * 16;
+            let size = read_u32(entry + 4)? as usize;
+            // The first image starts at the beginning of the file, others relative to the MPF header
+            let start = match read_u32(entry + 8)? as usize {
+                0 => 0,
+                relative => header + relative,
+            };
+
+            (start + size <= data.len()).then_some(start..start + size)
+        })
+        .collect();
+
+    match frames.len() > 1 {
+        true => Some(frames),
+        false => None,
+    }
+}
+
+/// Offset of the tiff header inside the APP2 MPF segment.
+fn mpf_header(data: &[u8]) -> Option<usize> {
+    if data.get(..2)? != [0xFF, 0xD8] {
+        return None;
+    }
+
+    let mut position = 2;
+
+    loop {
+        let marker = data.get(position..position + 4)?;
+        // Markers stop at the start of the scan data
+        if marker[0] != 0xFF || marker[1] == 0xDA {
+            return None;
+        }
+
+        let length = u16::from_be_bytes([marker[2], marker[3]]) as usize;
+
+        if marker[1] == 0xE2 && data.get(position + 4..position + 8)? == b"MPF\0" {
+            return Some(position + 8);
+        }
+
+        position += 2 + length;
+    }
+}
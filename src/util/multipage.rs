@@ -0,0 +1,63 @@
+use std::{error::Error, fs, io::BufReader, path::Path};
+
+use image::{DynamicImage, ImageBuffer};
+use tiff::{
+    decoder::{Decoder, DecodingResult},
+    ColorType,
+};
+
+/// Decodes every page of a TIFF, the image crate only reads the first one.
+pub fn tiff_pages(path: &Path) -> Result<Vec<DynamicImage>, Box<dyn Error>> {
+    let mut decoder = Decoder::new(BufReader::new(fs::File::open(path)?))?;
+    let mut pages = Vec::new();
+
+    loop {
+        let (width, height) = decoder.dimensions()?;
+        let color = decoder.colortype()?;
+        pages.push(to_image(width, height, color, decoder.read_image()?)?);
+
+        if !decoder.more_images() {
+            break;
+        }
+        decoder.next_image()?;
+    }
+
+    Ok(pages)
+}
+
+fn to_image(
+    width: u32,
+    height: u32,
+    color: ColorType,
+    data: DecodingResult,
+) -> Result<DynamicImage, Box<dyn Error>> {
+    let img = match (color, data) {
+        (ColorType::Gray(8), DecodingResult::U8(buf)) => {
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLuma8)
+        }
+        (ColorType::GrayA(8), DecodingResult::U8(buf)) => {
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLumaA8)
+        }
+        (ColorType::RGB(8), DecodingResult::U8(buf)) => {
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgb8)
+        }
+        (ColorType::RGBA(8), DecodingResult::U8(buf)) => {
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgba8)
+        }
+        (ColorType::Gray(16), DecodingResult::U16(buf)) => {
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLuma16)
+        }
+        (ColorType::GrayA(16), DecodingResult::U16(buf)) => {
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLumaA16)
+        }
+        (ColorType::RGB(16), DecodingResult::U16(buf)) => {
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgb16)
+        }
+        (ColorType::RGBA(16), DecodingResult::U16(buf)) => {
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgba16)
+        }
+        (color, _) => return Err(format!("Unsupported TIFF color type {:?}", color).into()),
+    };
+
+    img.ok_or_else(|| "TIFF page has an invalid size".into())
+}
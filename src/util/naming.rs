@@ -0,0 +1,90 @@
+use std::{
+    collections::HashMap,
+    env,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    process::{output_path, target_size},
+    structs::settings::Settings,
+    util::{download::is_url, sequence},
+};
+
+// Longest path Windows opens without the \\?\ prefix
+const MAX_PATH: usize = 259;
+
+/// Output paths that would go wrong if the queue was converted with the current settings.
+#[derive(Default)]
+pub struct NamingIssues {
+    // Output path and every source that would be written to it
+    pub collisions: Vec<(PathBuf, Vec<PathBuf>)>,
+    pub too_long: Vec<PathBuf>,
+    // Source and its size after resizing, for images over the output format's size limit
+    pub too_large: Vec<(PathBuf, u32, u32)>,
+}
+
+impl NamingIssues {
+    pub fn is_empty(&self) -> bool {
+        self.collisions.is_empty() && self.too_long.is_empty() && self.too_large.is_empty()
+    }
+}
+
+/// Works out the output path of every file in `files` without converting anything.
+pub fn check_names(files: &[PathBuf], settings: &Settings) -> NamingIssues {
+    let current_dir = env::current_dir().unwrap_or_default();
+
+    // Numbered like a run of the same queue would be
+    let numbers = match settings.rename_pattern {
+        Some(_) => sequence::number(files, settings, settings.sequence_start),
+        None => HashMap::new(),
+    };
+
+    let mut issues = NamingIssues::default();
+    let mut outputs: HashMap<String, (PathBuf, Vec<PathBuf>)> = HashMap::new();
+
+    for file in files.iter().filter(|file| !is_url(file)) {
+        let numbered = sequence::numbered(settings, numbers.get(file).copied());
+        let Ok(output) = output_path(file, None, &numbered) else {
+            continue;
+        };
+
+        if current_dir.join(&output).as_os_str().len() > MAX_PATH {
+            issues.too_long.push(output.clone());
+        }
+
+        // Windows and macOS don't tell names apart by case
+        outputs
+            .entry(key(&output))
+            .or_insert_with(|| (output, Vec::new()))
+            .1
+            .push(file.clone());
+    }
+
+    issues.collisions = outputs
+        .into_values()
+        .filter(|(_, sources)| sources.len() > 1)
+        .collect();
+    issues.collisions.sort();
+
+    // Only the headers are read, images that need a decoder of their own are left out
+    if let Some(max) = settings.encoding_options.max_dimension() {
+        if !settings.split_oversized && !settings.fit_to_limit {
+            for file in files.iter().filter(|file| !is_url(file)) {
+                let Ok((width, height)) = image::image_dimensions(file) else {
+                    continue;
+                };
+
+                let (width, height) = target_size(width, height, &settings.resize_options);
+                if width.max(height) > max {
+                    issues.too_large.push((file.clone(), width, height));
+                }
+            }
+        }
+    }
+
+    issues
+}
+
+fn key(path: &Path) -> String {
+    path.to_string_lossy().to_lowercase()
+}
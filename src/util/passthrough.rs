@@ -0,0 +1,143 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use image::ImageFormat;
+
+use crate::{
+    structs::{
+        file_type::EncodingOptions,
+        settings::{ChannelMode, PassthroughOption, ResizeOptions, Settings},
+    },
+    util::{files::sniff_format, xmp},
+};
+
+/// Whether converting `path` would only re-encode it: it is already in the target format,
+/// has the target size and nothing else in `settings` changes its pixels.
+pub fn is_unchanged(path: &Path, settings: &Settings) -> bool {
+    let target = match settings.encoding_options {
+        EncodingOptions::Avif(_) => ImageFormat::Avif,
+        EncodingOptions::WebP(_) => ImageFormat::WebP,
+        EncodingOptions::Jpeg(_) => ImageFormat::Jpeg,
+        EncodingOptions::Png(_) => ImageFormat::Png,
+        EncodingOptions::Qoi => ImageFormat::Qoi,
+        EncodingOptions::Tiff(_) => ImageFormat::Tiff,
+        EncodingOptions::Gif(_) => ImageFormat::Gif,
+        EncodingOptions::Ico(_) => ImageFormat::Ico,
+        EncodingOptions::Bmp(_) => ImageFormat::Bmp,
+        #[cfg(feature = "jxl-output")]
+        EncodingOptions::Jxl(_) => {
+            return crate::util::jxl::is_jxl(path) && keeps_pixels(path, settings)
+        }
+    };
+
+    sniff_format(path) == Some(target) && keeps_pixels(path, settings)
+}
+
+/// Whether converting `path` leaves its pixels as they are: it has the target size, is
+/// encoded with the default options and nothing else in `settings` edits it.
+pub fn keeps_pixels(path: &Path, settings: &Settings) -> bool {
+    if settings.channels != ChannelMode::Off
+        || settings.favicon_package
+        || settings.split_oversized
+        || settings.fit_to_limit
+        || settings.keep_exif
+        || settings.time_shift != 0
+        || settings.adaptive_quality
+        || !uses_default_encoding(path, settings)
+    {
+        return false;
+    }
+
+    // Stereo pairs would be split up when converted
+    let is_mpo = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mpo"));
+    if is_mpo && settings.mpo_all_frames {
+        return false;
+    }
+
+    if settings.use_sidecars && xmp::read_sidecar(path).is_some() {
+        return false;
+    }
+
+    let Ok((width, height)) = image::image_dimensions(path) else {
+        return false;
+    };
+
+    match settings.resize_options {
+        ResizeOptions::None => true,
+        ResizeOptions::Largest(size) => width.max(height) == size,
+        ResizeOptions::Smallest(size) => width.min(height) == size,
+        ResizeOptions::Exact(new_width, new_height) => (width, height) == (new_width, new_height),
+        #[cfg(feature = "seam-carving")]
+        ResizeOptions::SeamCarve(new_width, new_height) => {
+            (width, height) == (new_width, new_height)
+        }
+    }
+}
+
+/// Whether the output options are the defaults of their format, anything else like a palette,
+/// another bit depth or a JPEG thumbnail changes what gets written.
+fn uses_default_encoding(path: &Path, settings: &Settings) -> bool {
+    repacks_jpeg(path, settings)
+        || settings.encoding_options == settings.encoding_options.defaults()
+}
+
+// Repacking a JPEG as JPEG XL keeps its data whatever the JPEG XL options are
+#[cfg(feature = "jxl-output")]
+fn repacks_jpeg(path: &Path, settings: &Settings) -> bool {
+    matches!(settings.encoding_options, EncodingOptions::Jxl(_))
+        && sniff_format(path) == Some(ImageFormat::Jpeg)
+}
+
+#[cfg(not(feature = "jxl-output"))]
+fn repacks_jpeg(_path: &Path, _settings: &Settings) -> bool {
+    false
+}
+
+/// Places `source` at `output` without re-encoding it. Hard links fall back to a copy
+/// when the output folder is on another drive.
+pub fn write(source: &Path, output: &Path, option: PassthroughOption) -> io::Result<()> {
+    if let (Ok(source), Ok(output)) = (fs::canonicalize(source), fs::canonicalize(output)) {
+        if source == output {
+            return Err(io::Error::other("the output is the source file itself"));
+        }
+    }
+
+    // Written next to the output and renamed over it, an existing output that links to the
+    // source is only replaced once the new one is complete
+    let mut partial = output.as_os_str().to_owned();
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+    let _ = fs::remove_file(&partial);
+
+    let result = match option {
+        PassthroughOption::HardLink if fs::hard_link(source, &partial).is_ok() => Ok(()),
+        PassthroughOption::Optimize if sniff_format(source) == Some(ImageFormat::Png) => {
+            optimize_png(source, &partial)
+        }
+        _ => fs::copy(source, &partial).map(|_| ()),
+    }
+    .and_then(|_| fs::rename(&partial, output));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+
+    result
+}
+
+/// Rewrites the PNG at `source` with the filters and deflate settings that make it smallest
+/// without touching its pixels, the original is copied when it was already smaller.
+fn optimize_png(source: &Path, output: &Path) -> io::Result<()> {
+    let data = fs::read(source)?;
+
+    // Metadata chunks are kept, they were copied along before too
+    match oxipng::optimize_from_memory(&data, &oxipng::Options::from_preset(2)) {
+        Ok(optimized) if optimized.len() < data.len() => fs::write(output, optimized),
+        _ => fs::write(output, data),
+    }
+}
@@ -0,0 +1,88 @@
+use std::{
+    error::Error,
+    path::Path,
+    sync::{Mutex, MutexGuard, OnceLock},
+};
+
+use image::DynamicImage;
+use pdfium_render::prelude::*;
+
+use crate::structs::settings::{PdfMode, Settings};
+
+// PDF user space has 72 units per inch
+const POINTS_PER_INCH: f32 = 72.0;
+
+// Dropping a binding tears down the whole library, even while other threads still use it.
+// So it's bound once for the session and documents are handled one at a time
+static PDFIUM: OnceLock<Result<Mutex<Pdfium>, String>> = OnceLock::new();
+
+pub fn is_pdf(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+}
+
+/// Whether the pages of `path` are rendered, rather than having their images extracted.
+pub fn is_rendered(path: &Path, settings: &Settings) -> bool {
+    settings.pdf_mode == PdfMode::Rasterize && is_pdf(path)
+}
+
+/// Output suffix of the page at `index`, padded so pages sort in order.
+pub fn page_suffix(index: usize) -> String {
+    format!("-page-{:03}", index + 1)
+}
+
+/// Pdfium next to the executable, or else the system's copy.
+fn bind() -> Result<Pdfium, PdfiumError> {
+    let bindings = Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path("./"))
+        .or_else(|_| Pdfium::bind_to_system_library())?;
+
+    Ok(Pdfium::new(bindings))
+}
+
+fn library() -> &'static Result<Mutex<Pdfium>, String> {
+    PDFIUM.get_or_init(|| bind().map(Mutex::new).map_err(|e| e.to_string()))
+}
+
+/// The session's pdfium binding, locked for the caller.
+fn pdfium() -> Result<MutexGuard<'static, Pdfium>, Box<dyn Error>> {
+    match library() {
+        Ok(pdfium) => Ok(pdfium.lock().unwrap_or_else(|e| e.into_inner())),
+        Err(e) => Err(e.clone().into()),
+    }
+}
+
+/// Whether pdfium can be found at all.
+pub fn is_installed() -> bool {
+    library().is_ok()
+}
+
+/// Images from a PDF, either every page rendered or the raster images embedded in it.
+pub fn pdf_images(path: &Path, settings: &Settings) -> Result<Vec<DynamicImage>, Box<dyn Error>> {
+    let pdfium = pdfium()?;
+    let document = pdfium.load_pdf_from_file(path, None)?;
+    let mut images = Vec::new();
+
+    for page in document.pages().iter() {
+        match settings.pdf_mode {
+            PdfMode::Rasterize => {
+                let config = PdfRenderConfig::new()
+                    .scale_page_by_factor(settings.pdf_dpi as f32 / POINTS_PER_INCH);
+                images.push(page.render_with_config(&config)?.as_image());
+            }
+            PdfMode::Extract => {
+                for object in page.objects().iter() {
+                    if let Some(image) = object.as_image_object() {
+                        images.push(image.get_raw_image()?);
+                    }
+                }
+            }
+        }
+    }
+
+    if images.is_empty() {
+        return Err("PDF contains no images".into());
+    }
+
+    Ok(images)
+}
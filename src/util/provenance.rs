@@ -0,0 +1,95 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{structs::settings::Settings, OUTPUT_FOLDER};
+
+pub static PROVENANCE_FILE: &str = "provenance.log";
+
+// Conversions run in parallel, lines must not interleave
+static LOG_LOCK: Mutex<()> = Mutex::new(());
+
+/// What was recorded when an output was written.
+pub struct Record {
+    pub time: u64,
+    pub source: String,
+    pub settings: String,
+}
+
+fn log_path() -> PathBuf {
+    Path::new(OUTPUT_FOLDER).join(PROVENANCE_FILE)
+}
+
+/// Appends the settings used to write `output` from `source` to the provenance log, without
+/// secrets like the webhook url.
+///
+/// Each line holds the time, output, source and settings separated by tabs. Paths and settings
+/// are written in their debug form, which escapes tabs and newlines. Sources outside the working
+/// folder are only named, the log is shared along with the outputs.
+pub fn record(output: &Path, source: &Path, settings: &Settings) -> io::Result<()> {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    let line = format!(
+        "{}\t{:?}\t{:?}\t{:?}\n",
+        time,
+        output,
+        logged_source(source),
+        settings.redacted()
+    );
+
+    let _lock = LOG_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    fs::create_dir_all(OUTPUT_FOLDER)?;
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path())?
+        .write_all(line.as_bytes())
+}
+
+/// `source` relative to the working folder, or only its name when it lies outside of it.
+fn logged_source(source: &Path) -> &Path {
+    if source.is_relative() {
+        return source;
+    }
+
+    std::env::current_dir()
+        .ok()
+        .and_then(|dir| source.strip_prefix(dir).ok())
+        .unwrap_or_else(|| Path::new(source.file_name().unwrap_or_default()))
+}
+
+/// Most recent record for `output`, if it was written while provenance was recorded.
+pub fn inspect(output: &Path) -> io::Result<Option<Record>> {
+    let log = match fs::read_to_string(log_path()) {
+        Ok(log) => log,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let output = format!("{:?}", output);
+
+    let record = log.lines().rev().find_map(|line| {
+        let mut fields = line.splitn(4, '\t');
+        let (time, path, source, settings) = (
+            fields.next()?,
+            fields.next()?,
+            fields.next()?,
+            fields.next()?,
+        );
+
+        (path == output).then(|| Record {
+            time: time.parse().unwrap_or(0),
+            source: source.to_string(),
+            settings: settings.to_string(),
+        })
+    });
+
+    Ok(record)
+}
@@ -0,0 +1,20 @@
+use std::{error::Error, fs, path::Path};
+
+use image::{DynamicImage, RgbaImage};
+use psd::Psd;
+
+pub fn is_psd(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("psd"))
+}
+
+/// The flattened composite Photoshop stores alongside the layers.
+pub fn composite(path: &Path) -> Result<DynamicImage, Box<dyn Error>> {
+    let psd = Psd::from_bytes(&fs::read(path)?)?;
+
+    let buf = RgbaImage::from_raw(psd.width(), psd.height(), psd.rgba())
+        .ok_or("PSD has an invalid size")?;
+
+    Ok(DynamicImage::ImageRgba8(buf))
+}
@@ -0,0 +1,156 @@
+use std::error::Error;
+
+use image::{DynamicImage, RgbaImage};
+use imagequant::RGBA;
+
+use crate::structs::file_type::PaletteDither;
+
+/// 4x4 Bayer threshold matrix for ordered dithering.
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// A palette and the palette index of every pixel of a frame.
+pub struct Indexed {
+    pub palette: Vec<RGBA>,
+    pub indexes: Vec<u8>,
+}
+
+/// Reduces `frames` to palettes of at most `colors` colors, built from the colors of all frames
+/// so animations don't flicker between them.
+pub fn remap(
+    frames: &[RgbaImage],
+    colors: u16,
+    dither: PaletteDither,
+) -> Result<Vec<Indexed>, Box<dyn Error>> {
+    let mut attributes = imagequant::new();
+    attributes.set_max_colors(colors.clamp(2, 256) as u32)?;
+
+    let pixels: Vec<Vec<RGBA>> = frames
+        .iter()
+        .map(|frame| {
+            frame
+                .pixels()
+                .map(|pixel| RGBA::new(pixel[0], pixel[1], pixel[2], pixel[3]))
+                .collect()
+        })
+        .collect();
+
+    let mut images = frames
+        .iter()
+        .zip(&pixels)
+        .map(|(frame, pixels)| {
+            attributes.new_image(
+                &pixels[..],
+                frame.width() as usize,
+                frame.height() as usize,
+                0.0,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut histogram = imagequant::Histogram::new(&attributes);
+    for image in &mut images {
+        histogram.add_image(&attributes, image)?;
+    }
+    let mut result = histogram.quantize(&attributes)?;
+
+    if dither == PaletteDither::Ordered {
+        let palette = result.palette_vec();
+        return Ok(frames
+            .iter()
+            .map(|frame| Indexed {
+                indexes: ordered(frame, &palette),
+                palette: palette.clone(),
+            })
+            .collect());
+    }
+
+    result.set_dithering_level(match dither {
+        PaletteDither::None => 0.0,
+        _ => 1.0,
+    })?;
+
+    images
+        .iter_mut()
+        .map(|image| {
+            let (palette, indexes) = result.remapped(image)?;
+            Ok(Indexed { palette, indexes })
+        })
+        .collect()
+}
+
+/// Maps every pixel to the closest palette color after offsetting it by the Bayer matrix.
+fn ordered(frame: &RgbaImage, palette: &[RGBA]) -> Vec<u8> {
+    // Roughly the distance between neighbouring palette colors
+    let spread = 255.0 / (palette.len() as f32).cbrt();
+
+    frame
+        .enumerate_pixels()
+        .map(|(x, y, pixel)| {
+            let threshold = BAYER[y as usize % 4][x as usize % 4] as f32;
+            let offset = ((threshold + 0.5) / 16.0 - 0.5) * spread;
+            let target = [
+                pixel[0] as f32 + offset,
+                pixel[1] as f32 + offset,
+                pixel[2] as f32 + offset,
+                pixel[3] as f32,
+            ];
+
+            let distance = |color: &RGBA| {
+                let channels = [color.r, color.g, color.b, color.a];
+                channels
+                    .iter()
+                    .zip(target)
+                    .map(|(&channel, target)| (channel as f32 - target).powi(2))
+                    .sum::<f32>()
+            };
+
+            palette
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b)))
+                .map_or(0, |(i, _)| i as u8)
+        })
+        .collect()
+}
+
+/// Reduces `img` to a palette of at most `colors` colors and writes it as an 8 bit indexed PNG.
+pub fn indexed_png(
+    img: &DynamicImage,
+    colors: u16,
+    compression: png::Compression,
+    adaptive_filter: bool,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let buf = img.to_rgba8();
+    let (width, height) = buf.dimensions();
+
+    let Indexed { palette, indexes } = remap(&[buf], colors, PaletteDither::FloydSteinberg)?
+        .pop()
+        .ok_or("Quantizing returned no image")?;
+
+    let mut data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut data, width, height);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_compression(compression);
+    encoder.set_adaptive_filter(match adaptive_filter {
+        true => png::AdaptiveFilterType::Adaptive,
+        false => png::AdaptiveFilterType::NonAdaptive,
+    });
+    encoder.set_palette(
+        palette
+            .iter()
+            .flat_map(|color| [color.r, color.g, color.b])
+            .collect::<Vec<u8>>(),
+    );
+
+    // Only needed when some palette entry isn't fully opaque
+    if palette.iter().any(|color| color.a < 255) {
+        encoder.set_trns(palette.iter().map(|color| color.a).collect::<Vec<u8>>());
+    }
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&indexes)?;
+    writer.finish()?;
+
+    Ok(data)
+}
@@ -0,0 +1,127 @@
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    storage::{Catalog, CATALOG_FILE},
+    structs::settings::Settings,
+    util::{
+        manifest::{listed_outputs, mime_type, MANIFEST_FILE},
+        provenance::PROVENANCE_FILE,
+    },
+    OUTPUT_FOLDER,
+};
+
+/// Deletes the oldest outputs until all output folders together fit in `settings.output_quota` bytes.
+///
+/// Only images the catalog or the manifest lists as outputs are deleted, anything else in the
+/// output folders counts towards the quota but is left alone. Returns the deleted files.
+pub fn enforce_quota(settings: &Settings) -> io::Result<Vec<PathBuf>> {
+    let recorded = recorded_outputs(settings)?;
+
+    let mut roots = vec![PathBuf::from(OUTPUT_FOLDER)];
+    for output in settings
+        .input_folders
+        .iter()
+        .filter_map(|folder| folder.output.clone())
+    {
+        if !roots.contains(&output) {
+            roots.push(output);
+        }
+    }
+
+    let mut files = Vec::new();
+    for root in roots.iter().filter(|root| root.is_dir()) {
+        collect_files(root, &mut files);
+    }
+
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    files.sort_by_key(|(_, _, modified)| *modified);
+
+    let mut deleted = Vec::new();
+    for (path, size, _) in files {
+        if total <= settings.output_quota {
+            break;
+        }
+
+        let is_recorded =
+            fs::canonicalize(&path).is_ok_and(|canonical| recorded.contains(&canonical));
+        if !is_recorded || !is_known_output(&path) || is_bookkeeping(&path) {
+            continue;
+        }
+
+        // One stuck file shouldn't keep the rest over the quota
+        match fs::remove_file(&path) {
+            Ok(()) => {
+                total -= size;
+                deleted.push(path);
+            }
+            Err(e) => eprintln!("Failed to delete '{}': {}", path.display(), e),
+        }
+    }
+
+    Ok(deleted)
+}
+
+/// Real paths of every output the catalog and the manifest know about.
+fn recorded_outputs(settings: &Settings) -> io::Result<HashSet<PathBuf>> {
+    let mut outputs = listed_outputs()?;
+
+    if settings.use_catalog {
+        let catalog = Catalog::open().map_err(io::Error::other)?;
+        outputs.extend(catalog.outputs().map_err(io::Error::other)?);
+    }
+
+    if outputs.is_empty() && !settings.use_catalog && !settings.write_manifest {
+        return Err(io::Error::other(
+            "outputs are only deleted when the catalog or the manifest lists them",
+        ));
+    }
+
+    Ok(outputs
+        .iter()
+        .filter_map(|output| fs::canonicalize(output).ok())
+        .collect())
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<(PathBuf, u64, SystemTime)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+
+        if metadata.is_dir() {
+            collect_files(&path, files);
+        } else if let Ok(modified) = metadata.modified() {
+            files.push((path, metadata.len(), modified));
+        }
+    }
+}
+
+// Images and the JSON indexes written next to them
+fn is_known_output(path: &Path) -> bool {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    mime_type(&extension) != "application/octet-stream"
+}
+
+// Files the app keeps about the outputs, which are never deleted. The catalog comes with
+// -wal, -shm and -journal files next to it
+fn is_bookkeeping(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| {
+        let name = name.to_string_lossy();
+        name == MANIFEST_FILE || name == PROVENANCE_FILE || name.starts_with(CATALOG_FILE)
+    })
+}
@@ -0,0 +1,25 @@
+use std::path::Path;
+
+use image::{DynamicImage, RgbImage};
+
+pub const RAW_EXTENSIONS: [&str; 10] = [
+    "cr2", "nef", "nrw", "arw", "dng", "orf", "rw2", "raf", "pef", "srw",
+];
+
+/// Whether `path` is a camera RAW file, most of them are TIFF based so this goes by extension.
+pub fn is_raw(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| RAW_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Demosaics a RAW file with the camera's default development into an 8 bit sRGB image.
+pub fn decode(path: &Path) -> Result<DynamicImage, String> {
+    // Full size, resizing is left to the pipeline
+    let decoded = imagepipe::simple_decode_8bit(path, 0, 0)?;
+
+    let buf = RgbImage::from_raw(decoded.width as u32, decoded.height as u32, decoded.data)
+        .ok_or("RAW image has an invalid size")?;
+
+    Ok(DynamicImage::ImageRgb8(buf))
+}
@@ -0,0 +1,110 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    structs::{error::ConvertError, update::Converted},
+    util::humanize,
+};
+
+#[derive(Clone, Copy)]
+pub enum TableFormat {
+    Markdown,
+    Csv,
+}
+
+/// Per-file outcomes of a run as a table for pasting into tickets or emails.
+pub fn results_table(
+    success: &[(PathBuf, Converted)],
+    failed: &[(PathBuf, ConvertError)],
+    format: TableFormat,
+    verbose: bool,
+) -> String {
+    let mut rows = vec![vec![
+        "Name".to_string(),
+        "Old size".to_string(),
+        "New size".to_string(),
+        "Ratio".to_string(),
+    ]];
+
+    if verbose {
+        rows[0].push("Stages".to_string());
+    }
+
+    for (path, converted) in success {
+        let ratio = match converted.input_size {
+            _ if converted.unchanged => "unchanged".to_string(),
+            0 => "-".to_string(),
+            size => format!("{:.1}%", converted.output_size as f64 / size as f64 * 100.0),
+        };
+
+        rows.push(vec![
+            file_name(path),
+            size_cell(converted.input_size, format),
+            size_cell(converted.output_size, format),
+            ratio,
+        ]);
+
+        if verbose {
+            rows.last_mut().unwrap().push(converted.timings.to_string());
+        }
+    }
+
+    for (path, error) in failed {
+        rows.push(vec![
+            file_name(path),
+            "-".to_string(),
+            error.category().to_string(),
+            "-".to_string(),
+        ]);
+
+        if verbose {
+            rows.last_mut().unwrap().push("-".to_string());
+        }
+    }
+
+    let mut table = String::new();
+
+    for (i, row) in rows.iter().enumerate() {
+        match format {
+            TableFormat::Markdown => {
+                let cells: Vec<String> = row.iter().map(|cell| cell.replace('|', "\\|")).collect();
+                table.push_str(&format!("| {} |\n", cells.join(" | ")));
+
+                if i == 0 {
+                    table.push_str("|---|---:|---:|---:|");
+                    if verbose {
+                        table.push_str("---|");
+                    }
+                    table.push('\n');
+                }
+            }
+            TableFormat::Csv => {
+                let cells: Vec<String> = row.iter().map(|cell| csv_escape(cell)).collect();
+                table.push_str(&format!("{}\n", cells.join(",")));
+            }
+        }
+    }
+
+    table
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+fn size_cell(size: u64, format: TableFormat) -> String {
+    match format {
+        // Raw bytes so spreadsheets can do math on them
+        TableFormat::Csv => size.to_string(),
+        TableFormat::Markdown => humanize::size(size),
+    }
+}
+
+fn csv_escape(cell: &str) -> String {
+    match cell.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", cell.replace('"', "\"\"")),
+        false => cell.to_string(),
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+        Arc, Mutex,
+    },
+    time::Instant,
+};
+
+use crate::{
+    structs::{settings::Settings, update::ScanUpdate},
+    util::{
+        files::{prepare_folders, total_size, walk_folder},
+        stats::FileStats,
+    },
+};
+
+/// Scans every input folder, streaming the images found to `sender` as they come in.
+///
+/// Stops when `stop_flag` is set or the receiving end is dropped.
+pub fn scan_inputs(sender: Sender<ScanUpdate>, stop_flag: Arc<AtomicBool>, settings: Settings) {
+    let start = Instant::now();
+
+    if let Err(e) = prepare_folders() {
+        let _ = sender.send(ScanUpdate::Failed(e.to_string()));
+        return;
+    }
+
+    // Each file once, even when input folders overlap
+    let seen = Mutex::new(HashSet::new());
+
+    let found = |files: Vec<PathBuf>| {
+        let keys: Vec<PathBuf> = files
+            .iter()
+            .map(|path| fs::canonicalize(path).unwrap_or_else(|_| path.clone()))
+            .collect();
+
+        let files: Vec<PathBuf> = {
+            let mut seen = seen.lock().unwrap();
+            files
+                .into_iter()
+                .zip(keys)
+                .filter(|(_, key)| seen.insert(key.clone()))
+                .map(|(path, _)| path)
+                .collect()
+        };
+
+        if files.is_empty() {
+            return;
+        }
+
+        let size = total_size(&files);
+        if sender.send(ScanUpdate::Found(files.clone(), size)).is_err() {
+            stop_flag.store(true, Ordering::Relaxed);
+            return;
+        }
+
+        let stats = FileStats::inspect(&files);
+        if sender.send(ScanUpdate::Inspected(stats)).is_err() {
+            stop_flag.store(true, Ordering::Relaxed);
+        }
+    };
+
+    for folder in &settings.input_folders {
+        if stop_flag.load(Ordering::Relaxed) {
+            return;
+        }
+
+        if !folder.path.is_dir() {
+            eprintln!("Skipping '{}', not a directory", folder.path.display());
+            continue;
+        }
+
+        let skipped = |path: &Path, e: io::Error| {
+            let _ = sender.send(ScanUpdate::Skipped(path.to_path_buf(), e.to_string()));
+        };
+
+        if let Err(e) = walk_folder(
+            &folder.path,
+            folder.recursive,
+            &settings,
+            &stop_flag,
+            &found,
+            &skipped,
+        ) {
+            let _ = sender.send(ScanUpdate::Failed(format!(
+                "Failed to scan '{}': {}",
+                folder.path.display(),
+                e
+            )));
+            return;
+        }
+    }
+
+    if !stop_flag.load(Ordering::Relaxed) {
+        let _ = sender.send(ScanUpdate::Completed(start.elapsed()));
+    }
+}
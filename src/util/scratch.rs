@@ -0,0 +1,86 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use crate::structs::settings::Settings;
+
+static STAGED_COUNT: AtomicUsize = AtomicUsize::new(0);
+// Every session folder handed out, the scratch location can be changed while the app runs
+static SESSION_DIRS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Folder for this session's temporary files, inside the configured scratch location.
+pub fn session_dir(settings: &Settings) -> PathBuf {
+    let dir = settings
+        .scratch_dir
+        .clone()
+        .unwrap_or_else(std::env::temp_dir)
+        .join(format!("image-converter-{}", std::process::id()));
+
+    let mut dirs = SESSION_DIRS.lock().unwrap();
+    if !dirs.contains(&dir) {
+        dirs.push(dir.clone());
+    }
+
+    dir
+}
+
+/// Removes everything this session left in the scratch locations it used.
+pub fn cleanup() -> io::Result<()> {
+    let dirs = SESSION_DIRS.lock().unwrap();
+    let mut result = Ok(());
+
+    // One folder failing doesn't keep the others around
+    for dir in dirs.iter().filter(|dir| dir.exists()) {
+        if let Err(e) = fs::remove_dir_all(dir) {
+            result = Err(e);
+        }
+    }
+
+    result
+}
+
+/// Writes `data` to `path` through the scratch folder so an interrupted write never leaves a half written output.
+pub fn write_staged(data: &[u8], path: &Path, settings: &Settings) -> io::Result<()> {
+    let staged = staging_file(path, settings)?;
+    if let Err(e) = fs::write(&staged, data) {
+        let _ = fs::remove_file(&staged);
+        return Err(e);
+    }
+
+    finish_staged(&staged, path)
+}
+
+/// A fresh file in the scratch folder to build an output for `path` in, for outputs too large
+/// to hold in memory. Moved into place with [`finish_staged`].
+pub fn staging_file(path: &Path, settings: &Settings) -> io::Result<PathBuf> {
+    let staging = session_dir(settings).join("staging");
+    fs::create_dir_all(&staging)?;
+
+    let count = STAGED_COUNT.fetch_add(1, Ordering::Relaxed);
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    Ok(staging.join(format!("{}-{}", count, file_name)))
+}
+
+/// Moves a file written with [`staging_file`] to `path`, replacing what was there.
+pub fn finish_staged(staged: &Path, path: &Path) -> io::Result<()> {
+    if fs::rename(staged, path).is_ok() {
+        return Ok(());
+    }
+
+    // Renames don't work across volumes, copy next to the output and rename there instead
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".part");
+
+    let result = fs::copy(staged, &partial).and_then(|_| fs::rename(&partial, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+
+    fs::remove_file(staged)?;
+    result
+}
@@ -0,0 +1,93 @@
+use image::{imageops, imageops::FilterType, DynamicImage, GenericImageView, RgbaImage};
+
+use crate::util::color;
+
+/// Retargets `img` to exactly `width` by `height`, removing the least noticeable seams instead of cropping.
+///
+/// The image is first scaled to cover the target size, then seams are carved out of the dimension that is too long.
+pub fn retarget(img: DynamicImage, width: u32, height: u32) -> DynamicImage {
+    if width == 0 || height == 0 {
+        return img;
+    }
+
+    let color_type = img.color();
+    let (original_width, original_height) = img.dimensions();
+
+    let scale = (width as f64 / original_width as f64).max(height as f64 / original_height as f64);
+    let scaled_width = ((original_width as f64 * scale).round() as u32).max(width);
+    let scaled_height = ((original_height as f64 * scale).round() as u32).max(height);
+
+    let mut buf = img
+        .resize_exact(scaled_width, scaled_height, FilterType::Lanczos3)
+        .to_rgba8();
+
+    buf = carve_width(buf, width);
+
+    // Horizontal seams are vertical seams of the rotated image
+    if buf.height() > height {
+        buf = imageops::rotate270(&carve_width(imageops::rotate90(&buf), height));
+    }
+
+    color::convert(DynamicImage::ImageRgba8(buf), color_type)
+}
+
+fn carve_width(mut buf: RgbaImage, width: u32) -> RgbaImage {
+    while buf.width() > width {
+        let seam = find_seam(&buf);
+        buf = remove_seam(&buf, &seam);
+    }
+
+    buf
+}
+
+/// Column to remove per row, along the path with the least total energy.
+fn find_seam(buf: &RgbaImage) -> Vec<u32> {
+    let (width, height) = (buf.width() as usize, buf.height() as usize);
+
+    let luma: Vec<i32> = buf
+        .pixels()
+        .map(|pixel| pixel.0[..3].iter().map(|&channel| channel as i32).sum())
+        .collect();
+    let at = |x: usize, y: usize| luma[y * width + x];
+
+    // Gradient magnitude as energy
+    let mut cost: Vec<u64> = (0..width * height)
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            let dx = at((x + 1).min(width - 1), y) - at(x.saturating_sub(1), y);
+            let dy = at(x, (y + 1).min(height - 1)) - at(x, y.saturating_sub(1));
+            (dx.unsigned_abs() + dy.unsigned_abs()) as u64
+        })
+        .collect();
+
+    for y in 1..height {
+        for x in 0..width {
+            let above = (x.saturating_sub(1)..=(x + 1).min(width - 1))
+                .map(|above| cost[(y - 1) * width + above])
+                .min()
+                .unwrap_or(0);
+            cost[y * width + x] += above;
+        }
+    }
+
+    let mut seam = vec![0; height];
+    let last_row = &cost[(height - 1) * width..];
+    let mut x = (0..width).min_by_key(|&x| last_row[x]).unwrap_or(0);
+    seam[height - 1] = x as u32;
+
+    for y in (0..height - 1).rev() {
+        x = (x.saturating_sub(1)..=(x + 1).min(width - 1))
+            .min_by_key(|&above| cost[y * width + above])
+            .unwrap_or(x);
+        seam[y] = x as u32;
+    }
+
+    seam
+}
+
+fn remove_seam(buf: &RgbaImage, seam: &[u32]) -> RgbaImage {
+    RgbaImage::from_fn(buf.width() - 1, buf.height(), |x, y| {
+        let source = if x < seam[y as usize] { x } else { x + 1 };
+        *buf.get_pixel(source, y)
+    })
+}
@@ -0,0 +1,122 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    structs::{
+        settings::{SequenceOrder, Settings},
+        update::Job,
+    },
+    util::{download::is_url, exif},
+};
+
+/// Sequence number of every file in `files`, counted from `start` in `settings.sequence_order`.
+pub fn number<T: AsRef<Path>>(
+    files: &[T],
+    settings: &Settings,
+    start: u64,
+) -> HashMap<PathBuf, u64> {
+    let mut files: Vec<&Path> = files
+        .iter()
+        .map(AsRef::as_ref)
+        .filter(|file| !is_url(file))
+        .collect();
+
+    match settings.sequence_order {
+        SequenceOrder::Name => files.sort_by_key(|file| file.file_name()),
+        // Photos without a date go last, "YYYY:MM:DD HH:MM:SS" sorts chronologically
+        SequenceOrder::DateTaken => files.sort_by_cached_key(|file| {
+            let date = exif::date_taken(file);
+            (date.is_none(), date, file.file_name())
+        }),
+    }
+
+    files
+        .into_iter()
+        .zip((start..).step_by(settings.sequence_step.max(1) as usize))
+        .map(|(file, number)| (file.to_path_buf(), number))
+        .collect()
+}
+
+/// Numbers the jobs in `jobs` that don't have a number yet, counting on from `start`.
+/// Returns the number the next batch continues from.
+pub fn number_jobs(jobs: &mut [Job], settings: &Settings, start: u64) -> u64 {
+    let unnumbered: Vec<&Path> = jobs
+        .iter()
+        .filter(|job| job.sequence.is_none())
+        .map(|job| job.path.as_path())
+        .collect();
+    let numbers = number(&unnumbered, settings, start);
+
+    for job in jobs.iter_mut().filter(|job| job.sequence.is_none()) {
+        job.sequence = numbers.get(&job.path).copied();
+    }
+
+    start + numbers.len() as u64 * settings.sequence_step.max(1)
+}
+
+/// `settings` for the file numbered `number`, with the number filled into the rename pattern.
+pub fn numbered(settings: &Settings, number: Option<u64>) -> Settings {
+    let mut settings = settings.clone();
+    if let (Some(pattern), Some(number)) = (&settings.rename_pattern, number) {
+        settings.rename_pattern = Some(replace_placeholders(pattern, |placeholder, width| {
+            (placeholder == "seq").then(|| format!("{:0width$}", number, width = width.len()))
+        }));
+    }
+
+    settings
+}
+
+/// Whether `pattern` names files apart, without `{name}` or `{seq}` every file would get the
+/// same name.
+pub fn is_valid(pattern: &str) -> bool {
+    let mut found = false;
+    replace_placeholders(pattern, |placeholder, _| {
+        found |= matches!(placeholder, "name" | "seq");
+        None
+    });
+
+    found
+}
+
+/// Fills `{name}` and `{seq}` into `pattern`. `{seq:0000}` pads the number to as many digits
+/// as there are zeros, files without a number keep their name there.
+pub fn apply(pattern: &str, name: &str, number: Option<u64>) -> String {
+    replace_placeholders(pattern, |placeholder, width| match (placeholder, number) {
+        ("name", _) | ("seq", None) => Some(name.to_string()),
+        ("seq", Some(number)) => Some(format!("{:0width$}", number, width = width.len())),
+        _ => None,
+    })
+}
+
+/// Replaces every `{placeholder:width}` in `pattern` with what `fill` returns for it,
+/// placeholders it returns `None` for are kept as typed.
+fn replace_placeholders(
+    pattern: &str,
+    mut fill: impl FnMut(&str, &str) -> Option<String>,
+) -> String {
+    let mut output = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+
+        let token = &rest[1..end];
+        let (placeholder, width) = token.split_once(':').unwrap_or((token, ""));
+        match fill(placeholder, width) {
+            Some(value) => output.push_str(&value),
+            None => output.push_str(&rest[..=end]),
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    output.push_str(rest);
+    output
+}
@@ -0,0 +1,226 @@
+use std::{
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::structs::settings::SortOrder;
+
+pub static SESSION_FILE: &str = "session.txt";
+
+/// Where the user left the window, restored on the next start so a half prepared queue
+/// survives closing the app.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub page: String,
+    // Name filter of the file list
+    pub filter: String,
+    pub file_filter: Option<String>,
+    pub sort_order: SortOrder,
+    // Discovery filters, see `Settings`
+    pub min_size: u64,
+    pub max_size: u64,
+    pub modified_after: Option<u64>,
+    // Files left out of the queue
+    pub excluded: Vec<PathBuf>,
+}
+
+impl Session {
+    pub fn load() -> Option<Self> {
+        let text = fs::read_to_string(SESSION_FILE).ok()?;
+
+        let mut session = Session {
+            page: String::new(),
+            filter: String::new(),
+            file_filter: None,
+            sort_order: SortOrder::Name,
+            min_size: 0,
+            max_size: 0,
+            modified_after: None,
+            excluded: Vec::new(),
+        };
+
+        // One `key<tab>value` per line with tabs, newlines and backslashes in values escaped,
+        // excluded files repeat
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('\t') else {
+                continue;
+            };
+
+            match key {
+                "page" => session.page = unescape(value),
+                "filter" => session.filter = unescape(value),
+                "file_filter" => session.file_filter = Some(unescape(value)),
+                "sort_order" => {
+                    session.sort_order = [
+                        SortOrder::Name,
+                        SortOrder::SmallestFirst,
+                        SortOrder::LargestFirst,
+                        SortOrder::NewestFirst,
+                        SortOrder::OldestFirst,
+                        SortOrder::Shuffle,
+                    ]
+                    .into_iter()
+                    .find(|order| order.to_string() == value)
+                    .unwrap_or(SortOrder::Name)
+                }
+                "min_size" => session.min_size = value.parse().unwrap_or(0),
+                "max_size" => session.max_size = value.parse().unwrap_or(0),
+                "modified_after" => session.modified_after = value.parse().ok(),
+                "excluded" => session.excluded.extend(unescape_path(value)),
+                _ => {}
+            }
+        }
+
+        Some(session)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let mut text = format!(
+            "page\t{}\nfilter\t{}\nsort_order\t{}\nmin_size\t{}\nmax_size\t{}\n",
+            escape(&self.page),
+            escape(&self.filter),
+            self.sort_order,
+            self.min_size,
+            self.max_size
+        );
+        if let Some(file_filter) = &self.file_filter {
+            text.push_str(&format!("file_filter\t{}\n", escape(file_filter)));
+        }
+        if let Some(modified_after) = self.modified_after {
+            text.push_str(&format!("modified_after\t{}\n", modified_after));
+        }
+        for path in &self.excluded {
+            text.push_str(&format!("excluded\t{}\n", escape_path(path)));
+        }
+
+        fs::write(SESSION_FILE, text)
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    pieces(text)
+        .filter_map(|piece| match piece {
+            Piece::Char(c) => Some(c),
+            Piece::Unit(_) => None,
+        })
+        .collect()
+}
+
+/// Part of an escaped value, a character or a `\xNN` or `\uNNNN` unit of a path that
+/// isn't valid unicode.
+enum Piece {
+    Char(char),
+    Unit(u16),
+}
+
+fn pieces(text: &str) -> impl Iterator<Item = Piece> + '_ {
+    let mut chars = text.chars();
+
+    std::iter::from_fn(move || loop {
+        let c = chars.next()?;
+        if c != '\\' {
+            return Some(Piece::Char(c));
+        }
+
+        let digits = match chars.next()? {
+            't' => return Some(Piece::Char('\t')),
+            'n' => return Some(Piece::Char('\n')),
+            'r' => return Some(Piece::Char('\r')),
+            'x' => 2,
+            'u' => 4,
+            c => return Some(Piece::Char(c)),
+        };
+
+        let hex: String = chars.by_ref().take(digits).collect();
+        if let Ok(unit) = u16::from_str_radix(&hex, 16) {
+            return Some(Piece::Unit(unit));
+        }
+    })
+}
+
+/// `path` escaped like other values, with bytes that aren't valid unicode written as `\xNN`
+/// so the exact path comes back.
+#[cfg(unix)]
+fn escape_path(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut escaped = String::new();
+    for chunk in path.as_os_str().as_bytes().utf8_chunks() {
+        escaped.push_str(&escape(chunk.valid()));
+        for byte in chunk.invalid() {
+            escaped.push_str(&format!("\\x{:02x}", byte));
+        }
+    }
+
+    escaped
+}
+
+#[cfg(unix)]
+fn unescape_path(text: &str) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStringExt;
+
+    let mut bytes = Vec::with_capacity(text.len());
+    for piece in pieces(text) {
+        match piece {
+            Piece::Char(c) => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            Piece::Unit(byte) => bytes.push(u8::try_from(byte).ok()?),
+        }
+    }
+
+    Some(PathBuf::from(OsString::from_vec(bytes)))
+}
+
+/// `path` escaped like other values, with unpaired surrogates written as `\uNNNN` so the
+/// exact path comes back.
+#[cfg(windows)]
+fn escape_path(path: &Path) -> String {
+    use std::os::windows::ffi::OsStrExt;
+
+    char::decode_utf16(path.as_os_str().encode_wide())
+        .map(|c| match c {
+            Ok(c) => escape(c.encode_utf8(&mut [0; 4])),
+            Err(e) => format!("\\u{:04x}", e.unpaired_surrogate()),
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+fn unescape_path(text: &str) -> Option<PathBuf> {
+    use std::os::windows::ffi::OsStringExt;
+
+    let mut units = Vec::with_capacity(text.len());
+    for piece in pieces(text) {
+        match piece {
+            Piece::Char(c) => units.extend_from_slice(c.encode_utf16(&mut [0; 2])),
+            Piece::Unit(unit) => units.push(unit),
+        }
+    }
+
+    Some(PathBuf::from(OsString::from_wide(&units)))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn escape_path(path: &Path) -> String {
+    escape(&path.to_string_lossy())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn unescape_path(text: &str) -> Option<PathBuf> {
+    Some(PathBuf::from(unescape(text)))
+}
@@ -0,0 +1,285 @@
+use std::{
+    collections::hash_map::RandomState,
+    fs,
+    hash::{BuildHasher, Hasher},
+    io::{self, BufRead, BufReader, Write},
+    net::{IpAddr, Ipv4Addr, TcpListener, TcpStream, UdpSocket},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::util::manifest::mime_type;
+
+// How often the server checks whether it should stop while no one connects
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A read-only HTTP server for a folder, stopped when dropped.
+pub struct Share {
+    pub url: String,
+    stop_flag: Arc<AtomicBool>,
+}
+
+impl Drop for Share {
+    fn drop(&mut self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Serves the images in `root` on a free port of every interface, so phones on the same network
+/// can browse them. Everything is under a random path, others on the network can't guess the URL.
+pub fn start(root: PathBuf) -> io::Result<Share> {
+    let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    listener.set_nonblocking(true)?;
+
+    let token = random_token();
+    let url = format!(
+        "http://{}:{}/{}/",
+        local_ip(),
+        listener.local_addr()?.port(),
+        token
+    );
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    fs::create_dir_all(&root)?;
+    let root = fs::canonicalize(&root)?;
+
+    let flag = Arc::clone(&stop_flag);
+    thread::spawn(move || {
+        while !flag.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let root = root.clone();
+                    let token = token.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = respond(stream, &root, &token) {
+                            eprintln!("Failed to answer share request: {}", e);
+                        }
+                    });
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+                Err(e) => eprintln!("Failed to accept share connection: {}", e),
+            }
+        }
+    });
+
+    Ok(Share { url, stop_flag })
+}
+
+/// 128 bits in hex, taken from the keys std seeds its hash maps with from the OS.
+fn random_token() -> String {
+    let mut hasher = blake3::Hasher::new();
+    for _ in 0..4 {
+        hasher.update(&RandomState::new().build_hasher().finish().to_le_bytes());
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0);
+    hasher.update(&now.to_le_bytes());
+
+    hasher.finalize().to_hex()[..32].to_string()
+}
+
+/// Address other devices on the network reach this one at. Connecting a UDP socket sends nothing,
+/// it only picks the interface that routes outwards.
+fn local_ip() -> IpAddr {
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| {
+            socket.connect((Ipv4Addr::new(192, 0, 2, 1), 80))?;
+            socket.local_addr()
+        })
+        .map(|address| address.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST))
+}
+
+fn respond(mut stream: TcpStream, root: &Path, token: &str) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+
+    let mut parts = request.split_whitespace();
+    let (method, target) = (
+        parts.next().unwrap_or_default(),
+        parts.next().unwrap_or("/"),
+    );
+    if method != "GET" && method != "HEAD" {
+        return send(
+            &mut stream,
+            false,
+            "405 Method Not Allowed",
+            "text/plain",
+            b"Read only",
+        );
+    }
+    let head = method == "HEAD";
+
+    // Requests without the token, and anything resolving outside the shared folder, e.g.
+    // through '..', are treated as missing
+    let target = percent_decode(target.split('?').next().unwrap_or_default());
+    let relative = match target
+        .strip_prefix('/')
+        .and_then(|target| target.strip_prefix(token))
+    {
+        Some(relative) if relative.starts_with('/') => relative,
+        _ => return not_found(&mut stream, head),
+    };
+    let path = match fs::canonicalize(root.join(relative.trim_start_matches('/'))) {
+        Ok(path) if path.starts_with(root) => path,
+        _ => return not_found(&mut stream, head),
+    };
+
+    if path.is_dir() {
+        let page = index_page(&path, root)?;
+        return send(
+            &mut stream,
+            head,
+            "200 OK",
+            "text/html; charset=utf-8",
+            page.as_bytes(),
+        );
+    }
+
+    // Only images, the catalog, manifest and logs next to them stay private
+    let mime_type = mime_type(&extension(&path));
+    if !mime_type.starts_with("image/") {
+        return not_found(&mut stream, head);
+    }
+
+    match head {
+        true => send_headers(&mut stream, "200 OK", mime_type, fs::metadata(&path)?.len()),
+        false => send(&mut stream, false, "200 OK", mime_type, &fs::read(&path)?),
+    }
+}
+
+fn not_found(stream: &mut TcpStream, head: bool) -> io::Result<()> {
+    send(stream, head, "404 Not Found", "text/plain", b"Not found")
+}
+
+/// Sends a response with `body`, or only its headers for HEAD requests.
+fn send(
+    stream: &mut TcpStream,
+    head: bool,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> io::Result<()> {
+    send_headers(stream, status, content_type, body.len() as u64)?;
+    if !head {
+        stream.write_all(body)?;
+    }
+    stream.flush()
+}
+
+fn send_headers(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    length: u64,
+) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status, content_type, length
+    )?;
+    stream.flush()
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// Gallery of the images in `dir` with links to its subfolders.
+fn index_page(dir: &Path, root: &Path) -> io::Result<String> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    entries.sort();
+
+    let title = match dir.strip_prefix(root) {
+        Ok(relative) if !relative.as_os_str().is_empty() => relative.to_string_lossy().to_string(),
+        _ => "Output".to_string(),
+    };
+
+    // The root has nothing shared above it
+    let mut links = match dir == root {
+        true => String::new(),
+        false => "<li><a href=\"../\">../</a></li>".to_string(),
+    };
+    let mut images = String::new();
+    for entry in &entries {
+        let name = entry.file_name().unwrap_or_default().to_string_lossy();
+        let href = html_escape(&percent_encode(&name));
+        let label = html_escape(&name);
+
+        if entry.is_dir() {
+            links.push_str(&format!("<li><a href=\"{}/\">{}/</a></li>", href, label));
+        } else if mime_type(&extension(entry)).starts_with("image/") {
+            images.push_str(&format!(
+                "<a href=\"{0}\"><img src=\"{0}\" alt=\"{1}\" loading=\"lazy\"></a>",
+                href, label
+            ));
+        }
+    }
+
+    Ok(format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><meta name=\"viewport\" content=\"width=device-width\">\
+         <title>{0}</title><style>body{{font-family:sans-serif;margin:1em}}img{{width:100%;max-width:320px;margin:4px}}</style></head>\
+         <body><h1>{0}</h1><ul>{1}</ul>{2}</body></html>",
+        html_escape(&title),
+        links,
+        images
+    ))
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
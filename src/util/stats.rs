@@ -0,0 +1,73 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use image::{ImageDecoder, ImageReader};
+use rayon::prelude::*;
+
+use crate::util::files::sniff_format;
+
+// Images with more pixels than this are counted as large, 20 megapixels
+const LARGE_PIXELS: u64 = 20_000_000;
+
+/// Breakdown of the queued files by type, transparency and size.
+#[derive(Debug, Clone, Default)]
+pub struct FileStats {
+    // Number of files and their total size in bytes per type
+    pub types: BTreeMap<String, (usize, u64)>,
+    pub with_alpha: usize,
+    pub large: usize,
+    // Files whose header the image crate can't read, decoded by other means or skipped
+    pub unknown: usize,
+}
+
+impl FileStats {
+    /// Reads the headers of `files`, without decoding any pixels.
+    pub fn inspect(files: &[PathBuf]) -> FileStats {
+        files
+            .par_iter()
+            .map(|path| {
+                let mut stats = FileStats::default();
+
+                let size = path.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+                let file_type = match sniff_format(path) {
+                    Some(format) => format.extensions_str()[0].to_string(),
+                    None => path
+                        .extension()
+                        .map(|ext| ext.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                };
+                stats.types.insert(file_type.to_uppercase(), (1, size));
+
+                let decoder = ImageReader::open(path)
+                    .and_then(|reader| reader.with_guessed_format())
+                    .ok()
+                    .and_then(|reader| reader.into_decoder().ok());
+
+                match decoder {
+                    Some(decoder) => {
+                        let (width, height) = decoder.dimensions();
+                        stats.with_alpha = decoder.color_type().has_alpha() as usize;
+                        stats.large = (width as u64 * height as u64 > LARGE_PIXELS) as usize;
+                    }
+                    None => stats.unknown = 1,
+                }
+
+                stats
+            })
+            .reduce(FileStats::default, |mut total, stats| {
+                total.merge(stats);
+                total
+            })
+    }
+
+    pub fn merge(&mut self, other: FileStats) {
+        for (file_type, (count, size)) in other.types {
+            let entry = self.types.entry(file_type).or_default();
+            entry.0 += count;
+            entry.1 += size;
+        }
+
+        self.with_alpha += other.with_alpha;
+        self.large += other.large;
+        self.unknown += other.unknown;
+    }
+}
@@ -0,0 +1,51 @@
+use std::{error::Error, fs, path::Path};
+
+use image::{DynamicImage, RgbaImage};
+use resvg::{tiny_skia, usvg};
+
+pub fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg") || ext.eq_ignore_ascii_case("svgz"))
+}
+
+/// Renders an SVG so its longest side is `size` pixels.
+pub fn rasterize(path: &Path, size: u32) -> Result<DynamicImage, Box<dyn Error>> {
+    let data = fs::read(path)?;
+
+    let mut options = usvg::Options {
+        resources_dir: path.parent().map(Path::to_path_buf),
+        ..Default::default()
+    };
+    options.fontdb_mut().load_system_fonts();
+
+    let tree = usvg::Tree::from_data(&data, &options)?;
+
+    let (width, height) = (tree.size().width(), tree.size().height());
+    let scale = size as f32 / width.max(height);
+    let (width, height) = (
+        ((width * scale).round() as u32).max(1),
+        ((height * scale).round() as u32).max(1),
+    );
+
+    let mut pixmap = tiny_skia::Pixmap::new(width, height).ok_or("SVG is too large to render")?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    // Pixmaps are premultiplied
+    let data = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+
+    let buf = RgbaImage::from_raw(width, height, data).ok_or("SVG has an invalid size")?;
+
+    Ok(DynamicImage::ImageRgba8(buf))
+}
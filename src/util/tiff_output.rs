@@ -0,0 +1,72 @@
+use std::{error::Error, io::Cursor};
+
+use image::DynamicImage;
+use tiff::encoder::{
+    colortype::{self, ColorType},
+    compression::{Deflate, DeflateLevel, Lzw, Uncompressed},
+    TiffEncoder, TiffValue,
+};
+
+use crate::{
+    structs::file_type::{TiffCompression, TiffSettings},
+    util::color,
+};
+
+/// Encodes `img` as a single page TIFF, 16 bit images stay 16 bit when `options` allows it.
+pub fn encode(img: &DynamicImage, options: &TiffSettings) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (width, height) = (img.width(), img.height());
+    let compression = options.compression;
+    let deep = options.sixteen_bit && color::bit_depth(img) > 8;
+
+    match (img.color().has_color(), img.color().has_alpha(), deep) {
+        (false, false, true) => {
+            write::<colortype::Gray16>(width, height, compression, img.to_luma16().as_raw())
+        }
+        (false, false, false) => {
+            write::<colortype::Gray8>(width, height, compression, img.to_luma8().as_raw())
+        }
+        // Gray with alpha is stored as rgba, few readers support it
+        (_, true, true) => {
+            write::<colortype::RGBA16>(width, height, compression, img.to_rgba16().as_raw())
+        }
+        (_, true, false) => {
+            write::<colortype::RGBA8>(width, height, compression, img.to_rgba8().as_raw())
+        }
+        (true, false, true) => {
+            write::<colortype::RGB16>(width, height, compression, img.to_rgb16().as_raw())
+        }
+        (true, false, false) => {
+            write::<colortype::RGB8>(width, height, compression, img.to_rgb8().as_raw())
+        }
+    }
+}
+
+fn write<C: ColorType>(
+    width: u32,
+    height: u32,
+    compression: TiffCompression,
+    data: &[C::Inner],
+) -> Result<Vec<u8>, Box<dyn Error>>
+where
+    [C::Inner]: TiffValue,
+{
+    let mut buf = Cursor::new(Vec::new());
+    let mut encoder = TiffEncoder::new(&mut buf)?;
+
+    match compression {
+        TiffCompression::None => {
+            encoder.write_image_with_compression::<C, _>(width, height, Uncompressed, data)?
+        }
+        TiffCompression::Lzw => {
+            encoder.write_image_with_compression::<C, _>(width, height, Lzw, data)?
+        }
+        TiffCompression::Deflate => encoder.write_image_with_compression::<C, _>(
+            width,
+            height,
+            Deflate::with_level(DeflateLevel::Balanced),
+            data,
+        )?,
+    }
+
+    Ok(buf.into_inner())
+}
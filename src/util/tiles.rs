@@ -0,0 +1,72 @@
+use image::DynamicImage;
+
+use crate::util::manifest::json_string;
+
+// Suffix of the top left tile, outputs are found by it
+pub static FIRST_SUFFIX: &str = "-tile-1-1";
+
+/// A piece of a larger image and where it sits in it.
+pub struct Tile {
+    pub column: u32,
+    pub row: u32,
+    pub x: u32,
+    pub y: u32,
+    pub image: DynamicImage,
+}
+
+/// Cuts `img` into a grid of equally sized tiles no wider or higher than `max`.
+pub fn split(img: &DynamicImage, max: u32) -> Vec<Tile> {
+    let (width, height) = (img.width(), img.height());
+    let columns = width.div_ceil(max);
+    let rows = height.div_ceil(max);
+    let tile_width = width.div_ceil(columns);
+    let tile_height = height.div_ceil(rows);
+
+    let mut tiles = Vec::with_capacity((columns * rows) as usize);
+    for row in 0..rows {
+        for column in 0..columns {
+            let (x, y) = (column * tile_width, row * tile_height);
+            tiles.push(Tile {
+                column,
+                row,
+                x,
+                y,
+                image: img.crop_imm(x, y, tile_width.min(width - x), tile_height.min(height - y)),
+            });
+        }
+    }
+
+    tiles
+}
+
+/// Suffix of the output of the tile at `column`, `row`.
+pub fn suffix(tile: &Tile) -> String {
+    format!("-tile-{}-{}", tile.row + 1, tile.column + 1)
+}
+
+/// JSON index describing how the tiles in `files` fit back together into the full image.
+pub fn index(width: u32, height: u32, tiles: &[Tile], files: &[String]) -> String {
+    let entries: Vec<String> = tiles
+        .iter()
+        .zip(files)
+        .map(|(tile, file)| {
+            format!(
+                "    {{ \"file\": {}, \"row\": {}, \"column\": {}, \"x\": {}, \"y\": {}, \"width\": {}, \"height\": {} }}",
+                json_string(file),
+                tile.row,
+                tile.column,
+                tile.x,
+                tile.y,
+                tile.image.width(),
+                tile.image.height()
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\n  \"width\": {},\n  \"height\": {},\n  \"tiles\": [\n{}\n  ]\n}}\n",
+        width,
+        height,
+        entries.join(",\n")
+    )
+}
@@ -0,0 +1,256 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use image::{GenericImageView, ImageReader};
+use rayon::prelude::*;
+
+use crate::{
+    process::{first_outputs, output_path, target_size},
+    storage::Catalog,
+    structs::{file_type::EncodingOptions, settings::Settings},
+    util::{download::is_url, jxl, sequence, xmp},
+};
+
+/// What's wrong with the output of a source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    Missing,
+    Empty,
+    // Truncated or otherwise unreadable, with the decoder's error
+    Corrupt(String),
+    // The source changed after it was converted
+    Outdated,
+    // The catalog has it written with different settings
+    OtherSettings,
+    // Smaller than the settings make it, its size and the expected one
+    Undersized((u32, u32), (u32, u32)),
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::Missing => write!(f, "missing"),
+            Problem::Empty => write!(f, "empty"),
+            Problem::Corrupt(e) => write!(f, "corrupt: {}", e),
+            Problem::Outdated => write!(f, "older than its source"),
+            Problem::OtherSettings => write!(f, "converted with other settings"),
+            Problem::Undersized((width, height), (expected_width, expected_height)) => write!(
+                f,
+                "{}x{} instead of {}x{}",
+                width, height, expected_width, expected_height
+            ),
+        }
+    }
+}
+
+/// A source whose output needs converting again.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub source: PathBuf,
+    // Number the source had in the whole queue, so a repair names it the same
+    pub sequence: Option<u64>,
+    pub output: PathBuf,
+    pub problem: Problem,
+}
+
+/// Checks that every file in `files` has a complete output in every format of `settings`,
+/// reading each output in full. Sources that turn into several outputs are judged by their
+/// first one.
+pub fn verify(files: &[PathBuf], settings: &Settings) -> Vec<Finding> {
+    // Numbered like a run of the same queue would be
+    let numbers = match settings.rename_pattern {
+        Some(_) => sequence::number(files, settings, settings.sequence_start),
+        None => HashMap::new(),
+    };
+    let numbered = |file: &PathBuf| sequence::numbered(settings, numbers.get(file).copied());
+
+    let mut findings: Vec<Finding> = files
+        .par_iter()
+        .filter(|file| !is_url(file))
+        .filter_map(|file| {
+            let (output, problem) = check(file, &numbered(file))?;
+            Some(Finding {
+                source: file.clone(),
+                sequence: numbers.get(file).copied(),
+                output,
+                problem,
+            })
+        })
+        .collect();
+
+    // Outputs that look fine may still be left over from other settings
+    if settings.use_catalog {
+        if let Ok(catalog) = Catalog::open() {
+            let fingerprint = settings.fingerprint();
+
+            for file in files.iter().filter(|file| !is_url(file)) {
+                if findings.iter().any(|finding| &finding.source == file) {
+                    continue;
+                }
+
+                // Every format's output, any of them written with other settings counts
+                let outdated = formats(&numbered(file))
+                    .into_iter()
+                    .filter_map(|settings| {
+                        first_outputs(file, &settings)
+                            .into_iter()
+                            .find(|output| output.exists())
+                    })
+                    .find(|output| {
+                        matches!(catalog.lookup(output), Ok(Some(record)) if record.settings != fingerprint)
+                    });
+
+                if let Some(output) = outdated {
+                    findings.push(Finding {
+                        source: file.clone(),
+                        sequence: numbers.get(file).copied(),
+                        output,
+                        problem: Problem::OtherSettings,
+                    });
+                }
+            }
+        }
+    }
+
+    findings.sort_by(|a, b| a.source.cmp(&b.source));
+    findings
+}
+
+/// `settings` once per format an output is written in. Favicon packages are the same for
+/// every format, so only the main one counts.
+fn formats(settings: &Settings) -> Vec<Settings> {
+    match settings.favicon_package {
+        true => vec![settings.clone()],
+        false => settings.per_format(),
+    }
+}
+
+/// The first broken output of `source` and what's wrong with it, `None` when all are fine.
+fn check(source: &Path, settings: &Settings) -> Option<(PathBuf, Problem)> {
+    formats(settings)
+        .iter()
+        .find_map(|settings| check_format(source, settings))
+}
+
+/// The output of `source` in the format of `settings` and what's wrong with it, `None` when
+/// it's fine.
+fn check_format(source: &Path, settings: &Settings) -> Option<(PathBuf, Problem)> {
+    let candidates = first_outputs(source, settings);
+
+    let Some(output) = candidates.iter().find(|output| output.exists()) else {
+        return Some((candidates.into_iter().next()?, Problem::Missing));
+    };
+
+    let metadata = fs::metadata(output).ok()?;
+    if metadata.len() == 0 {
+        return Some((output.clone(), Problem::Empty));
+    }
+
+    let size = match read(output) {
+        Ok(size) => size,
+        Err(e) => return Some((output.clone(), Problem::Corrupt(e))),
+    };
+
+    // Sides are compared longest to longest, EXIF orientation may have turned the output
+    if let (Some(size), Some(expected)) = (size, expected_size(source, output, settings)) {
+        let sides = |(width, height): (u32, u32)| (width.max(height), width.min(height));
+        let ((long, short), (expected_long, expected_short)) = (sides(size), sides(expected));
+
+        // Resizing may round a pixel off
+        if long + 1 < expected_long || short + 1 < expected_short {
+            return Some((output.clone(), Problem::Undersized(size, expected)));
+        }
+    }
+
+    let source_modified = fs::metadata(source).and_then(|m| m.modified()).ok()?;
+    if metadata
+        .modified()
+        .is_ok_and(|modified| modified < source_modified)
+    {
+        return Some((output.clone(), Problem::Outdated));
+    }
+
+    None
+}
+
+/// Size the output of `source` at `output` should have, `None` when it can't be told from
+/// the source's header or the output is only a part of it.
+fn expected_size(source: &Path, output: &Path, settings: &Settings) -> Option<(u32, u32)> {
+    // Frames, pages, tiles and icons have sizes of their own, limits and crops shrink images
+    let is_whole = output_path(source, None, settings).is_ok_and(|whole| whole == output);
+    if !is_whole
+        || settings.fit_to_limit
+        || matches!(settings.encoding_options, EncodingOptions::Ico(_))
+        || (settings.use_sidecars && xmp::read_sidecar(source).is_some())
+    {
+        return None;
+    }
+
+    let (width, height) = image::image_dimensions(source).ok()?;
+    Some(target_size(width, height, &settings.resize_options))
+}
+
+/// Reads `path` the whole way through, returns its size when it could be decoded.
+fn read(path: &Path) -> Result<Option<(u32, u32)>, String> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    match extension.as_str() {
+        // AVIF outputs can't be decoded without dav1d, their boxes show whether they're cut off
+        "avif" => fs::read(path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| check_boxes(&data))
+            .map(|_| None),
+        "jxl" => jxl::decode(path)
+            .map(|img| Some(img.dimensions()))
+            .map_err(|e| e.to_string()),
+        _ => ImageReader::open(path)
+            .map_err(|e| e.to_string())?
+            .with_guessed_format()
+            .map_err(|e| e.to_string())?
+            .decode()
+            .map(|img| Some(img.dimensions()))
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// Whether the top level ISOBMFF boxes of an AVIF file start with `ftyp` and add up to
+/// the file's length.
+fn check_boxes(data: &[u8]) -> Result<(), String> {
+    if data.get(4..8) != Some(&b"ftyp"[..]) {
+        return Err("not an AVIF file".to_string());
+    }
+
+    let mut position = 0;
+
+    while position < data.len() {
+        let header = data
+            .get(position..position + 8)
+            .ok_or("file ends inside a box header")?;
+
+        let size = match u32::from_be_bytes(header[..4].try_into().unwrap()) {
+            // Box runs to the end of the file
+            0 => (data.len() - position) as u64,
+            // 64 bit size after the type
+            1 => data
+                .get(position + 8..position + 16)
+                .map(|size| u64::from_be_bytes(size.try_into().unwrap()))
+                .ok_or("file ends inside a box header")?,
+            size => size as u64,
+        };
+
+        if size < 8 || position as u64 + size > data.len() as u64 {
+            return Err("file is truncated".to_string());
+        }
+
+        position += size as usize;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use std::{
+    error::Error,
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, RecvTimeoutError, Sender},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use notify::{event::ModifyKind, EventKind, RecursiveMode, Watcher};
+
+use crate::{
+    process::convert_images,
+    structs::{
+        settings::Settings,
+        update::{Job, Update},
+    },
+    util::{
+        files::{is_moved_original, is_wanted},
+        humanize,
+        quota::enforce_quota,
+    },
+};
+
+// Time without new events before pending files are considered fully written
+const SETTLE_TIME: Duration = Duration::from_secs(1);
+
+pub fn watch_folder(
+    sender: Sender<Update>,
+    stop_flag: Arc<AtomicBool>,
+    settings: Settings,
+) -> Result<(), Box<dyn Error>> {
+    let (event_sender, events) = channel();
+    let mut watcher = notify::recommended_watcher(event_sender)?;
+
+    for folder in &settings.input_folders {
+        let mode = match folder.recursive {
+            true => RecursiveMode::Recursive,
+            false => RecursiveMode::NonRecursive,
+        };
+        watcher.watch(&folder.path, mode)?;
+
+        sender
+            .send(Update::Message(format!(
+                "Watching '{}' for new images",
+                folder.path.display()
+            )))
+            .unwrap();
+    }
+
+    let mut pending: Vec<PathBuf> = Vec::new();
+    let mut last_event = Instant::now();
+    // Batches keep counting where the last one stopped
+    let mut next_number = settings.sequence_start;
+
+    while !stop_flag.load(Ordering::Relaxed) {
+        match events.recv_timeout(Duration::from_millis(250)) {
+            Ok(Ok(event)) => {
+                if matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_))
+                ) {
+                    for path in event.paths {
+                        let is_link = fs::symlink_metadata(&path)
+                            .is_ok_and(|metadata| metadata.file_type().is_symlink());
+                        if is_wanted(&path, is_link, &settings)
+                            && !is_moved_original(&path)
+                            && !pending.contains(&path)
+                        {
+                            pending.push(path);
+                        }
+                    }
+                }
+                last_event = Instant::now();
+            }
+            Ok(Err(e)) => eprintln!("Watch error: {}", e),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if !pending.is_empty() && last_event.elapsed() >= SETTLE_TIME {
+            let jobs = pending.drain(..).map(Job::new).collect();
+            next_number = convert_images(
+                sender.clone(),
+                Arc::clone(&stop_flag),
+                jobs,
+                settings.clone(),
+                next_number,
+            );
+
+            if settings.output_quota > 0 {
+                let message = match enforce_quota(&settings) {
+                    Ok(deleted) if deleted.is_empty() => None,
+                    Ok(deleted) => Some(format!(
+                        "Deleted {} old outputs to stay under the quota",
+                        humanize::count(deleted.len())
+                    )),
+                    Err(e) => Some(format!("Failed to enforce output quota: {}", e)),
+                };
+
+                if let Some(message) = message {
+                    sender.send(Update::Message(message)).unwrap();
+                }
+            }
+        }
+    }
+
+    sender
+        .send(Update::Message("Stopped watching".to_string()))
+        .unwrap();
+
+    Ok(())
+}
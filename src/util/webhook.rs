@@ -0,0 +1,58 @@
+use std::{error::Error, path::PathBuf, time::Duration};
+
+use crate::{
+    structs::{error::ConvertError, update::Converted},
+    util::manifest::json_string,
+};
+
+/// What happened in a finished run.
+pub struct RunSummary<'a> {
+    pub success: &'a [(PathBuf, Converted)],
+    pub failed: &'a [(PathBuf, ConvertError)],
+    pub skipped: usize,
+    pub duplicates: usize,
+    pub duration: Duration,
+    pub stopped: bool,
+}
+
+impl RunSummary<'_> {
+    pub fn to_json(&self) -> String {
+        let input_size: u64 = self.success.iter().map(|(_, c)| c.input_size).sum();
+        let output_size: u64 = self.success.iter().map(|(_, c)| c.output_size).sum();
+
+        let failures: Vec<String> = self
+            .failed
+            .iter()
+            .map(|(path, error)| {
+                format!(
+                    "{{\"file\": {}, \"error\": {}}}",
+                    json_string(&path.to_string_lossy()),
+                    json_string(&error.to_string())
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"converted\": {}, \"failed\": {}, \"skipped\": {}, \"duplicates\": {}, \"inputSize\": {}, \"outputSize\": {}, \"seconds\": {:.1}, \"stopped\": {}, \"failures\": [{}]}}",
+            self.success.len(),
+            self.failed.len(),
+            self.skipped,
+            self.duplicates,
+            input_size,
+            output_size,
+            self.duration.as_secs_f64(),
+            self.stopped,
+            failures.join(", ")
+        )
+    }
+}
+
+/// Posts `json` to `url`, for services like ntfy or a chat webhook to pass on.
+pub fn post(url: &str, json: &str) -> Result<(), Box<dyn Error>> {
+    ureq::post(url)
+        .set("Content-Type", "application/json")
+        .timeout(Duration::from_secs(30))
+        .send_string(json)?;
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use image::{metadata::Orientation, DynamicImage, GenericImageView};
+
+/// Edits from a Lightroom or darktable .xmp sidecar that can be applied without a RAW developer.
+pub struct Sidecar {
+    pub orientation: Option<Orientation>,
+    // Left, top, right and bottom edges relative to the unrotated image, 0 to 1
+    pub crop: Option<[f64; 4]>,
+}
+
+/// Reads the sidecar next to `source`, `IMG_1.xmp` as written by Lightroom or `IMG_1.jpg.xmp` as written by darktable.
+pub fn read_sidecar(source: &Path) -> Option<Sidecar> {
+    let xml = sidecar_paths(source)
+        .into_iter()
+        .find_map(|path| fs::read_to_string(path).ok())?;
+
+    let orientation = value(&xml, "tiff:Orientation")
+        .and_then(|value| value.parse().ok())
+        .and_then(Orientation::from_exif);
+
+    let crop =
+        match value(&xml, "crs:HasCrop").is_some_and(|value| value.eq_ignore_ascii_case("true")) {
+            true => [
+                "crs:CropLeft",
+                "crs:CropTop",
+                "crs:CropRight",
+                "crs:CropBottom",
+            ]
+            .map(|name| value(&xml, name).and_then(|value| value.parse::<f64>().ok()))
+            .into_iter()
+            .collect::<Option<Vec<f64>>>()
+            .and_then(|edges| edges.try_into().ok()),
+            false => None,
+        };
+
+    Some(Sidecar { orientation, crop })
+}
+
+/// Crops and rotates `img` like the editor that wrote the sidecar shows it.
+pub fn apply(mut img: DynamicImage, sidecar: &Sidecar) -> DynamicImage {
+    if let Some([left, top, right, bottom]) = sidecar.crop {
+        let (width, height) = img.dimensions();
+        let x = (left.clamp(0.0, 1.0) * width as f64).round() as u32;
+        let y = (top.clamp(0.0, 1.0) * height as f64).round() as u32;
+        let crop_width = (right.clamp(0.0, 1.0) * width as f64).round() as u32;
+        let crop_height = (bottom.clamp(0.0, 1.0) * height as f64).round() as u32;
+
+        if crop_width > x && crop_height > y {
+            img = img.crop_imm(x, y, crop_width - x, crop_height - y);
+        }
+    }
+
+    if let Some(orientation) = sidecar.orientation {
+        img.apply_orientation(orientation);
+    }
+
+    img
+}
+
+fn sidecar_paths(source: &Path) -> [PathBuf; 2] {
+    let mut with_extension = source.as_os_str().to_owned();
+    with_extension.push(".xmp");
+
+    [source.with_extension("xmp"), PathBuf::from(with_extension)]
+}
+
+/// Value of a property, written either as `name="value"` or as `<name>value</name>`.
+fn value<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let attribute = format!("{}=\"", name);
+    if let Some(start) = xml.find(&attribute).map(|i| i + attribute.len()) {
+        let end = xml[start..].find('"')?;
+        return Some(xml[start..start + end].trim());
+    }
+
+    let element = format!("<{}>", name);
+    let start = xml.find(&element)? + element.len();
+    let end = xml[start..].find('<')?;
+    Some(xml[start..start + end].trim())
+}